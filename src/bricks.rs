@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+
+use crate::core::serde_derive::{Deserialize, Serialize};
+use crate::image::{Bitmap, PixelRGBA};
+use crate::math::clampf;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Constants
+
+/// Distance between neighbouring studs
+pub const STUD_PITCH_MM: f32 = 8.0;
+/// Longest part we use for runs of studs of the same color, i.e. 1x4
+pub const PART_LENGTH_MAX: usize = 4;
+/// Our background tiles always contain 8x8 studs
+const BASEPLATE_TILE_STUD_COUNT: i32 = 8;
+/// Stud radius relative to the tile size
+const STUD_RADIUS: f32 = 0.3;
+
+const COLOR_BASEPLATE: PixelRGBA = PixelRGBA::new(160, 165, 169, 255);
+const COLOR_BASEPLATE_STUD_SHADOW: PixelRGBA = PixelRGBA::new(120, 124, 127, 255);
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Brick setup
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BrickPart {
+    /// Parts with studs on top
+    #[default]
+    Plate,
+    /// Smooth parts without studs
+    Tile,
+}
+
+impl BrickPart {
+    pub fn name_plural(self) -> &'static str {
+        match self {
+            BrickPart::Plate => "plates",
+            BrickPart::Tile => "tiles",
+        }
+    }
+}
+
+/// Determines the parts and baseplates of a brick mosaic
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BrickSetup {
+    pub part: BrickPart,
+    /// Number of studs along each side of a square baseplate, either 16 or 32. Every baseplate
+    /// gets its own chart page.
+    pub baseplate_size: i32,
+    /// Replaces horizontal runs of the same color with 1x2, 1x3 and 1x4 parts to need fewer parts.
+    /// Runs never cross baseplates so that every baseplate can be built on its own.
+    pub optimize_runs: bool,
+    /// Palette file with one `NAME,R,G,B` part color per line that replaces the bundled palette.
+    /// See `load_palette_file`.
+    pub palette_filepath: Option<String>,
+}
+
+impl Default for BrickSetup {
+    fn default() -> BrickSetup {
+        BrickSetup {
+            part: BrickPart::Plate,
+            baseplate_size: 32,
+            optimize_runs: false,
+            palette_filepath: None,
+        }
+    }
+}
+
+impl BrickSetup {
+    pub const PART_NAMES: [&'static str; 2] = ["plate", "tile"];
+    pub const BASEPLATE_SIZES: [i32; 2] = [16, 32];
+
+    pub fn part_from_name(name: &str) -> Result<BrickPart, String> {
+        match name {
+            "plate" => Ok(BrickPart::Plate),
+            "tile" => Ok(BrickPart::Tile),
+            _ => Err(format!(
+                "Unknown brick part '{}' - valid parts are: {}",
+                name,
+                BrickSetup::PART_NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Brick palette
+
+/// Returns the solid colors that 1x1 plates and tiles are commonly available in.
+///
+/// NOTE: These are approximations of the official colors. Parts of the same color can look
+///       different depending on their age and the light, so for exact matching load your own
+///       palette file.
+pub fn make_palette() -> HashMap<PixelRGBA, String> {
+    let colors: &[((u8, u8, u8), &str)] = &[
+        ((5, 19, 29), "Black"),
+        ((255, 255, 255), "White"),
+        ((160, 165, 169), "Light Bluish Gray"),
+        ((108, 110, 104), "Dark Bluish Gray"),
+        ((201, 26, 9), "Red"),
+        ((114, 14, 15), "Dark Red"),
+        ((0, 85, 191), "Blue"),
+        ((10, 52, 99), "Dark Blue"),
+        ((90, 147, 219), "Medium Blue"),
+        ((54, 174, 191), "Medium Azure"),
+        ((7, 139, 201), "Dark Azure"),
+        ((159, 195, 233), "Bright Light Blue"),
+        ((96, 116, 161), "Sand Blue"),
+        ((35, 120, 65), "Green"),
+        ((24, 70, 50), "Dark Green"),
+        ((75, 159, 74), "Bright Green"),
+        ((187, 233, 11), "Lime"),
+        ((155, 154, 90), "Olive Green"),
+        ((160, 188, 172), "Sand Green"),
+        ((223, 238, 165), "Yellowish Green"),
+        ((0, 143, 155), "Dark Turquoise"),
+        ((173, 195, 192), "Light Aqua"),
+        ((242, 205, 55), "Yellow"),
+        ((255, 240, 58), "Bright Light Yellow"),
+        ((254, 138, 24), "Orange"),
+        ((248, 187, 61), "Bright Light Orange"),
+        ((169, 85, 0), "Dark Orange"),
+        ((228, 205, 158), "Tan"),
+        ((149, 138, 115), "Dark Tan"),
+        ((88, 42, 18), "Reddish Brown"),
+        ((53, 33, 0), "Dark Brown"),
+        ((170, 125, 85), "Medium Nougat"),
+        ((208, 145, 104), "Nougat"),
+        ((252, 195, 158), "Light Nougat"),
+        ((228, 173, 200), "Bright Pink"),
+        ((200, 112, 160), "Dark Pink"),
+        ((255, 105, 143), "Coral"),
+        ((146, 57, 120), "Magenta"),
+        ((172, 120, 186), "Medium Lavender"),
+        ((225, 213, 237), "Lavender"),
+        ((63, 54, 145), "Dark Purple"),
+    ];
+
+    colors
+        .iter()
+        .map(|&((r, g, b), name)| (PixelRGBA::new(r, g, b, 255), name.to_owned()))
+        .collect()
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Part counts
+
+/// Number of parts of a single color where index `i` counts the 1x(i+1) parts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PartCounts(pub [usize; PART_LENGTH_MAX]);
+
+impl PartCounts {
+    pub fn total(&self) -> usize {
+        self.0.iter().sum()
+    }
+
+    /// Lists the used part sizes, i.e. `2x 1x4, 1x 1x1`
+    pub fn to_text(&self) -> String {
+        let parts: Vec<String> = self
+            .0
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, count)| **count > 0)
+            .map(|(length_index, count)| format!("{}x 1x{}", count, length_index + 1))
+            .collect();
+        parts.join(", ")
+    }
+}
+
+/// Counts the parts of every color of the given image. With `optimize_runs` horizontal runs of the
+/// same color are split into as few parts as possible, otherwise every stud is a 1x1 part.
+/// Transparent pixels are skipped.
+pub fn count_parts(
+    image: &Bitmap,
+    baseplate_size: i32,
+    optimize_runs: bool,
+) -> HashMap<PixelRGBA, PartCounts> {
+    let mut result: HashMap<PixelRGBA, PartCounts> = HashMap::new();
+    for y in 0..image.height {
+        let mut x = 0;
+        while x < image.width {
+            let color = image.get(x, y);
+            let baseplate_right = (x / baseplate_size + 1) * baseplate_size;
+            let run_end_max = i32::min(image.width, baseplate_right);
+            let mut run_end = x + 1;
+            if optimize_runs {
+                while run_end < run_end_max && image.get(run_end, y) == color {
+                    run_end += 1;
+                }
+            }
+
+            if color.a != 0 {
+                // NOTE: Using the longest parts first gives the fewest parts
+                let counts = result.entry(color).or_default();
+                let run_length = (run_end - x) as usize;
+                counts.0[PART_LENGTH_MAX - 1] += run_length / PART_LENGTH_MAX;
+                let remainder = run_length % PART_LENGTH_MAX;
+                if remainder > 0 {
+                    counts.0[remainder - 1] += 1;
+                }
+            }
+            x = run_end;
+        }
+    }
+    result
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Preview tiles
+
+/// Creates a white part seen from above and its luminance for the stitch preview. Both tiles have
+/// the given size and cover it completely. Plates get a stud in their center.
+pub fn create_part_tiles(part: BrickPart, tile_size: i32) -> (Bitmap, Bitmap) {
+    let part_tile = Bitmap::new_filled(tile_size as u32, tile_size as u32, PixelRGBA::white());
+    let mut luminance = part_tile.clone();
+
+    let center = tile_size as f32 / 2.0;
+    let stud_radius = STUD_RADIUS * tile_size as f32;
+    let light_direction = std::f32::consts::FRAC_1_SQRT_2;
+    for y in 0..tile_size {
+        for x in 0..tile_size {
+            let offset_x = x as f32 + 0.5 - center;
+            let offset_y = y as f32 + 0.5 - center;
+
+            // NOTE: Neighbouring parts are separated by a thin dark seam
+            let edge_distance = center - f32::max(offset_x.abs(), offset_y.abs());
+            let mut brightness = if edge_distance < 1.0 { 0.55 } else { 0.8 };
+
+            if part == BrickPart::Plate {
+                let radius = f32::sqrt(offset_x * offset_x + offset_y * offset_y);
+                let stud_edge = stud_radius - radius;
+                if stud_edge > 0.0 {
+                    // NOTE: The stud side is lit from the top left, its top is flat with a
+                    //       highlight ring on the lit side
+                    let facing_light = -(offset_x + offset_y) * light_direction / radius.max(0.001);
+                    brightness = if stud_edge < 0.18 * stud_radius {
+                        0.8 + 0.2 * facing_light
+                    } else {
+                        0.85
+                    };
+                } else if stud_edge > -0.15 * stud_radius && offset_x + offset_y > 0.0 {
+                    // Shadow of the stud towards the bottom right
+                    brightness = 0.6;
+                }
+            }
+
+            let grey = (255.0 * clampf(brightness, 0.0, 1.0)).round() as u8;
+            luminance.set(x, y, PixelRGBA::new(grey, grey, grey, 255));
+        }
+    }
+
+    (part_tile, luminance)
+}
+
+/// Creates a background tile of 8x8 empty studs on a grey baseplate. The tile has the same size
+/// as the bundled fabric tile.
+pub fn create_baseplate_tile_8x8(tile_size: i32) -> Bitmap {
+    let mut tile = Bitmap::new_filled(tile_size as u32, tile_size as u32, COLOR_BASEPLATE);
+    let cell_size = tile_size / BASEPLATE_TILE_STUD_COUNT;
+    let stud_radius = STUD_RADIUS * cell_size as f32;
+    for y in 0..tile_size {
+        for x in 0..tile_size {
+            let offset_x = (x % cell_size) as f32 + 0.5 - cell_size as f32 / 2.0;
+            let offset_y = (y % cell_size) as f32 + 0.5 - cell_size as f32 / 2.0;
+            let radius = f32::sqrt(offset_x * offset_x + offset_y * offset_y);
+            if radius > stud_radius && radius < stud_radius + 1.5 && offset_x + offset_y > 0.0 {
+                tile.set(x, y, COLOR_BASEPLATE_STUD_SHADOW);
+            }
+        }
+    }
+    tile
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_runs_into_few_parts_within_baseplates() {
+        let red = PixelRGBA::new(201, 26, 9, 255);
+        let blue = PixelRGBA::new(0, 85, 191, 255);
+        let mut image = Bitmap::new_filled(12, 1, red);
+        image.set(11, 0, blue);
+
+        // NOTE: The red run of 11 studs is cut into 8 and 3 studs by the baseplate border
+        let counts = count_parts(&image, 8, true);
+        assert_eq!(counts[&red], PartCounts([0, 0, 1, 2]));
+        assert_eq!(counts[&blue], PartCounts([1, 0, 0, 0]));
+        assert_eq!(counts[&red].to_text(), "2x 1x4, 1x 1x3");
+
+        let counts = count_parts(&image, 8, false);
+        assert_eq!(counts[&red].total(), 11);
+    }
+}
@@ -0,0 +1,193 @@
+use std::io::Write;
+
+use crate::core::serde_derive::Serialize;
+use crate::stitch_images::{PatternType, ThreadUsage};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Zip archives
+
+/// Packs the given `(filename, content)` pairs into an in-memory zip archive
+pub fn create_zip_archive(files: &[(&str, &[u8])]) -> Result<Vec<u8>, String> {
+    let mut archive = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let file_options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    for (filename, content) in files {
+        archive
+            .start_file(*filename, file_options)
+            .and_then(|_| {
+                archive
+                    .write_all(content)
+                    .map_err(zip::result::ZipError::from)
+            })
+            .map_err(|error| format!("Could not add '{}' to zip archive: {}", filename, error))?;
+    }
+
+    let archive = archive
+        .finish()
+        .map_err(|error| format!("Could not finish zip archive: {}", error))?;
+    Ok(archive.into_inner())
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Pattern bundles
+
+/// Lists the files of a pattern bundle and what they are for
+#[derive(Debug, Clone, Serialize)]
+pub struct Manifest {
+    pub image: String,
+    pub width: i32,
+    pub height: i32,
+    pub threads: Vec<ThreadUsage>,
+    pub files: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ManifestEntry {
+    pub filename: String,
+    /// One of `legend`, `complete_chart`, `chart_segment`, `preview`, `real_scale_preview`,
+    /// `mockup`, `cover_page`, `comparison`, `color_error_stats`, `project`, `palette` or
+    /// `instructions`
+    pub role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern_type: Option<String>,
+    /// Whether the chart labels its stitches relative to the center of the image
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub centered: bool,
+    /// Starts at 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment: Option<usize>,
+}
+
+impl ManifestEntry {
+    pub fn new(filename: String, role: &str) -> ManifestEntry {
+        ManifestEntry {
+            filename,
+            role: role.to_owned(),
+            pattern_type: None,
+            centered: false,
+            segment: None,
+        }
+    }
+
+    /// Entry of a chart of the complete pattern or of a single page if `segment` is given
+    pub fn new_chart(
+        filename: String,
+        pattern_type: PatternType,
+        centered: bool,
+        segment: Option<usize>,
+    ) -> ManifestEntry {
+        ManifestEntry {
+            filename,
+            role: if segment.is_some() {
+                "chart_segment"
+            } else {
+                "complete_chart"
+            }
+            .to_owned(),
+            pattern_type: Some(pattern_type.name().to_owned()),
+            centered,
+            segment,
+        }
+    }
+}
+
+/// A rendered output file of a pattern together with what it is for
+#[derive(Debug, Clone)]
+pub struct OutputFile {
+    pub entry: ManifestEntry,
+    pub data: Vec<u8>,
+}
+
+impl OutputFile {
+    pub fn new(filename: String, role: &str, data: Vec<u8>) -> OutputFile {
+        OutputFile {
+            entry: ManifestEntry::new(filename, role),
+            data,
+        }
+    }
+
+    pub fn filename(&self) -> &str {
+        &self.entry.filename
+    }
+}
+
+/// Packs the given output files of a pattern together with a `manifest.json` into a zip archive.
+/// The files of the manifest are filled in here.
+pub fn create_pattern_bundle(
+    mut manifest: Manifest,
+    files: &[OutputFile],
+) -> Result<Vec<u8>, String> {
+    manifest.files = files.iter().map(|file| file.entry.clone()).collect();
+    let manifest_json = serde_json::to_string_pretty(&manifest)
+        .map_err(|error| format!("Could not serialize manifest: {}", error))?;
+
+    let mut archive_files: Vec<(&str, &[u8])> = files
+        .iter()
+        .map(|file| (file.filename(), file.data.as_slice()))
+        .collect();
+    archive_files.push(("manifest.json", manifest_json.as_bytes()));
+    create_zip_archive(&archive_files)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn bundles_output_files_with_manifest() {
+        let manifest = Manifest {
+            image: "cat_face.png".to_owned(),
+            width: 2,
+            height: 1,
+            threads: Vec::new(),
+            files: Vec::new(),
+        };
+        let files = [
+            OutputFile::new(
+                "cat_face_legend.png".to_owned(),
+                "legend",
+                b"legend".to_vec(),
+            ),
+            OutputFile {
+                entry: ManifestEntry::new_chart(
+                    "cat_face_cross_stitch_centered_segment_2.png".to_owned(),
+                    PatternType::BlackAndWhite,
+                    true,
+                    Some(2),
+                ),
+                data: b"chart".to_vec(),
+            },
+        ];
+
+        let bundle = create_pattern_bundle(manifest, &files).unwrap();
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bundle)).unwrap();
+        let mut read_file = |filename: &str| {
+            let mut content = String::new();
+            archive
+                .by_name(filename)
+                .unwrap()
+                .read_to_string(&mut content)
+                .unwrap();
+            content
+        };
+        assert_eq!(read_file("cat_face_legend.png"), "legend");
+        assert_eq!(
+            read_file("cat_face_cross_stitch_centered_segment_2.png"),
+            "chart"
+        );
+
+        let manifest: serde_json::Value =
+            serde_json::from_str(&read_file("manifest.json")).unwrap();
+        assert_eq!(manifest["files"][0]["role"], "legend");
+        assert!(manifest["files"][0].get("centered").is_none());
+        assert_eq!(manifest["files"][1]["role"], "chart_segment");
+        assert_eq!(manifest["files"][1]["pattern_type"], "black_and_white");
+        assert_eq!(manifest["files"][1]["centered"], true);
+        assert_eq!(manifest["files"][1]["segment"], 2);
+    }
+}
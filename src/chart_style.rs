@@ -0,0 +1,142 @@
+use crate::core::serde_derive::{Deserialize, Serialize};
+use crate::image::PixelRGBA;
+use crate::math::Vec2i;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Chart style
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LabelFont {
+    Regular,
+    Big,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GridLabels {
+    /// Stitch coordinates from the top left or from the image center for centered charts
+    #[default]
+    Coordinates,
+    /// Stitches and rows counted from the bottom right in the order they are knitted. Row numbers
+    /// are placed on the side where the row starts.
+    KnittingRows,
+}
+
+/// Determines how the grid, cells and labels of a chart are drawn
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChartStyle {
+    /// Width of a single stitch cell in pixels
+    pub cell_size: i32,
+    /// Height of a stitch cell relative to its width. Knitting and crochet stitches are wider
+    /// than tall.
+    pub cell_aspect: f32,
+    pub grid_color_thin: PixelRGBA,
+    pub grid_color_thick: PixelRGBA,
+    pub grid_thickness_thin: i32,
+    pub grid_thickness_thick: i32,
+    /// Number of stitches between two thick grid lines
+    pub major_grid_interval: i32,
+    pub label_font: LabelFont,
+    pub label_font_scale: i32,
+    pub grid_labels: GridLabels,
+}
+
+impl Default for ChartStyle {
+    fn default() -> ChartStyle {
+        ChartStyle::new_print()
+    }
+}
+
+impl ChartStyle {
+    pub const PRESET_NAMES: [&'static str; 4] = ["print", "high-contrast", "ink-saving", "screen"];
+
+    pub fn new_print() -> ChartStyle {
+        ChartStyle {
+            cell_size: 16,
+            cell_aspect: 1.0,
+            grid_color_thin: PixelRGBA::new(128, 128, 128, 255),
+            grid_color_thick: PixelRGBA::new(64, 64, 64, 255),
+            grid_thickness_thin: 1,
+            grid_thickness_thick: 2,
+            major_grid_interval: 10,
+            label_font: LabelFont::Regular,
+            label_font_scale: 1,
+            grid_labels: GridLabels::Coordinates,
+        }
+    }
+
+    /// Doubled cells and labels with black grid lines for low vision
+    pub fn new_high_contrast() -> ChartStyle {
+        ChartStyle {
+            cell_size: 32,
+            cell_aspect: 1.0,
+            grid_color_thin: PixelRGBA::black(),
+            grid_color_thick: PixelRGBA::black(),
+            grid_thickness_thin: 1,
+            grid_thickness_thick: 4,
+            major_grid_interval: 10,
+            label_font: LabelFont::Big,
+            label_font_scale: 1,
+            grid_labels: GridLabels::Coordinates,
+        }
+    }
+
+    /// Light grid lines to use as little ink as possible
+    pub fn new_ink_saving() -> ChartStyle {
+        ChartStyle {
+            cell_size: 16,
+            cell_aspect: 1.0,
+            grid_color_thin: PixelRGBA::new(200, 200, 200, 255),
+            grid_color_thick: PixelRGBA::new(128, 128, 128, 255),
+            grid_thickness_thin: 1,
+            grid_thickness_thick: 2,
+            major_grid_interval: 10,
+            label_font: LabelFont::Regular,
+            label_font_scale: 1,
+            grid_labels: GridLabels::Coordinates,
+        }
+    }
+
+    /// Subtle thin lines and tinted thick lines that stay visible when zoomed out on a monitor
+    pub fn new_screen() -> ChartStyle {
+        ChartStyle {
+            cell_size: 16,
+            cell_aspect: 1.0,
+            grid_color_thin: PixelRGBA::new(176, 176, 192, 255),
+            grid_color_thick: PixelRGBA::new(32, 64, 128, 255),
+            grid_thickness_thin: 1,
+            grid_thickness_thick: 2,
+            major_grid_interval: 10,
+            label_font: LabelFont::Regular,
+            label_font_scale: 1,
+            grid_labels: GridLabels::Coordinates,
+        }
+    }
+
+    pub fn cell_height(&self) -> i32 {
+        i32::max(1, (self.cell_size as f32 * self.cell_aspect).round() as i32)
+    }
+
+    /// Returns the stitch that gets the logical coordinate (0, 0) in a chart of an image with the
+    /// given dimensions, ignoring centered charts
+    pub fn label_origin(&self, image_dim: Vec2i) -> Vec2i {
+        match self.grid_labels {
+            GridLabels::Coordinates => Vec2i::zero(),
+            GridLabels::KnittingRows => image_dim,
+        }
+    }
+
+    pub fn from_preset_name(preset_name: &str) -> Result<ChartStyle, String> {
+        match preset_name {
+            "print" => Ok(ChartStyle::new_print()),
+            "high-contrast" => Ok(ChartStyle::new_high_contrast()),
+            "ink-saving" => Ok(ChartStyle::new_ink_saving()),
+            "screen" => Ok(ChartStyle::new_screen()),
+            _ => Err(format!(
+                "Unknown chart style '{}' - valid styles are: {}",
+                preset_name,
+                ChartStyle::PRESET_NAMES.join(", ")
+            )),
+        }
+    }
+}
@@ -1,7 +1,74 @@
 use std::collections::HashMap;
 
+use color_art::{delta_e, distance, distance_with, Color as ArtColor, ColorSpace};
+
+use crate::core::platform::read_file_whole;
+use crate::core::serde_derive::{Deserialize, Serialize};
 use crate::image::PixelRGBA;
 
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Color metric
+
+/// Determines how the difference between an image color and a thread color is measured
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorMetric {
+    /// Euclidean distance of the RGB values
+    #[default]
+    Rgb,
+    /// Euclidean distance in Lab color space, also known as ΔE 1976
+    Cie76,
+    /// Perceptual color difference ΔE 2000
+    Ciede2000,
+}
+
+impl ColorMetric {
+    pub const NAMES: [&'static str; 3] = ["rgb", "cie76", "ciede2000"];
+
+    pub fn from_name(name: &str) -> Result<ColorMetric, String> {
+        match name {
+            "rgb" => Ok(ColorMetric::Rgb),
+            "cie76" => Ok(ColorMetric::Cie76),
+            "ciede2000" => Ok(ColorMetric::Ciede2000),
+            _ => Err(format!(
+                "Unknown color metric '{}' - valid metrics are: {}",
+                name,
+                ColorMetric::NAMES.join(", ")
+            )),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorMetric::Rgb => "rgb",
+            ColorMetric::Cie76 => "cie76",
+            ColorMetric::Ciede2000 => "ciede2000",
+        }
+    }
+
+    /// Errors at or above this value are considered clearly visible. We use this to normalize
+    /// error visualizations.
+    pub fn clearly_visible_error(self) -> f64 {
+        match self {
+            ColorMetric::Rgb => 100.0,
+            ColorMetric::Cie76 | ColorMetric::Ciede2000 => 20.0,
+        }
+    }
+
+    /// Returns the difference between two colors ignoring their alpha
+    pub fn distance(self, color_a: PixelRGBA, color_b: PixelRGBA) -> f64 {
+        let color_a = ArtColor::from_rgb(color_a.r, color_a.g, color_a.b).unwrap_or_default();
+        let color_b = ArtColor::from_rgb(color_b.r, color_b.g, color_b.b).unwrap_or_default();
+        match self {
+            ColorMetric::Rgb => distance(&color_a, &color_b),
+            ColorMetric::Cie76 => distance_with(&color_a, &color_b, ColorSpace::Lab),
+            ColorMetric::Ciede2000 => delta_e(&color_a, &color_b),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Thread colors
+
 pub fn make() -> HashMap<PixelRGBA, String> {
     HashMap::from([
         ((255,226,226), ("3713")),
@@ -459,6 +526,53 @@ pub fn make() -> HashMap<PixelRGBA, String> {
         ((66,66,66), ("3799")),
         ((0,0,0), ("310")),
     ]).into_iter()
-    .map(|((r, g, b), stitch_id)| (PixelRGBA {r: r, g: g, b: b, a: 255}, String::from(stitch_id)))
+    .map(|((r, g, b), stitch_id)| (PixelRGBA {r, g, b, a: 255}, String::from(stitch_id)))
     .collect()
-}
\ No newline at end of file
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Palette files
+
+/// Loads a palette from a text file with one `NAME,R,G,B` color per line, where the name is what
+/// the color is sold as, i.e. a DMC number. Empty lines and lines starting with `#` are ignored.
+pub fn load_palette_file(palette_filepath: &str) -> Result<HashMap<PixelRGBA, String>, String> {
+    let content = read_file_whole(palette_filepath)?;
+    let content = String::from_utf8(content)
+        .map_err(|_| format!("Palette '{}' is not valid UTF-8", palette_filepath))?;
+
+    let mut palette = HashMap::new();
+    for (line_index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid_line = || {
+            format!(
+                "Line {} of palette '{}' must look like 'NAME,R,G,B' - got '{}'",
+                line_index + 1,
+                palette_filepath,
+                line
+            )
+        };
+        let parts: Vec<&str> = line.split(',').map(|part| part.trim()).collect();
+        let [name, r, g, b] = parts[..] else {
+            return Err(invalid_line());
+        };
+        if name.is_empty() {
+            return Err(invalid_line());
+        }
+        let channel = |value: &str| value.parse::<u8>().map_err(|_| invalid_line());
+        palette.insert(
+            PixelRGBA::new(channel(r)?, channel(g)?, channel(b)?, 255),
+            name.to_owned(),
+        );
+    }
+
+    if palette.is_empty() {
+        return Err(format!(
+            "Palette '{}' does not contain any colors",
+            palette_filepath
+        ));
+    }
+    Ok(palette)
+}
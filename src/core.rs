@@ -169,7 +169,7 @@ pub struct Deserializer<'a> {
 }
 
 impl Deserializer<'_> {
-    pub fn new(data: &[u8]) -> Deserializer {
+    pub fn new(data: &[u8]) -> Deserializer<'_> {
         Deserializer { data }
     }
 
@@ -227,7 +227,7 @@ impl Deserializer<'_> {
 
 /// Makes a panic info a little easier to read by splitting it into the message and location
 pub fn panic_message_split_to_message_and_location(
-    panic_info: &std::panic::PanicInfo<'_>,
+    panic_info: &std::panic::PanicHookInfo<'_>,
 ) -> (String, String) {
     let panic_info_content = format!("{}", panic_info).replace("panicked at '", "");
     if let Some(split_pos) = panic_info_content.rfind("', ") {
@@ -284,7 +284,7 @@ pub fn transmute_slice_to_byte_slice<S>(from: &[S]) -> &[u8] {
     unsafe {
         std::slice::from_raw_parts(
             from.as_ptr() as *const u8,
-            from.len() * std::mem::size_of::<S>(),
+            std::mem::size_of_val(from),
         )
     }
 }
@@ -293,7 +293,7 @@ pub fn transmute_slice_to_byte_slice_mut<S>(from: &mut [S]) -> &mut [u8] {
     unsafe {
         std::slice::from_raw_parts_mut(
             from.as_mut_ptr() as *mut u8,
-            from.len() * std::mem::size_of::<S>(),
+            std::mem::size_of_val(from),
         )
     }
 }
@@ -302,7 +302,7 @@ pub fn transmute_slices<S, D>(from: &[S]) -> &[D] {
     unsafe {
         std::slice::from_raw_parts(
             from.as_ptr() as *const D,
-            from.len() * std::mem::size_of::<S>() / std::mem::size_of::<D>(),
+            std::mem::size_of_val(from) / std::mem::size_of::<D>(),
         )
     }
 }
@@ -311,7 +311,7 @@ pub fn transmute_slices_mut<S, D>(from: &mut [S]) -> &mut [D] {
     unsafe {
         std::slice::from_raw_parts_mut(
             from.as_mut_ptr() as *mut D,
-            from.len() * std::mem::size_of::<S>() / std::mem::size_of::<D>(),
+            std::mem::size_of_val(from) / std::mem::size_of::<D>(),
         )
     }
 }
@@ -360,7 +360,7 @@ pub fn hash_string_64(input: &str) -> u64 {
 // Finding all common divisors of a given list (useful for finding scaled down display resolutions)
 
 pub fn get_all_divisors(value: u32) -> Vec<u32> {
-    (1..(value / 2)).filter(|x| value % x == 0).collect()
+    (1..(value / 2)).filter(|x| value.is_multiple_of(*x)).collect()
 }
 
 pub fn common_divisors(values: &[u32]) -> Vec<u32> {
@@ -412,38 +412,38 @@ pub fn common_resolutions(resolutions: &[(u32, u32)]) -> Vec<(u32, u32)> {
 pub fn min_in_list_f32(values: &[f32]) -> f32 {
     values
         .iter()
-        .fold(std::f32::MAX, |acc, val| f32::min(acc, *val))
+        .fold(f32::MAX, |acc, val| f32::min(acc, *val))
 }
 
 pub fn max_in_list_f32(values: &[f32]) -> f32 {
     values
         .iter()
-        .fold(std::f32::MIN, |acc, val| f32::max(acc, *val))
+        .fold(f32::MIN, |acc, val| f32::max(acc, *val))
 }
 
 pub fn min_in_list_f64(values: &[f64]) -> f64 {
     values
         .iter()
-        .fold(std::f64::MAX, |acc, val| f64::min(acc, *val))
+        .fold(f64::MAX, |acc, val| f64::min(acc, *val))
 }
 
 pub fn max_in_list_f64(values: &[f64]) -> f64 {
     values
         .iter()
-        .fold(std::f64::MIN, |acc, val| f64::max(acc, *val))
+        .fold(f64::MIN, |acc, val| f64::max(acc, *val))
 }
 
 pub fn sort_list_f32(values: &mut [f32]) {
     values.sort_by(|a, b| {
         a.partial_cmp(b)
-            .unwrap_or_else(|| std::cmp::Ordering::Equal)
+            .unwrap_or(std::cmp::Ordering::Equal)
     })
 }
 
 pub fn sort_list_f64(values: &mut [f64]) {
     values.sort_by(|a, b| {
         a.partial_cmp(b)
-            .unwrap_or_else(|| std::cmp::Ordering::Equal)
+            .unwrap_or(std::cmp::Ordering::Equal)
     })
 }
 
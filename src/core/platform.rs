@@ -6,34 +6,24 @@ pub use easy_process;
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 // Debugging and performance
 
-static mut TIMER_STARTING_INSTANT: Option<std::time::Instant> = None;
+static TIMER_STARTING_INSTANT: std::sync::OnceLock<std::time::Instant> =
+    std::sync::OnceLock::new();
 pub fn timer_initialize() {
-    unsafe {
-        TIMER_STARTING_INSTANT = Some(std::time::Instant::now());
-    }
+    TIMER_STARTING_INSTANT.get_or_init(std::time::Instant::now);
 }
+/// NOTE: If the timer was not initialized yet, this initializes it
 pub fn timer_current_time_seconds() -> f64 {
-    unsafe {
-        std::time::Instant::now()
-            .duration_since(
-                TIMER_STARTING_INSTANT.expect("Timer needs to be initialized before use"),
-            )
-            .as_secs_f64()
-    }
+    std::time::Instant::now()
+        .duration_since(*TIMER_STARTING_INSTANT.get_or_init(std::time::Instant::now))
+        .as_secs_f64()
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 // Logger
 
-pub fn init_logging(logfile_path: &str, loglevel: log::Level) -> Result<(), String> {
-    let logfile = std::fs::OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(logfile_path)
-        .map_err(|error| format!("Could not create logfile at '{}' : {}", logfile_path, error))?;
-
-    fern::Dispatch::new()
+/// Logs to stdout and additionally into the given logfile if any
+pub fn init_logging(logfile_path: Option<&str>, loglevel: log::Level) -> Result<(), String> {
+    let mut dispatch = fern::Dispatch::new()
         .format(|out, message, record| {
             out.finish(format_args!(
                 "{}::{}: {}",
@@ -49,8 +39,21 @@ pub fn init_logging(logfile_path: &str, loglevel: log::Level) -> Result<(), Stri
         .level_for("rusty_xinput", log::LevelFilter::Info)
         .level_for("gilrs::gamepad", log::LevelFilter::Info)
         .level_for("gilrs::ff::server", log::LevelFilter::Info)
-        .chain(std::io::stdout())
-        .chain(logfile)
+        .chain(std::io::stdout());
+
+    if let Some(logfile_path) = logfile_path {
+        let logfile = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(logfile_path)
+            .map_err(|error| {
+                format!("Could not create logfile at '{}' : {}", logfile_path, error)
+            })?;
+        dispatch = dispatch.chain(logfile);
+    }
+
+    dispatch
         .apply()
         .map_err(|error| format!("Could initialize logger: {}", error))?;
 
@@ -140,7 +143,7 @@ pub fn path_is_file(path: &str) -> bool {
 }
 
 pub fn path_is_directory(path: &str) -> bool {
-    Path::new(path).is_file()
+    Path::new(path).is_dir()
 }
 
 pub fn path_canonicalize(path: &str) -> Result<String, String> {
@@ -172,13 +175,9 @@ pub fn path_join(first: &str, second: &str) -> String {
 
 /// NOTE: Result contains Unix-style file seperators only
 pub fn path_with_extension(filepath: &str, new_extension: &str) -> String {
-    let extension = if new_extension.starts_with('.') {
-        // NOTE: The `.with_extension(..)` method always adds an extra '.' even if we already
-        //       provide on. So we remove ours if needed.
-        &new_extension[1..]
-    } else {
-        new_extension
-    };
+    // NOTE: The `.with_extension(..)` method always adds an extra '.' even if we already
+    //       provide on. So we remove ours if needed.
+    let extension = new_extension.strip_prefix('.').unwrap_or(new_extension);
     Path::new(filepath)
         .with_extension(extension)
         .to_string_owned_or_panic()
@@ -285,7 +284,7 @@ pub fn path_recreate_directory_looped(path: &str) {
             }
         }
     } else {
-        std::fs::create_dir_all(path).expect(&format!("Unable to create '{}' dir", path));
+        std::fs::create_dir_all(path).unwrap_or_else(|_| panic!("Unable to create '{}' dir", path));
     }
 }
 
@@ -351,7 +350,7 @@ pub fn collect_directories(root_folder: &str) -> Vec<String> {
 
 /// NOTE: Result is prefixed by the given `root_folder` and contains Unix-style file seperators only
 pub fn collect_directories_recursive(root_folder: &str) -> Vec<String> {
-    collect_directories_with_depth(root_folder, std::usize::MAX)
+    collect_directories_with_depth(root_folder, usize::MAX)
 }
 
 /// NOTE: Result is prefixed by the given `root_folder` and contains Unix-style file seperators only
@@ -372,7 +371,7 @@ pub fn collect_files(root_folder: &str) -> Vec<String> {
 
 /// NOTE: Result is prefixed by the given `root_folder` and contains Unix-style file seperators only
 pub fn collect_files_recursive(root_folder: &str) -> Vec<String> {
-    collect_files_with_depth(root_folder, std::usize::MAX)
+    collect_files_with_depth(root_folder, usize::MAX)
 }
 
 /// NOTE: Result is prefixed by the given `root_folder` and contains Unix-style file seperators only
@@ -388,20 +387,25 @@ pub fn collect_files_with_depth(root_folder: &str, max_depth: usize) -> Vec<Stri
 
 /// Glob patterns: https://en.wikipedia.org/wiki/Glob_%28programming%29
 /// NOTE: Result is prefixed by the given `root_folder` and contains Unix-style file seperators only
-pub fn collect_files_by_glob_pattern(root_folder: &str, glob_pattern: &str) -> Vec<String> {
-    let pattern = path_join(root_folder, glob_pattern);
-    glob::glob(&pattern)
-        .unwrap()
-        .into_iter()
+/// NOTE: Special characters like `[` in the `root_folder` are matched literally
+pub fn collect_files_by_glob_pattern(
+    root_folder: &str,
+    glob_pattern: &str,
+) -> Result<Vec<String>, String> {
+    let pattern = path_join(&glob::Pattern::escape(root_folder), glob_pattern);
+    let entries = glob::glob(&pattern)
+        .map_err(|error| format!("Invalid glob pattern '{}': {}", glob_pattern, error))?;
+    Ok(entries
         .filter_map(|maybe_entry| maybe_entry.ok())
         .map(|entry| entry.to_string_owned_or_panic().replace("\\", "/"))
-        .collect()
+        .collect())
 }
 
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 // Appdata
 
+#[cfg(target_os = "windows")]
 fn get_home_dir() -> Result<String, String> {
     directories::UserDirs::new()
         .ok_or("Could not find home directory".to_string())?
@@ -461,7 +465,7 @@ pub fn get_user_savedata_dir(company_name: &str, application_name: &str) -> Resu
 
 #[cfg(not(target_os = "windows"))]
 pub fn get_user_savedata_dir(company_name: &str, application_name: &str) -> Result<String, String> {
-    let TODO = "use a `internal_mode` feature here";
+    // TODO: use a `internal_mode` feature here
     /*
     // Try working dir first: Write test file to see if we even have writing permissions for './'
         if std::fs::write("test.txt", "test").is_ok() {
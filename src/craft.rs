@@ -0,0 +1,82 @@
+use crate::core::serde_derive::{Deserialize, Serialize};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Craft
+
+/// Determines what a pattern is made with. This selects the color palette, the preview tiles and
+/// how material is counted in legends.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Craft {
+    #[default]
+    CrossStitch,
+    /// Resin drills glued onto an adhesive canvas
+    DiamondPainting,
+    /// Plastic beads placed on pegboards and fused with an iron
+    FuseBeads,
+    /// Knitted stitches which are wider than tall
+    Knitting,
+    /// Crocheted stitches, i.e. tapestry crochet
+    Crochet,
+    /// Plates or tiles placed on baseplates, i.e. a brick mosaic
+    Bricks,
+}
+
+impl Craft {
+    pub const NAMES: [&'static str; 6] = [
+        "cross-stitch",
+        "diamond-painting",
+        "fuse-beads",
+        "knitting",
+        "crochet",
+        "bricks",
+    ];
+
+    pub fn from_name(name: &str) -> Result<Craft, String> {
+        match name {
+            "cross-stitch" => Ok(Craft::CrossStitch),
+            "diamond-painting" => Ok(Craft::DiamondPainting),
+            "fuse-beads" => Ok(Craft::FuseBeads),
+            "knitting" => Ok(Craft::Knitting),
+            "crochet" => Ok(Craft::Crochet),
+            "bricks" => Ok(Craft::Bricks),
+            _ => Err(format!(
+                "Unknown craft '{}' - valid crafts are: {}",
+                name,
+                Craft::NAMES.join(", ")
+            )),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Craft::CrossStitch => "cross-stitch",
+            Craft::DiamondPainting => "diamond-painting",
+            Craft::FuseBeads => "fuse-beads",
+            Craft::Knitting => "knitting",
+            Craft::Crochet => "crochet",
+            Craft::Bricks => "bricks",
+        }
+    }
+
+    /// What fills a single chart cell, i.e. `stitches`
+    pub fn cell_name_plural(self) -> &'static str {
+        match self {
+            Craft::CrossStitch | Craft::Knitting | Craft::Crochet => "stitches",
+            Craft::DiamondPainting => "drills",
+            Craft::FuseBeads => "beads",
+            Craft::Bricks => "studs",
+        }
+    }
+
+    /// Knitting and crochet stitches are not square, so their patterns follow a gauge
+    pub fn uses_gauge(self) -> bool {
+        matches!(self, Craft::Knitting | Craft::Crochet)
+    }
+
+    /// Paint by numbers charts merge cells into painted regions. Only thread colors translate to
+    /// paints, while drills, beads, yarns and bricks are placed cell by cell from their own
+    /// palettes.
+    pub fn has_paint_by_numbers(self) -> bool {
+        self == Craft::CrossStitch
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use crate::color_mapping;
+use crate::core::serde_derive::{Deserialize, Serialize};
+use crate::image::{Bitmap, PixelRGBA};
+use crate::math::clampf;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Constants
+
+/// Distance between the centers of neighbouring drills on the canvas
+pub const DRILL_PITCH_MM: f32 = 2.5;
+/// Our background tiles always contain 8x8 drills
+const CANVAS_TILE_DRILL_COUNT: i32 = 8;
+/// Gap between neighbouring drills in preview pixels
+const DRILL_GAP: i32 = 2;
+
+const COLOR_CANVAS: PixelRGBA = PixelRGBA::new(236, 234, 226, 255);
+const COLOR_CANVAS_GRID: PixelRGBA = PixelRGBA::new(212, 209, 200, 255);
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Diamond painting setup
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DrillShape {
+    #[default]
+    Square,
+    Round,
+}
+
+/// Determines the drills of a diamond painting kit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiamondSetup {
+    pub drill_shape: DrillShape,
+    /// Extra drills packed into every bag, i.e. 10 means 10% more drills than the chart needs
+    pub spare_percent: f32,
+    /// Drill manufacturers use the DMC numbering of the floss colors, but their resin colors are
+    /// not identical. A palette file with one `DMC,R,G,B` drill color per line replaces the
+    /// bundled drill palette, i.e. with the colors of a specific manufacturer. See
+    /// `load_palette_file`.
+    pub palette_filepath: Option<String>,
+}
+
+impl Default for DiamondSetup {
+    fn default() -> DiamondSetup {
+        DiamondSetup {
+            drill_shape: DrillShape::Square,
+            spare_percent: 10.0,
+            palette_filepath: None,
+        }
+    }
+}
+
+impl DiamondSetup {
+    pub const DRILL_SHAPE_NAMES: [&'static str; 2] = ["square", "round"];
+
+    pub fn drill_shape_from_name(name: &str) -> Result<DrillShape, String> {
+        match name {
+            "square" => Ok(DrillShape::Square),
+            "round" => Ok(DrillShape::Round),
+            _ => Err(format!(
+                "Unknown drill shape '{}' - valid shapes are: {}",
+                name,
+                DiamondSetup::DRILL_SHAPE_NAMES.join(", ")
+            )),
+        }
+    }
+
+    /// Number of drills to pack for a color that covers `drill_count` cells
+    pub fn drill_count_with_spare(&self, drill_count: usize) -> usize {
+        (drill_count as f32 * (1.0 + self.spare_percent / 100.0)).ceil() as usize
+    }
+}
+
+/// Returns the width and height of the drilled area in centimeters
+pub fn canvas_size_cm(width_drills: i32, height_drills: i32) -> (f32, f32) {
+    (
+        width_drills as f32 * DRILL_PITCH_MM / 10.0,
+        height_drills as f32 * DRILL_PITCH_MM / 10.0,
+    )
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Drill palette
+
+/// DMC numbers of the drills that kits are commonly packed from
+const DRILL_NUMBERS: &[&str] = &[
+    "B5200", "White", "Ecru", "310", "3799", "413", "317", "414", "318", "415", "762", "3865",
+    "712", "739", "738", "437", "436", "435", "434", "433", "801", "938", "3371", "898", "840",
+    "841", "842", "3021", "3782", "3033", "644", "822", "3072", "648", "646", "844", "3031",
+    "3781", "3045", "3046", "3047", "445", "307", "973", "444", "742", "740", "608", "946", "900",
+    "352", "351", "350", "349", "817", "321", "666", "304", "498", "815", "902", "3713", "761",
+    "760", "3328", "347", "818", "3326", "899", "335", "326", "963", "3716", "962", "961", "3350",
+    "3804", "3805", "601", "600", "718", "917", "915", "3609", "3608", "3607", "554", "553", "552",
+    "550", "211", "210", "209", "208", "327", "3747", "341", "340", "3746", "333", "809", "799",
+    "798", "797", "820", "3325", "334", "322", "312", "336", "939", "3755", "826", "825", "824",
+    "3761", "519", "518", "517", "3760", "3765", "3811", "598", "597", "3810", "3809", "564",
+    "563", "562", "561", "955", "954", "913", "911", "910", "909", "704", "703", "702", "701",
+    "700", "699", "772", "3348", "3347", "3346", "3345", "3052", "470", "469", "937", "936",
+    "3013", "3012", "3011", "3364", "3363", "3362", "934", "472", "3819", "166", "581", "580",
+    "167", "3828", "420", "869", "3862", "945", "951", "3770", "754", "758", "3778", "356", "355",
+    "3064", "407", "3863", "3864", "632", "3772", "223", "224", "225", "3727", "316", "3726",
+    "315", "3041", "3042", "3740", "153", "154", "3834", "3836", "3837", "3835", "3743",
+];
+
+/// Returns the DMC numbered drills that kits are commonly packed from.
+///
+/// NOTE: Drill sets don't cover the whole floss range and their colors are approximated by the
+///       floss colors of the same number. Resin colors vary between manufacturers, so for exact
+///       matching load the manufacturer's chart as a palette file.
+pub fn make_palette() -> HashMap<PixelRGBA, String> {
+    color_mapping::make()
+        .into_iter()
+        .filter(|(_color, number)| DRILL_NUMBERS.contains(&number.as_str()))
+        .collect()
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Preview tiles
+
+/// Creates a white drill and its facet luminance for the stitch preview. Both tiles have the
+/// given size and contain the drill in their center.
+pub fn create_drill_tiles(shape: DrillShape, tile_size: i32) -> (Bitmap, Bitmap) {
+    let mut drill = Bitmap::new_filled(tile_size as u32, tile_size as u32, PixelRGBA::transparent());
+    let mut luminance = drill.clone();
+
+    let center = tile_size as f32 / 2.0;
+    let half_size = (tile_size - DRILL_GAP) as f32 / 2.0;
+    for y in 0..tile_size {
+        for x in 0..tile_size {
+            let offset_x = x as f32 + 0.5 - center;
+            let offset_y = y as f32 + 0.5 - center;
+
+            let (coverage, brightness) = match shape {
+                DrillShape::Square => {
+                    let edge_distance = half_size - f32::max(offset_x.abs(), offset_y.abs());
+                    (
+                        clampf(edge_distance + 0.5, 0.0, 1.0),
+                        square_facet_brightness(offset_x, offset_y, half_size),
+                    )
+                }
+                DrillShape::Round => {
+                    let radius = f32::sqrt(offset_x * offset_x + offset_y * offset_y);
+                    (
+                        clampf(half_size - radius + 0.5, 0.0, 1.0),
+                        round_facet_brightness(offset_x, offset_y, half_size),
+                    )
+                }
+            };
+            if coverage <= 0.0 {
+                continue;
+            }
+
+            let alpha = (255.0 * coverage).round() as u8;
+            let grey = (255.0 * clampf(brightness, 0.0, 1.0)).round() as u8;
+            drill.set(x, y, PixelRGBA::new(255, 255, 255, alpha));
+            luminance.set(x, y, PixelRGBA::new(grey, grey, grey, alpha));
+        }
+    }
+
+    (drill, luminance)
+}
+
+/// Square drills have a flat table surrounded by four bevels lit from the top left
+fn square_facet_brightness(offset_x: f32, offset_y: f32, half_size: f32) -> f32 {
+    let bevel_width = 0.3 * half_size;
+    let table_half_size = half_size - bevel_width;
+    if offset_x.abs() <= table_half_size && offset_y.abs() <= table_half_size {
+        return table_brightness(offset_x, offset_y, half_size);
+    }
+
+    if offset_y.abs() >= offset_x.abs() {
+        if offset_y < 0.0 {
+            0.85
+        } else {
+            0.35
+        }
+    } else if offset_x < 0.0 {
+        0.7
+    } else {
+        0.45
+    }
+}
+
+/// Round drills have a flat table surrounded by a ring of facets lit from the top left
+fn round_facet_brightness(offset_x: f32, offset_y: f32, half_size: f32) -> f32 {
+    const FACET_COUNT: f32 = 12.0;
+
+    let radius = f32::sqrt(offset_x * offset_x + offset_y * offset_y);
+    if radius <= 0.6 * half_size {
+        return table_brightness(offset_x, offset_y, half_size);
+    }
+
+    // NOTE: Every facet is lit by how much its center faces the light in the top left
+    let facet_angle_step = std::f32::consts::TAU / FACET_COUNT;
+    let angle = f32::atan2(offset_y, offset_x);
+    let facet_angle = (angle / facet_angle_step).floor() * facet_angle_step + facet_angle_step / 2.0;
+    let light_angle = f32::atan2(-1.0, -1.0);
+    0.6 + 0.3 * f32::cos(facet_angle - light_angle)
+}
+
+/// The flat top of a drill with a small highlight in its top left
+fn table_brightness(offset_x: f32, offset_y: f32, half_size: f32) -> f32 {
+    let highlight_x = offset_x + 0.3 * half_size;
+    let highlight_y = offset_y + 0.3 * half_size;
+    let highlight_distance = f32::sqrt(highlight_x * highlight_x + highlight_y * highlight_y);
+    if highlight_distance < 0.15 * half_size {
+        1.0
+    } else {
+        0.6
+    }
+}
+
+/// Creates a background tile of 8x8 empty drill cells on an adhesive canvas. The tile has the
+/// same size as the bundled fabric tile.
+pub fn create_canvas_tile_8x8(tile_size: i32) -> Bitmap {
+    let mut tile = Bitmap::new_filled(tile_size as u32, tile_size as u32, COLOR_CANVAS);
+    let cell_size = tile_size / CANVAS_TILE_DRILL_COUNT;
+    for index in 0..CANVAS_TILE_DRILL_COUNT {
+        tile.draw_rect_filled(cell_size * index, 0, 1, tile_size, COLOR_CANVAS_GRID);
+        tile.draw_rect_filled(0, cell_size * index, tile_size, 1, COLOR_CANVAS_GRID);
+    }
+    tile
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_spare_drills_and_measures_canvas() {
+        let setup = DiamondSetup::default();
+        assert_eq!(setup.drill_count_with_spare(0), 0);
+        assert_eq!(setup.drill_count_with_spare(100), 110);
+        assert_eq!(setup.drill_count_with_spare(101), 112);
+        assert_eq!(canvas_size_cm(40, 60), (10.0, 15.0));
+    }
+
+    #[test]
+    fn drill_palette_has_the_floss_color_of_every_drill_number() {
+        let floss_palette = color_mapping::make();
+        let palette = make_palette();
+        assert_eq!(palette.len(), DRILL_NUMBERS.len());
+        for (color, number) in &palette {
+            assert_eq!(floss_palette.get(color), Some(number));
+        }
+    }
+}
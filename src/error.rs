@@ -0,0 +1,81 @@
+use std::fmt;
+
+use crate::image::PixelRGBA;
+
+/// Everything that can go wrong while converting an image into a pattern
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConversionError {
+    /// We only read GIF and PNG images
+    UnsupportedImageFormat { filepath: String },
+    /// An image or resource file could not be read or decoded
+    FileLoad { filepath: String, reason: String },
+    /// An output file could not be written
+    FileWrite { filepath: String, reason: String },
+    /// Bundled or user supplied resources like symbols or stitch tiles are missing or invalid
+    InvalidResources(String),
+    /// The given options contradict each other or don't work for the given image
+    InvalidOptions(String),
+    /// The image has more colors than there are symbols to tell them apart
+    NotEnoughSymbols {
+        image_name: String,
+        color_count: usize,
+        symbol_count: usize,
+        /// What the symbols are used for, i.e. `cross stitch` or `paint by numbers`
+        usage: &'static str,
+    },
+    /// A symbol was pinned to a thread with a symbol number we don't have or that is already
+    /// pinned to another thread
+    InvalidPinnedSymbol {
+        thread: String,
+        symbol_number: usize,
+        reason: String,
+    },
+    /// A stitch has a color that was not mapped to a thread
+    UnmappedColor(PixelRGBA),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConversionError::UnsupportedImageFormat { filepath } => write!(
+                formatter,
+                "Cannot read '{}' - we only support GIF or PNG images",
+                filepath
+            ),
+            ConversionError::FileLoad { filepath, reason } => {
+                write!(formatter, "Cannot load '{}': {}", filepath, reason)
+            }
+            ConversionError::FileWrite { filepath, reason } => {
+                write!(formatter, "Cannot write '{}': {}", filepath, reason)
+            }
+            ConversionError::InvalidResources(reason) => write!(formatter, "{}", reason),
+            ConversionError::InvalidOptions(reason) => write!(formatter, "{}", reason),
+            ConversionError::NotEnoughSymbols {
+                image_name,
+                color_count,
+                symbol_count,
+                usage,
+            } => write!(
+                formatter,
+                "Not enough symbols to map {} colors found in given image '{}' for {} - there are only {} symbols",
+                color_count, image_name, usage, symbol_count
+            ),
+            ConversionError::InvalidPinnedSymbol {
+                thread,
+                symbol_number,
+                reason,
+            } => write!(
+                formatter,
+                "Cannot pin symbol {} to DMC {} - {}",
+                symbol_number, thread, reason
+            ),
+            ConversionError::UnmappedColor(color) => write!(
+                formatter,
+                "No thread was mapped to color ({}, {}, {}, {})",
+                color.r, color.g, color.b, color.a
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
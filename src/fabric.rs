@@ -0,0 +1,211 @@
+use crate::core::serde_derive::{Deserialize, Serialize};
+use crate::image::{Bitmap, Color, PixelRGBA};
+use crate::math::{clampf, Random};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Constants
+
+/// Our background tiles always contain 8x8 stitches
+const FABRIC_TILE_STITCH_COUNT: i32 = 8;
+/// Evenweave and linen are stitched over two threads
+const FABRIC_THREADS_PER_STITCH: i32 = 2;
+/// Keeps some of the weave structure visible on dark fabrics where pure multiplication would
+/// result in a flat color
+const FABRIC_DARK_HIGHLIGHT: f32 = 0.15;
+const HAND_DYED_BRIGHTNESS_VARIATION: f32 = 0.12;
+const HAND_DYED_WAVE_COUNT: usize = 6;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Fabric
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FabricType {
+    Aida,
+    Evenweave,
+    Linen,
+}
+
+/// Determines what the stitch preview is stitched on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Fabric {
+    pub fabric_type: FabricType,
+    pub color: PixelRGBA,
+    /// Adds a cloudy color variation as seen on hand-dyed fabrics
+    pub hand_dyed: bool,
+}
+
+impl Default for Fabric {
+    fn default() -> Fabric {
+        Fabric {
+            fabric_type: FabricType::Aida,
+            color: PixelRGBA::white(),
+            hand_dyed: false,
+        }
+    }
+}
+
+impl Fabric {
+    pub const TYPE_NAMES: [&'static str; 3] = ["aida", "evenweave", "linen"];
+    pub const COLOR_NAMES: [&'static str; 6] =
+        ["white", "antique-white", "cream", "ivory", "black", "navy"];
+
+    pub fn fabric_type_from_name(name: &str) -> Result<FabricType, String> {
+        match name {
+            "aida" => Ok(FabricType::Aida),
+            "evenweave" => Ok(FabricType::Evenweave),
+            "linen" => Ok(FabricType::Linen),
+            _ => Err(format!(
+                "Unknown fabric '{}' - valid fabrics are: {}",
+                name,
+                Fabric::TYPE_NAMES.join(", ")
+            )),
+        }
+    }
+
+    /// Accepts either a color name like `cream` or a hex color like `F0E8D8`
+    pub fn color_from_name(name: &str) -> Result<PixelRGBA, String> {
+        match name {
+            "white" => Ok(PixelRGBA::white()),
+            "antique-white" => Ok(PixelRGBA::new(250, 240, 222, 255)),
+            "cream" => Ok(PixelRGBA::new(243, 232, 200, 255)),
+            "ivory" => Ok(PixelRGBA::new(252, 248, 232, 255)),
+            "black" => Ok(PixelRGBA::new(24, 24, 24, 255)),
+            "navy" => Ok(PixelRGBA::new(32, 40, 72, 255)),
+            hex => {
+                let hex = hex.trim_start_matches('#');
+                if hex.len() != 6 {
+                    return Err(format!(
+                        "Unknown fabric color '{}' - use one of {} or a hex color like 'F0E8D8'",
+                        name,
+                        Fabric::COLOR_NAMES.join(", ")
+                    ));
+                }
+                let rgb = u32::from_str_radix(hex, 16)
+                    .map_err(|_| format!("Invalid hex fabric color '{}'", name))?;
+                Ok(PixelRGBA::from_hex_rgba((rgb << 8) | 0xFF))
+            }
+        }
+    }
+}
+
+/// Creates a background tile of 8x8 unstitched cells in the color of the given fabric. The
+/// white aida tile is used as is for aida, the other weaves are generated with the same size.
+pub fn create_fabric_tile_8x8(fabric: &Fabric, aida_tile_8x8: &Bitmap) -> Bitmap {
+    let weave = match fabric.fabric_type {
+        FabricType::Aida => aida_tile_8x8.clone(),
+        FabricType::Evenweave => {
+            create_weave_luminance(aida_tile_8x8.width, aida_tile_8x8.height, false)
+        }
+        FabricType::Linen => create_weave_luminance(aida_tile_8x8.width, aida_tile_8x8.height, true),
+    };
+
+    let fabric_color = Color::from_pixelrgba(fabric.color);
+    let fabric_luminance = fabric_color.luminosity();
+    let mut result = weave;
+    for pixel in result.data.iter_mut() {
+        let weave_color = Color::from_pixelrgba(*pixel);
+        let highlight = FABRIC_DARK_HIGHLIGHT * weave_color.luminosity() * (1.0 - fabric_luminance);
+        let tinted = Color::new(
+            fabric_color.r * weave_color.r + highlight,
+            fabric_color.g * weave_color.g + highlight,
+            fabric_color.b * weave_color.b + highlight,
+            weave_color.a,
+        );
+        *pixel = tinted.to_pixelrgba();
+    }
+    result
+}
+
+/// Generates a seamless greyscale tile of over-under woven threads where each stitch covers
+/// FABRIC_THREADS_PER_STITCH threads in both directions. Linen gets uneven threads.
+fn create_weave_luminance(width: i32, height: i32, irregular: bool) -> Bitmap {
+    let thread_count = FABRIC_TILE_STITCH_COUNT * FABRIC_THREADS_PER_STITCH;
+
+    // NOTE: Evenweave threads are all alike, linen threads vary in thickness and brightness
+    let mut random = Random::new_from_seed(thread_count as u64);
+    let mut thread_variations = |count: i32| -> Vec<(f32, f32)> {
+        (0..count)
+            .map(|_| {
+                if irregular {
+                    (random.f32_in_range(0.7, 1.0), random.f32_in_range(-0.06, 0.06))
+                } else {
+                    (0.85, 0.0)
+                }
+            })
+            .collect()
+    };
+    let threads_x = thread_variations(thread_count);
+    let threads_y = thread_variations(thread_count);
+
+    let mut result = Bitmap::new(width as u32, height as u32);
+    for y in 0..height {
+        for x in 0..width {
+            let thread_pos_x = (x as f32 + 0.5) * thread_count as f32 / width as f32;
+            let thread_pos_y = (y as f32 + 0.5) * thread_count as f32 / height as f32;
+            let thread_x = thread_pos_x as usize;
+            let thread_y = thread_pos_y as usize;
+
+            // Distance from the thread center in [0, 1], relative to the thread thickness
+            let offset_in_thread = |thread_pos: f32, thickness: f32| {
+                (2.0 * thread_pos.fract() - 1.0).abs() / thickness
+            };
+            let (thickness_x, brightness_x) = threads_x[thread_x];
+            let (thickness_y, brightness_y) = threads_y[thread_y];
+            let offset_vertical_thread = offset_in_thread(thread_pos_x, thickness_x);
+            let offset_horizontal_thread = offset_in_thread(thread_pos_y, thickness_y);
+
+            let vertical_thread_on_top = (thread_x + thread_y).is_multiple_of(2);
+            let (offset_top, brightness_top, offset_bottom, brightness_bottom) =
+                if vertical_thread_on_top {
+                    (offset_vertical_thread, brightness_x, offset_horizontal_thread, brightness_y)
+                } else {
+                    (offset_horizontal_thread, brightness_y, offset_vertical_thread, brightness_x)
+                };
+
+            // NOTE: Threads are shaded like cylinders, the thread below is a bit darker and the
+            //       gaps between threads form the holes of the fabric
+            let luminance = if offset_top < 1.0 {
+                0.78 + 0.22 * (1.0 - offset_top * offset_top).sqrt() + brightness_top
+            } else if offset_bottom < 1.0 {
+                0.62 + 0.2 * (1.0 - offset_bottom * offset_bottom).sqrt() + brightness_bottom
+            } else {
+                0.45
+            };
+            let luminance = clampf(luminance, 0.0, 1.0);
+            result.set(
+                x,
+                y,
+                Color::new(luminance, luminance, luminance, 1.0).to_pixelrgba(),
+            );
+        }
+    }
+    result
+}
+
+/// Adds a cloudy brightness variation to the given fabric background to mimic hand-dyed fabric
+pub fn apply_hand_dyed_variation(background: &mut Bitmap, seed: u64) {
+    let mut random = Random::new_from_seed(seed);
+    let waves: Vec<(f32, f32, f32)> = (0..HAND_DYED_WAVE_COUNT)
+        .map(|_| {
+            let angle = random.f32_in_range(0.0, std::f32::consts::TAU);
+            let wavelength = random.f32_in_range(150.0, 600.0);
+            let phase = random.f32_in_range(0.0, std::f32::consts::TAU);
+            (angle, wavelength, phase)
+        })
+        .collect();
+
+    for y in 0..background.height {
+        for x in 0..background.width {
+            let mut variation = 0.0;
+            for (angle, wavelength, phase) in &waves {
+                let distance = x as f32 * angle.cos() + y as f32 * angle.sin();
+                variation += (std::f32::consts::TAU * distance / wavelength + phase).sin();
+            }
+            let factor = 1.0
+                + HAND_DYED_BRIGHTNESS_VARIATION * variation / HAND_DYED_WAVE_COUNT as f32 * 2.0;
+            let color = Color::from_pixelrgba(background.get(x, y));
+            background.set(x, y, color.with_color_multiplied_by(factor).to_pixelrgba());
+        }
+    }
+}
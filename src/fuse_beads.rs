@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+
+use crate::core::serde_derive::{Deserialize, Serialize};
+use crate::image::{Bitmap, PixelRGBA};
+use crate::math::clampf;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Constants
+
+/// Distance between neighbouring pegs of standard size (midi) pegboards
+pub const BEAD_PITCH_MM: f32 = 5.0;
+/// Our background tiles always contain 8x8 pegs
+const PEGBOARD_TILE_PEG_COUNT: i32 = 8;
+/// Outer and inner radius of a bead relative to the tile size
+const BEAD_RADIUS_OUTER: f32 = 0.47;
+const BEAD_RADIUS_INNER: f32 = 0.2;
+const PEG_RADIUS: f32 = 0.12;
+
+const COLOR_PEGBOARD: PixelRGBA = PixelRGBA::new(232, 234, 236, 255);
+const COLOR_PEG: PixelRGBA = PixelRGBA::new(208, 212, 216, 255);
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Bead setup
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BeadBrand {
+    #[default]
+    Perler,
+    Hama,
+    Artkal,
+}
+
+/// Determines the beads and pegboards of a fuse bead pattern
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BeadSetup {
+    pub brand: BeadBrand,
+    /// Number of pegs along each side of a square pegboard. Every board gets its own chart page.
+    pub pegboard_size: i32,
+    /// Palette file with one `CODE,R,G,B` bead color per line that replaces the bundled brand
+    /// palette. See `load_palette_file`.
+    pub palette_filepath: Option<String>,
+}
+
+impl Default for BeadSetup {
+    fn default() -> BeadSetup {
+        BeadSetup {
+            brand: BeadBrand::Perler,
+            pegboard_size: 29,
+            palette_filepath: None,
+        }
+    }
+}
+
+impl BeadSetup {
+    pub const BRAND_NAMES: [&'static str; 3] = ["perler", "hama", "artkal"];
+
+    pub fn brand_from_name(name: &str) -> Result<BeadBrand, String> {
+        match name {
+            "perler" => Ok(BeadBrand::Perler),
+            "hama" => Ok(BeadBrand::Hama),
+            "artkal" => Ok(BeadBrand::Artkal),
+            _ => Err(format!(
+                "Unknown bead brand '{}' - valid brands are: {}",
+                name,
+                BeadSetup::BRAND_NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl BeadBrand {
+    /// Used in front of the color codes in legends, i.e. `Hama H18 Black`
+    pub fn display_name(self) -> &'static str {
+        match self {
+            BeadBrand::Perler => "Perler",
+            BeadBrand::Hama => "Hama",
+            BeadBrand::Artkal => "Artkal",
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Bead palettes
+
+/// Returns the solid colors of the given brand's standard size beads.
+///
+/// NOTE: These are approximations of the colors on the manufacturers' color charts. Bead colors
+///       vary between batches and screens, so for exact matching load the manufacturer's chart
+///       as a palette file.
+pub fn make_palette(brand: BeadBrand) -> HashMap<PixelRGBA, String> {
+    brand_colors(brand)
+        .iter()
+        .map(|&((r, g, b), code)| (PixelRGBA::new(r, g, b, 255), code.to_owned()))
+        .collect()
+}
+
+fn brand_colors(brand: BeadBrand) -> &'static [((u8, u8, u8), &'static str)] {
+    match brand {
+        BeadBrand::Perler => &[
+            ((241, 241, 241), "P01 White"),
+            ((224, 222, 169), "P02 Cream"),
+            ((236, 216, 0), "P03 Yellow"),
+            ((237, 97, 32), "P04 Orange"),
+            ((191, 46, 64), "P05 Red"),
+            ((221, 102, 154), "P06 Bubblegum"),
+            ((96, 64, 137), "P07 Purple"),
+            ((43, 63, 135), "P08 Dark Blue"),
+            ((51, 112, 192), "P09 Light Blue"),
+            ((28, 117, 62), "P10 Dark Green"),
+            ((86, 186, 159), "P11 Light Green"),
+            ((81, 57, 49), "P12 Brown"),
+            ((138, 141, 145), "P17 Grey"),
+            ((46, 47, 50), "P18 Black"),
+            ((140, 55, 44), "P20 Rust"),
+            ((129, 93, 52), "P21 Light Brown"),
+            ((238, 186, 178), "P33 Peach"),
+            ((188, 147, 113), "P35 Tan"),
+            ((243, 46, 183), "P38 Magenta"),
+            ((100, 170, 220), "P52 Pastel Blue"),
+            ((118, 200, 130), "P53 Pastel Green"),
+            ((136, 121, 207), "P54 Pastel Lavender"),
+            ((248, 233, 94), "P56 Pastel Yellow"),
+            ((241, 170, 12), "P57 Cheddar"),
+            ((147, 200, 212), "P58 Toothpaste"),
+            ((255, 57, 81), "P59 Hot Coral"),
+            ((162, 75, 156), "P60 Plum"),
+            ((108, 190, 19), "P61 Kiwi Lime"),
+            ((39, 138, 203), "P62 Turquoise"),
+            ((255, 130, 133), "P63 Blush"),
+            ((118, 135, 210), "P70 Periwinkle"),
+            ((246, 179, 221), "P79 Light Pink"),
+            ((79, 173, 66), "P80 Bright Green"),
+            ((228, 72, 146), "P83 Pink"),
+            ((165, 48, 97), "P88 Raspberry"),
+            ((207, 112, 54), "P90 Butterscotch"),
+            ((77, 81, 86), "P92 Dark Grey"),
+            ((128, 14, 36), "P96 Cranapple"),
+            ((228, 182, 133), "P98 Sand"),
+        ],
+        BeadBrand::Hama => &[
+            ((236, 237, 237), "H01 White"),
+            ((240, 232, 185), "H02 Cream"),
+            ((240, 185, 1), "H03 Yellow"),
+            ((230, 79, 39), "H04 Orange"),
+            ((182, 49, 54), "H05 Red"),
+            ((225, 136, 159), "H06 Pink"),
+            ((105, 74, 130), "H07 Purple"),
+            ((44, 70, 144), "H08 Blue"),
+            ((48, 92, 176), "H09 Light Blue"),
+            ((37, 104, 71), "H10 Green"),
+            ((73, 174, 137), "H11 Light Green"),
+            ((83, 65, 55), "H12 Brown"),
+            ((131, 136, 138), "H17 Grey"),
+            ((46, 47, 49), "H18 Black"),
+            ((127, 51, 42), "H20 Reddish Brown"),
+            ((165, 105, 63), "H21 Light Brown"),
+            ((160, 50, 54), "H22 Dark Red"),
+            ((222, 158, 144), "H26 Flesh"),
+            ((222, 180, 139), "H27 Beige"),
+            ((54, 63, 56), "H28 Dark Green"),
+            ((185, 57, 94), "H29 Claret"),
+            ((65, 39, 47), "H30 Burgundy"),
+            ((105, 160, 175), "H31 Turquoise"),
+            ((240, 228, 128), "H43 Pastel Yellow"),
+            ((230, 107, 118), "H44 Pastel Red"),
+            ((155, 142, 199), "H45 Pastel Purple"),
+            ((124, 186, 218), "H46 Pastel Blue"),
+            ((144, 199, 134), "H47 Pastel Green"),
+            ((210, 108, 179), "H48 Pastel Pink"),
+            ((160, 115, 35), "H60 Teddy Brown"),
+            ((190, 195, 191), "H70 Light Grey"),
+            ((73, 76, 81), "H71 Dark Grey"),
+        ],
+        BeadBrand::Artkal => &[
+            ((255, 255, 255), "S01 White"),
+            ((247, 232, 190), "S02 Cream"),
+            ((255, 220, 0), "S03 Yellow"),
+            ((255, 130, 0), "S04 Orange"),
+            ((220, 30, 50), "S05 Red"),
+            ((120, 20, 40), "S06 Wine Red"),
+            ((250, 160, 190), "S07 Pink"),
+            ((230, 70, 150), "S08 Rose"),
+            ((120, 70, 160), "S09 Purple"),
+            ((180, 160, 220), "S10 Lavender"),
+            ((30, 50, 130), "S11 Navy Blue"),
+            ((0, 110, 190), "S12 Blue"),
+            ((120, 190, 230), "S13 Sky Blue"),
+            ((0, 170, 170), "S14 Turquoise"),
+            ((0, 110, 60), "S15 Dark Green"),
+            ((70, 170, 70), "S16 Green"),
+            ((170, 210, 90), "S17 Lime"),
+            ((90, 60, 40), "S18 Dark Brown"),
+            ((150, 100, 60), "S19 Brown"),
+            ((230, 190, 150), "S20 Skin"),
+            ((200, 200, 200), "S21 Light Grey"),
+            ((130, 130, 130), "S22 Grey"),
+            ((70, 70, 70), "S23 Dark Grey"),
+            ((20, 20, 20), "S24 Black"),
+        ],
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Preview tiles
+
+/// Creates a white bead seen from above and its luminance for the stitch preview. Both tiles
+/// have the given size and contain the bead in their center.
+pub fn create_bead_tiles(tile_size: i32) -> (Bitmap, Bitmap) {
+    let mut bead = Bitmap::new_filled(tile_size as u32, tile_size as u32, PixelRGBA::transparent());
+    let mut luminance = bead.clone();
+
+    let center = tile_size as f32 / 2.0;
+    let radius_outer = BEAD_RADIUS_OUTER * tile_size as f32;
+    let radius_inner = BEAD_RADIUS_INNER * tile_size as f32;
+    let ring_center = (radius_outer + radius_inner) / 2.0;
+    let ring_half_width = (radius_outer - radius_inner) / 2.0;
+    for y in 0..tile_size {
+        for x in 0..tile_size {
+            let offset_x = x as f32 + 0.5 - center;
+            let offset_y = y as f32 + 0.5 - center;
+            let radius = f32::sqrt(offset_x * offset_x + offset_y * offset_y);
+            let coverage = clampf(radius_outer - radius + 0.5, 0.0, 1.0)
+                * clampf(radius - radius_inner + 0.5, 0.0, 1.0);
+            if coverage <= 0.0 {
+                continue;
+            }
+
+            // NOTE: The ring is a rounded tube, lit from the top left
+            let across = clampf((radius - ring_center) / ring_half_width, -1.0, 1.0);
+            let normal_z = f32::sqrt(1.0 - across * across);
+            let (direction_x, direction_y) = if radius > 0.0 {
+                (offset_x / radius, offset_y / radius)
+            } else {
+                (0.0, 0.0)
+            };
+            let lighting = normal_z * 0.55
+                - 0.35 * across * (direction_x + direction_y) * std::f32::consts::FRAC_1_SQRT_2;
+            let brightness = clampf(0.3 + lighting, 0.0, 1.0);
+
+            let alpha = (255.0 * coverage).round() as u8;
+            let grey = (255.0 * brightness).round() as u8;
+            bead.set(x, y, PixelRGBA::new(255, 255, 255, alpha));
+            luminance.set(x, y, PixelRGBA::new(grey, grey, grey, alpha));
+        }
+    }
+
+    (bead, luminance)
+}
+
+/// Creates a background tile of 8x8 empty pegs on a pegboard. The tile has the same size as the
+/// bundled fabric tile.
+pub fn create_pegboard_tile_8x8(tile_size: i32) -> Bitmap {
+    let mut tile = Bitmap::new_filled(tile_size as u32, tile_size as u32, COLOR_PEGBOARD);
+    let cell_size = tile_size / PEGBOARD_TILE_PEG_COUNT;
+    let peg_radius = PEG_RADIUS * cell_size as f32;
+    for y in 0..tile_size {
+        for x in 0..tile_size {
+            let offset_x = (x % cell_size) as f32 + 0.5 - cell_size as f32 / 2.0;
+            let offset_y = (y % cell_size) as f32 + 0.5 - cell_size as f32 / 2.0;
+            if offset_x * offset_x + offset_y * offset_y <= peg_radius * peg_radius {
+                tile.set(x, y, COLOR_PEG);
+            }
+        }
+    }
+    tile
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brand_palettes_have_distinct_colors_and_codes() {
+        for name in BeadSetup::BRAND_NAMES {
+            let brand = BeadSetup::brand_from_name(name).unwrap();
+            let colors = brand_colors(brand);
+            let palette = make_palette(brand);
+            let mut codes: Vec<&String> = palette.values().collect();
+            codes.sort();
+            codes.dedup();
+            assert!(colors.len() >= 20);
+            assert_eq!(palette.len(), colors.len());
+            assert_eq!(codes.len(), colors.len());
+            // NOTE: Codes start with the product number, i.e. `S01 White`
+            assert!(codes.iter().all(|code| code.as_bytes()[1].is_ascii_digit()));
+        }
+        assert!(BeadSetup::brand_from_name("nabbi").is_err());
+    }
+}
@@ -77,6 +77,137 @@ impl Bitmap {
         result
     }
 
+    /// Rotates the bitmap clockwise around its center by the given angle while keeping its size.
+    /// Uses bilinear sampling, so the bitmap should be premultiplied. Pixels that are rotated in
+    /// from outside are transparent.
+    #[must_use]
+    pub fn rotated_bilinear(&self, angle_radians: f32) -> Bitmap {
+        let (sin, cos) = angle_radians.sin_cos();
+        let center_x = 0.5 * self.width as f32;
+        let center_y = 0.5 * self.height as f32;
+        let sample = |x: i32, y: i32| self.get_or_default(x, y, PixelRGBA::transparent());
+
+        let mut result = Bitmap::new(self.width as u32, self.height as u32);
+        for y in 0..result.height {
+            for x in 0..result.width {
+                // NOTE: We rotate the destination pixel center backwards to find its source
+                let offset_x = x as f32 + 0.5 - center_x;
+                let offset_y = y as f32 + 0.5 - center_y;
+                let source_x = cos * offset_x + sin * offset_y + center_x - 0.5;
+                let source_y = -sin * offset_x + cos * offset_y + center_y - 0.5;
+
+                let left = source_x.floor() as i32;
+                let top = source_y.floor() as i32;
+                let percent_x = source_x - left as f32;
+                let percent_y = source_y - top as f32;
+                let top_left = Color::from_pixelrgba(sample(left, top));
+                let top_right = Color::from_pixelrgba(sample(left + 1, top));
+                let bottom_left = Color::from_pixelrgba(sample(left, top + 1));
+                let bottom_right = Color::from_pixelrgba(sample(left + 1, top + 1));
+                let color = Color::lerp(
+                    Color::lerp(top_left, top_right, percent_x),
+                    Color::lerp(bottom_left, bottom_right, percent_x),
+                    percent_y,
+                );
+                result.set(x, y, color.to_pixelrgba());
+            }
+        }
+        result
+    }
+
+    /// Scales the bitmap using bilinear sampling which is suited for enlarging. Bitmaps with
+    /// translucent pixels should be premultiplied.
+    #[must_use]
+    pub fn scaled_bilinear(&self, new_width: u32, new_height: u32) -> Bitmap {
+        assert!(new_width > 0);
+        assert!(new_height > 0);
+
+        let scale_x = self.width as f32 / new_width as f32;
+        let scale_y = self.height as f32 / new_height as f32;
+        let sample = |x: i32, y: i32| {
+            Color::from_pixelrgba(self.get(
+                i32::clamp(x, 0, self.width - 1),
+                i32::clamp(y, 0, self.height - 1),
+            ))
+        };
+
+        let mut result = Bitmap::new(new_width, new_height);
+        for y in 0..result.height {
+            for x in 0..result.width {
+                let source_x = (x as f32 + 0.5) * scale_x - 0.5;
+                let source_y = (y as f32 + 0.5) * scale_y - 0.5;
+
+                let left = source_x.floor() as i32;
+                let top = source_y.floor() as i32;
+                let percent_x = source_x - left as f32;
+                let percent_y = source_y - top as f32;
+                let color = Color::lerp(
+                    Color::lerp(sample(left, top), sample(left + 1, top), percent_x),
+                    Color::lerp(sample(left, top + 1), sample(left + 1, top + 1), percent_x),
+                    percent_y,
+                );
+                result.set(x, y, color.to_pixelrgba());
+            }
+        }
+        result
+    }
+
+    /// Scales the bitmap by averaging all source pixels that overlap a destination pixel,
+    /// weighted by their overlapping area. This avoids the aliasing of nearest neighbor sampling
+    /// when downscaling. Bitmaps with translucent pixels should be premultiplied.
+    #[must_use]
+    pub fn scaled_area_average(&self, new_width: u32, new_height: u32) -> Bitmap {
+        assert!(new_width > 0);
+        assert!(new_height > 0);
+
+        let scale_x = self.width as f32 / new_width as f32;
+        let scale_y = self.height as f32 / new_height as f32;
+        let mut result = Bitmap::new(new_width, new_height);
+        for y in 0..result.height {
+            let source_top = y as f32 * scale_y;
+            let source_bottom = (y + 1) as f32 * scale_y;
+            for x in 0..result.width {
+                let source_left = x as f32 * scale_x;
+                let source_right = (x + 1) as f32 * scale_x;
+
+                let mut sum = [0.0f32; 4];
+                let mut weight_sum = 0.0;
+                for source_y in
+                    source_top as i32..i32::min(source_bottom.ceil() as i32, self.height)
+                {
+                    let weight_y = f32::min(source_bottom, (source_y + 1) as f32)
+                        - f32::max(source_top, source_y as f32);
+                    for source_x in
+                        source_left as i32..i32::min(source_right.ceil() as i32, self.width)
+                    {
+                        let weight_x = f32::min(source_right, (source_x + 1) as f32)
+                            - f32::max(source_left, source_x as f32);
+                        let weight = weight_x * weight_y;
+                        let pixel = self.get(source_x, source_y);
+                        sum[0] += weight * pixel.r as f32;
+                        sum[1] += weight * pixel.g as f32;
+                        sum[2] += weight * pixel.b as f32;
+                        sum[3] += weight * pixel.a as f32;
+                        weight_sum += weight;
+                    }
+                }
+
+                let average = |channel_sum: f32| (channel_sum / weight_sum).round() as u8;
+                result.set(
+                    x,
+                    y,
+                    PixelRGBA::new(
+                        average(sum[0]),
+                        average(sum[1]),
+                        average(sum[2]),
+                        average(sum[3]),
+                    ),
+                );
+            }
+        }
+        result
+    }
+
     // This assumes `self` and `other` to be premultiplied
     #[must_use]
     pub fn masked_by_premultiplied_alpha(&self, mask: &Bitmap) -> Bitmap {
@@ -219,6 +350,16 @@ impl Bitmap {
     }
 
     pub fn encoded_as_png(&self) -> Vec<u8> {
+        self.encoded_as_png_with_optional_dpi(None)
+    }
+
+    /// Same as `encoded_as_png` but also stores the print resolution in the png data so that
+    /// image viewers and printers can print it at its intended physical size
+    pub fn encoded_as_png_with_dpi(&self, dots_per_inch: f32) -> Vec<u8> {
+        self.encoded_as_png_with_optional_dpi(Some(dots_per_inch))
+    }
+
+    fn encoded_as_png_with_optional_dpi(&self, dots_per_inch: Option<f32>) -> Vec<u8> {
         let mut png_data = Vec::new();
         {
             let mut encoder = png::Encoder::new(
@@ -230,9 +371,22 @@ impl Bitmap {
             encoder.set_depth(png::BitDepth::Eight);
             let mut writer = encoder.write_header().unwrap();
 
+            if let Some(dots_per_inch) = dots_per_inch {
+                // NOTE: The `pHYs` chunk stores pixels per meter for both axes followed by a unit
+                //       specifier where 1 means meters
+                let pixels_per_meter = (dots_per_inch / 0.0254).round() as u32;
+                let mut physical_dimensions = Vec::with_capacity(9);
+                physical_dimensions.extend_from_slice(&pixels_per_meter.to_be_bytes());
+                physical_dimensions.extend_from_slice(&pixels_per_meter.to_be_bytes());
+                physical_dimensions.push(1);
+                writer
+                    .write_chunk(*b"pHYs", &physical_dimensions)
+                    .expect("Could not encode png data");
+            }
+
             writer
                 .write_image_data(self.as_bytes())
-                .expect(&format!("Could not encode png data to"));
+                .expect("Could not encode png data to");
         }
         png_data
     }
@@ -241,23 +395,11 @@ impl Bitmap {
     pub fn write_to_png_file(&self, png_filepath: &str) {
         use platform::path_without_filename;
 
-        std::fs::create_dir_all(path_without_filename(png_filepath)).expect(&format!(
-            "Could not create necessary directories to write to '{}'",
-            png_filepath
-        ));
-
-        let file = std::fs::File::create(png_filepath)
-            .expect(&format!("Could not open png file '{}'", png_filepath));
-
-        let ref mut file_writer = std::io::BufWriter::new(file);
-        let mut encoder = png::Encoder::new(file_writer, self.width as u32, self.height as u32);
-        encoder.set_color(png::ColorType::RGBA);
-        encoder.set_depth(png::BitDepth::Eight);
-        let mut writer = encoder.write_header().unwrap();
+        std::fs::create_dir_all(path_without_filename(png_filepath)).unwrap_or_else(|_| panic!("Could not create necessary directories to write to '{}'",
+            png_filepath));
 
-        writer
-            .write_image_data(self.as_bytes())
-            .expect(&format!("Could not write png file to '{}'", png_filepath));
+        std::fs::write(png_filepath, self.encoded_as_png())
+            .unwrap_or_else(|_| panic!("Could not write png file to '{}'", png_filepath));
     }
 
     /// Draws a given utf8 text to a given bitmap
@@ -385,7 +527,7 @@ impl BitmapAtlas {
         }
 
         // NOTE: At this point our image did not fit in our atlas textures, so we resize it
-        let texture_size_max = self.atlas_texture_size_max.unwrap_or(std::u32::MAX);
+        let texture_size_max = self.atlas_texture_size_max.unwrap_or(u32::MAX);
         loop {
             let texture_size = self.atlas_texture.width;
             if texture_size as u32 >= texture_size_max {
@@ -451,7 +593,7 @@ impl BitmapMultiAtlas {
         if let Some(atlas_position) = self.pack_bitmap_internal(sprite_name, image) {
             atlas_position
         } else {
-            let texture_size_max = self.atlas_texture_size_max.unwrap_or(std::u32::MAX);
+            let texture_size_max = self.atlas_texture_size_max.unwrap_or(u32::MAX);
             panic!(
                 "Could not pack image with dimensions {}x{} into atlas with dimensions with maxium dimensions {}x{}",
                 image.width, image.height, texture_size_max, texture_size_max
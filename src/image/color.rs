@@ -493,31 +493,31 @@ impl Color {
 
     #[inline]
     pub fn premultiplied_alpha_blend_normal(source: Color, dest: Color) -> Color {
-        return Color::premultiplied_alpha_blend_with_function(
+        Color::premultiplied_alpha_blend_with_function(
             source,
             dest,
             |source_x, _source_a, _dest_x, dest_a| source_x * dest_a,
-        );
+        )
     }
 
     #[inline]
     pub fn premultiplied_alpha_blend_multiply(source: Color, dest: Color) -> Color {
-        return Color::premultiplied_alpha_blend_with_function(
+        Color::premultiplied_alpha_blend_with_function(
             source,
             dest,
             |source_x, _source_a, dest_x, _dest_a| source_x * dest_x,
-        );
+        )
     }
 
     #[inline]
     pub fn premultiplied_alpha_blend_screen(source: Color, dest: Color) -> Color {
-        return Color::premultiplied_alpha_blend_with_function(
+        Color::premultiplied_alpha_blend_with_function(
             source,
             dest,
             |source_x, source_a, dest_x, dest_a| {
                 dest_a * source_x + source_a * dest_x - source_x * dest_x
             },
-        );
+        )
     }
 
     #[inline]
@@ -30,6 +30,23 @@ pub type Codepoint = i32;
 pub const FONT_MAX_NUM_FASTPATH_CODEPOINTS: usize = 256;
 const FIRST_VISIBLE_ASCII_CODE_POINT: Codepoint = 32;
 
+/// The metrics of our bundled pixelfonts are known to be slightly off, so we only warn about
+/// metrics of other fonts
+fn metrics_log_level(font_name: &str) -> log::Level {
+    let is_bundled_font = [
+        FONT_DEFAULT_TINY_NAME,
+        FONT_DEFAULT_SMALL_NAME,
+        FONT_DEFAULT_REGULAR_NAME,
+        FONT_DEFAULT_SQUARE_NAME,
+    ]
+    .contains(&font_name);
+    if is_bundled_font {
+        log::Level::Debug
+    } else {
+        log::Level::Warn
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 // Font and Glyph traits
 
@@ -86,7 +103,7 @@ pub trait Font<GlyphType: Glyph> {
     fn wrap_text_for_pixelwidth(&self, text: &str, width: u32) -> String {
         if self.is_fixed_width_font() {
             textwrap::fill(
-                &text,
+                text,
                 (width as i32 / self.horizontal_advance_max()) as usize,
             )
         } else {
@@ -104,17 +121,17 @@ pub trait Font<GlyphType: Glyph> {
         font_scale: i32,
         ignore_whitespace: bool,
     ) -> Recti {
-        if text.len() == 0 {
+        if text.is_empty() {
             return Recti::zero();
         }
 
         if ignore_whitespace {
             // NOTE: We don't start at (0,0) because the first glyph may be a whitespace which we don't
             //       want contained in our rect
-            let mut left = std::i32::MAX;
-            let mut top = std::i32::MAX;
-            let mut right = -std::i32::MAX;
-            let mut bottom = -std::i32::MAX;
+            let mut left = i32::MAX;
+            let mut top = i32::MAX;
+            let mut right = -i32::MAX;
+            let mut bottom = -i32::MAX;
 
             self.iter_text_glyphs(
                 text,
@@ -230,6 +247,8 @@ pub trait Font<GlyphType: Glyph> {
 
     /// Iterates a given utf8 text and runs a given operation on each glyph if it would be visible
     /// in the given clipping rect
+    // NOTE: This takes the same arguments as `iter_text_glyphs` plus the clipping rect
+    #[allow(clippy::too_many_arguments)]
     #[inline]
     fn iter_text_glyphs_clipped<Operation: core::ops::FnMut(&GlyphType, Vec2i, char) -> ()>(
         &self,
@@ -266,7 +285,7 @@ pub trait Font<GlyphType: Glyph> {
                 let glyph = self.get_glyph_for_codepoint(codepoint as Codepoint);
                 let draw_pos = origin + pos;
 
-                operation(&glyph, draw_pos, codepoint);
+                operation(glyph, draw_pos, codepoint);
 
                 pos.x += font_scale * glyph.horizontal_advance();
             } else {
@@ -339,7 +358,7 @@ pub trait Font<GlyphType: Glyph> {
             text,
             font_scale,
             origin_aligned,
-            starting_offset.into(),
+            starting_offset,
             origin_is_baseline,
             operation,
         )
@@ -349,6 +368,26 @@ pub trait Font<GlyphType: Glyph> {
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 // BitmapFont
 
+/// Determines how the glyphs of a `BitmapFont` are rasterized
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GlyphStyle {
+    pub border_thickness: i32,
+    pub atlas_padding: i32,
+    pub color_glyph: PixelRGBA,
+    pub color_border: PixelRGBA,
+}
+
+impl Default for GlyphStyle {
+    fn default() -> GlyphStyle {
+        GlyphStyle {
+            border_thickness: 0,
+            atlas_padding: 0,
+            color_glyph: PixelRGBA::black(),
+            color_border: PixelRGBA::transparent(),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct BitmapFont {
     pub font_name: String,
@@ -402,11 +441,9 @@ impl BitmapFont {
         font_ttf_bytes: &[u8],
         font_height: i32,
         font_raster_offset: Vec2,
-        border_thickness: i32,
-        atlas_padding: i32,
-        color_glyph: PixelRGBA,
-        color_border: PixelRGBA,
+        glyph_style: GlyphStyle,
     ) -> BitmapFont {
+        let border_thickness = glyph_style.border_thickness;
         let font = rusttype::Font::try_from_bytes(font_ttf_bytes)
             .unwrap_or_else(|| panic!("Could not decode font '{}' from bytes", font_name));
 
@@ -428,7 +465,8 @@ impl BitmapFont {
                 || !is_effectively_zero(descent - descent_integer as f32)
                 || !is_effectively_zero(line_gap - line_gap_integer as f32)
             {
-                log::warn!(
+                log::log!(
+                    metrics_log_level(font_name),
                     "Vertical metrics of pixelfont '{}' are not whole numbered\nascent: {}\ndescent: {}\nline_gap: {}\nThe given raster offset ({},{}) was not enough to correct this",
                     font_name,
                     v_metrics.ascent,
@@ -442,7 +480,8 @@ impl BitmapFont {
             // Check if our ascent + descent add up to the font height. If not then the raster
             // offsets we were given are wrong
             if font_height != (i32::abs(ascent_integer) + i32::abs(descent_integer)) {
-                log::warn!(
+                log::log!(
+                    metrics_log_level(font_name),
                     "Fontheight and (ascent + descent) of pixelfont '{}' do not match\nascent: {}\ndescent: {}\nascent + descent: {}\nfont height: {}\nThe given raster offset ({},{}) was probably wrong",
                     font_name,
                     i32::abs(ascent_integer),
@@ -462,8 +501,8 @@ impl BitmapFont {
 
         // Create glyphs
         let mut glyphs: IndexMap<Codepoint, BitmapGlyph> = IndexMap::new();
-        for index in 0..std::u16::MAX as Codepoint {
-            let codepoint = if index < 0 && index < FIRST_VISIBLE_ASCII_CODE_POINT {
+        for index in 0..u16::MAX as Codepoint {
+            let codepoint = if 0 < index && index < FIRST_VISIBLE_ASCII_CODE_POINT {
                 // NOTE: We want to turn ASCII characters 0..65535 into glyphs but want to treat the
                 //       non-displayable characters 1..31 as just whitespace. So we repeat the whitespace
                 //       character 32 times and chain it to the remaining ASCII characters.
@@ -475,13 +514,10 @@ impl BitmapFont {
                 index
             };
 
-            let character = {
-                let maybe_char = std::char::from_u32(codepoint as u32);
-                if maybe_char.is_none() {
-                    continue;
-                } else {
-                    maybe_char.unwrap()
-                }
+            let character = if let Some(character) = std::char::from_u32(codepoint as u32) {
+                character
+            } else {
+                continue;
             };
 
             let glyph = font.glyph(character);
@@ -490,19 +526,10 @@ impl BitmapFont {
                 continue;
             }
 
-            let glyph = BitmapGlyph::new(
-                &font,
-                font_name,
-                character,
-                font_height,
-                descent,
-                border_thickness as i32,
-                atlas_padding as i32,
-                color_glyph,
-                color_border,
-            );
+            let glyph =
+                BitmapGlyph::new(&font, font_name, character, font_height, descent, glyph_style);
 
-            glyphs.insert(codepoint as Codepoint, glyph);
+            glyphs.insert(index, glyph);
         }
 
         let horizontal_advance_max = glyphs
@@ -520,8 +547,8 @@ impl BitmapFont {
 
         BitmapFont {
             font_name: font_name.to_owned(),
-            font_height_in_pixels: font_height + 2 * border_thickness as i32,
-            vertical_advance: vertical_advance + 2 * border_thickness as i32,
+            font_height_in_pixels: font_height + 2 * border_thickness,
+            vertical_advance: vertical_advance + 2 * border_thickness,
             // NOTE: We don't add the border thickness here because it makes the text look
             //       too spaced apart
             horizontal_advance_max,
@@ -569,10 +596,7 @@ impl BitmapFont {
                 font_ttf_bytes,
                 font_height,
                 font_raster_offset,
-                0,
-                0,
-                PixelRGBA::black(),
-                PixelRGBA::transparent(),
+                GlyphStyle::default(),
             );
             bitmaps.push(
                 Bitmap::create_from_text(&font, &text, 1, PixelRGBA::white()).extended(
@@ -624,11 +648,14 @@ impl BitmapGlyph {
         codepoint: char,
         font_height: i32,
         descent: i32,
-        border_thickness: i32,
-        atlas_padding: i32,
-        color_glyph: PixelRGBA,
-        color_border: PixelRGBA,
+        glyph_style: GlyphStyle,
     ) -> BitmapGlyph {
+        let GlyphStyle {
+            border_thickness,
+            atlas_padding,
+            color_glyph,
+            color_border,
+        } = glyph_style;
         let glyph = font
             .glyph(codepoint)
             .scaled(rusttype::Scale::uniform(font_height as f32))
@@ -648,7 +675,8 @@ impl BitmapGlyph {
         if !is_effectively_zero(advance_width as f32 - h_metrics.advance_width)
             || !is_effectively_zero(left_side_bearing as f32 - h_metrics.left_side_bearing)
         {
-            log::warn!(
+            log::log!(
+                metrics_log_level(font_name),
                 "Horizontal metrics of pixelfont glyph '{}' are not whole numbered\nadvance_width: {}\nleft_side_bearing: {}",
                 h_metrics.advance_width,
                 BitmapFont::get_glyph_name(font_name, codepoint as Codepoint),
@@ -659,8 +687,8 @@ impl BitmapGlyph {
         let horizontal_advance = advance_width + border_thickness;
         // NOTE: The offset determines how many pixels the glyph-sprite needs to be offset
         //       from its origin (top-left corner) when drawn to the screen
-        let offset_x = left_side_bearing - atlas_padding as i32;
-        let mut offset_y = -(atlas_padding as i32);
+        let offset_x = left_side_bearing - atlas_padding;
+        let mut offset_y = -atlas_padding;
 
         let maybe_image = create_glyph_image(
             &glyph,
@@ -678,7 +706,7 @@ impl BitmapGlyph {
         }
 
         BitmapGlyph {
-            codepoint: codepoint,
+            codepoint,
             horizontal_advance,
             offset: Vec2i::new(offset_x, offset_y),
             bitmap: maybe_image,
@@ -696,8 +724,8 @@ fn create_glyph_image(
 ) -> Option<Bitmap> {
     glyph.pixel_bounding_box().map(|bounding_box| {
         let mut image = Bitmap::new_filled(
-            bounding_box.width() as u32 + 2 * u32::from(image_padding + border_thickness),
-            bounding_box.height() as u32 + 2 * u32::from(image_padding + border_thickness),
+            bounding_box.width() as u32 + 2 * (image_padding + border_thickness),
+            bounding_box.height() as u32 + 2 * (image_padding + border_thickness),
             PixelRGBA::new(0, 0, 0, 0),
         );
 
@@ -752,7 +780,7 @@ fn draw_glyph_border(image: &mut Bitmap, color_glyph: PixelRGBA, color_border: P
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
-/// Tests
+// Tests
 
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(test)]
@@ -765,10 +793,7 @@ mod tests {
             FONT_DEFAULT_TINY_TTF,
             FONT_DEFAULT_TINY_PIXEL_HEIGHT,
             FONT_DEFAULT_TINY_RASTER_OFFSET,
-            0,
-            0,
-            PixelRGBA::black(),
-            PixelRGBA::transparent(),
+            GlyphStyle::default(),
         )
     }
 
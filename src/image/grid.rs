@@ -139,10 +139,14 @@ where
         &mut self.data[(x + y * self.width) as usize]
     }
 
+    /// # Safety
+    /// The given coordinate must be contained in the grid
     #[inline]
     pub unsafe fn get_unchecked(&self, x: i32, y: i32) -> CellType {
         *self.data.get_unchecked((x + y * self.width) as usize)
     }
+    /// # Safety
+    /// The given coordinate must be contained in the grid
     #[inline]
     pub unsafe fn get_unchecked_mut(&mut self, x: i32, y: i32) -> &mut CellType {
         self.data.get_unchecked_mut((x + y * self.width) as usize)
@@ -152,6 +156,8 @@ where
     pub fn set(&mut self, x: i32, y: i32, value: CellType) {
         self.data[(x + y * self.width) as usize] = value
     }
+    /// # Safety
+    /// The given coordinate must be contained in the grid
     #[inline]
     pub unsafe fn set_unchecked(&mut self, x: i32, y: i32, value: CellType) {
         let cell = self.data.get_unchecked_mut((x + y * self.width) as usize);
@@ -488,7 +494,7 @@ where
         if let Some(crop_rect) = self.rect().clipped_by(crop_rect) {
             let mut result = Grid::new(crop_rect.width() as u32, crop_rect.height() as u32);
             let dest_rect = result.rect();
-            Grid::copy_region(&self, crop_rect, &mut result, dest_rect);
+            Grid::copy_region(self, crop_rect, &mut result, dest_rect);
 
             result
         } else {
@@ -757,7 +763,7 @@ where
             return a.clone();
         }
 
-        let result = match glue_position {
+        match glue_position {
             GluePosition::LeftTop => {
                 if a.height > b.height {
                     // NOTE: We use symmetry to avoid additional padding and adjusting
@@ -942,9 +948,7 @@ where
                     result
                 }
             }
-        };
-
-        result
+        }
     }
 
     pub fn floodfill(&mut self, start_x: i32, start_y: i32, fill_cell: CellType) {
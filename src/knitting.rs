@@ -0,0 +1,184 @@
+use crate::core::serde_derive::{Deserialize, Serialize};
+use crate::craft::Craft;
+use crate::image::{Bitmap, PixelRGBA};
+use crate::math::clampf;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Constants
+
+/// Yarn used by a single stitch relative to the sum of its width and height.
+///
+/// NOTE: These are rough estimates for stockinette and single crochet. Real yarn usage depends on
+///       the yarn, the needles or hook and the tension of the maker, which is why we add spare yarn.
+const KNIT_YARN_LENGTH_FACTOR: f32 = 2.6;
+const CROCHET_YARN_LENGTH_FACTOR: f32 = 4.2;
+const CENTIMETERS_PER_YARD: f32 = 91.44;
+
+const COLOR_KNIT_BACKGROUND: PixelRGBA = PixelRGBA::new(244, 244, 244, 255);
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Knitting setup
+
+/// Determines the gauge and yarn of knitting and crochet patterns
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KnittingSetup {
+    /// Stitches in 10 cm of the swatch, measured horizontally
+    pub stitches_per_10cm: f32,
+    /// Rows in 10 cm of the swatch, measured vertically
+    pub rows_per_10cm: f32,
+    /// Extra yarn on top of the estimate, i.e. 15 means 15% more yarn than estimated
+    pub spare_percent: f32,
+    /// Palette file with one `NAME,R,G,B` yarn color per line that replaces the DMC floss colors.
+    /// See `load_palette_file`.
+    pub palette_filepath: Option<String>,
+}
+
+impl Default for KnittingSetup {
+    fn default() -> KnittingSetup {
+        KnittingSetup {
+            stitches_per_10cm: 20.0,
+            rows_per_10cm: 28.0,
+            spare_percent: 15.0,
+            palette_filepath: None,
+        }
+    }
+}
+
+impl KnittingSetup {
+    /// Height of a stitch relative to its width, i.e. 0.71 for 20 stitches and 28 rows
+    pub fn stitch_aspect(&self) -> f32 {
+        self.stitches_per_10cm / self.rows_per_10cm
+    }
+
+    /// Resizes an image with square pixels so that it keeps its proportions when every pixel
+    /// becomes a stitch. The width stays the same while the row count follows the gauge.
+    pub fn resized_to_gauge(&self, image: &Bitmap) -> Bitmap {
+        let row_count = ((image.height as f32 / self.stitch_aspect()).round() as u32).max(1);
+        if row_count == image.height as u32 {
+            image.clone()
+        } else {
+            image.scaled_sample_nearest_neighbor(image.width as u32, row_count)
+        }
+    }
+
+    /// Returns the width and height of the finished piece in centimeters
+    pub fn finished_size_cm(&self, stitch_count: i32, row_count: i32) -> (f32, f32) {
+        (
+            10.0 * stitch_count as f32 / self.stitches_per_10cm,
+            10.0 * row_count as f32 / self.rows_per_10cm,
+        )
+    }
+
+    /// Estimates the yarn needed for the given number of stitches including spare yarn. Returns
+    /// meters and yards.
+    pub fn yarn_length(&self, craft: Craft, stitch_count: usize) -> (f32, f32) {
+        let length_factor = if craft == Craft::Crochet {
+            CROCHET_YARN_LENGTH_FACTOR
+        } else {
+            KNIT_YARN_LENGTH_FACTOR
+        };
+        let (stitch_width_cm, stitch_height_cm) = self.finished_size_cm(1, 1);
+        let length_cm = stitch_count as f32
+            * length_factor
+            * (stitch_width_cm + stitch_height_cm)
+            * (1.0 + self.spare_percent / 100.0);
+        (length_cm / 100.0, length_cm / CENTIMETERS_PER_YARD)
+    }
+}
+
+/// Rows are numbered from the bottom starting at 1. Odd rows are right side rows which are
+/// worked from right to left.
+pub fn is_right_side_row(row_number: i32) -> bool {
+    row_number % 2 == 1
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Preview tiles
+
+/// Creates a white knit stitch made of two slanted legs and its luminance for the stitch preview.
+/// The tiles are square and get squashed to the gauge together with the whole preview.
+pub fn create_knit_stitch_tiles(tile_size: i32) -> (Bitmap, Bitmap) {
+    let mut stitch =
+        Bitmap::new_filled(tile_size as u32, tile_size as u32, PixelRGBA::transparent());
+    let mut luminance = stitch.clone();
+
+    // NOTE: Each leg is an ellipse running from the top outer corner to the bottom center. The
+    //       legs overlap the tiles of the rows above and below a bit, just like real stitches.
+    let leg_length_half = 0.56;
+    let leg_width_half = 0.2;
+    for y in 0..tile_size {
+        for x in 0..tile_size {
+            let offset_x = (x as f32 + 0.5) / tile_size as f32 - 0.5;
+            let offset_y = (y as f32 + 0.5) / tile_size as f32 - 0.5;
+
+            let mut coverage: f32 = 0.0;
+            let mut brightness: f32 = 0.0;
+            for side in [-1.0, 1.0] {
+                let (direction_x, direction_y) = (-side * 0.38, 0.92);
+                let leg_x = offset_x - side * 0.22;
+                let along = leg_x * direction_x + offset_y * direction_y;
+                let across = -leg_x * direction_y + offset_y * direction_x;
+                let distance = (along / leg_length_half).powi(2) + (across / leg_width_half).powi(2);
+                if distance >= 1.0 {
+                    continue;
+                }
+
+                // NOTE: The yarn is a tube that is brightest along its middle and darker towards
+                //       the bottom where it disappears into the row below
+                let edge_falloff = 1.0 - (across / leg_width_half).powi(2);
+                let leg_brightness = 0.35 + 0.45 * edge_falloff - 0.15 * along / leg_length_half;
+                let leg_coverage = clampf((1.0 - distance) * tile_size as f32 * 0.25, 0.0, 1.0);
+                if leg_coverage > coverage {
+                    coverage = leg_coverage;
+                    brightness = leg_brightness;
+                }
+            }
+            if coverage <= 0.0 {
+                continue;
+            }
+
+            let alpha = (255.0 * coverage).round() as u8;
+            let grey = (255.0 * clampf(brightness, 0.0, 1.0)).round() as u8;
+            stitch.set(x, y, PixelRGBA::new(255, 255, 255, alpha));
+            luminance.set(x, y, PixelRGBA::new(grey, grey, grey, alpha));
+        }
+    }
+
+    (stitch, luminance)
+}
+
+/// Creates a plain background tile for cells without stitches. The tile has the same size as the
+/// bundled fabric tile.
+pub fn create_knit_background_tile_8x8(tile_size: i32) -> Bitmap {
+    Bitmap::new_filled(tile_size as u32, tile_size as u32, COLOR_KNIT_BACKGROUND)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resizes_to_gauge_and_estimates_yarn() {
+        let setup = KnittingSetup {
+            stitches_per_10cm: 20.0,
+            rows_per_10cm: 30.0,
+            spare_percent: 0.0,
+            palette_filepath: None,
+        };
+        let image = Bitmap::new_filled(40, 20, PixelRGBA::white());
+        let resized = setup.resized_to_gauge(&image);
+        assert_eq!((resized.width, resized.height), (40, 30));
+        assert_eq!(setup.finished_size_cm(40, 30), (20.0, 10.0));
+
+        let (knit_meters, _) = setup.yarn_length(Craft::Knitting, 100);
+        let (crochet_meters, _) = setup.yarn_length(Craft::Crochet, 100);
+        assert!(knit_meters > 1.0 && knit_meters < crochet_meters);
+
+        assert!(is_right_side_row(1));
+        assert!(!is_right_side_row(2));
+    }
+}
@@ -0,0 +1,26 @@
+pub mod core;
+pub mod error;
+pub mod image;
+pub mod math;
+pub mod bricks;
+pub mod bundle;
+pub mod chart_style;
+pub mod color_mapping;
+pub mod craft;
+pub mod diamond_painting;
+pub mod fabric;
+pub mod fuse_beads;
+pub mod knitting;
+pub mod mockup;
+pub mod options;
+pub mod page_layout;
+pub mod paint_by_numbers;
+pub mod serve;
+pub mod stitch_images;
+pub mod symbol_assignment;
+pub mod written_instructions;
+
+pub use error::ConversionError;
+pub use image::Bitmap;
+pub use options::ConversionOptions;
+pub use stitch_images::{decode_image, ColorErrorStats, Converter, Pattern, PatternType, ThreadUsage};
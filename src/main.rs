@@ -1,9 +1,116 @@
-pub mod core;
-pub mod image;
-pub mod math;
-pub mod color_mapping;
-pub mod stitch_images;
+use launcher::core::platform::{init_logging, path_join, path_to_filename_without_extension, timer_current_time_seconds, timer_initialize};
+use launcher::options::get_commandline_options;
+use launcher::serve::serve;
+use launcher::stitch_images::{collect_image_filepaths, collect_last_modified_times, convert_image_files, get_executable_dir, log_summary_table, watch_image_files, LOG_FILENAME};
+use launcher::Converter;
 
+/// Converts all images given on the commandline and writes their patterns next to them. Given
+/// directories are searched for images. Images that fail to convert are reported and skipped. If
+/// any image failed we exit with code 1. In watch mode we keep running and convert images again
+/// whenever they change.
 fn main() {
-    stitch_images::make();
+    timer_initialize();
+    // NOTE: The logger is configured by the commandline, so we cannot use it for these errors
+    let commandline = get_commandline_options().unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    });
+
+    let logfile_path = path_join(&get_executable_dir(), LOG_FILENAME);
+    init_logging(
+        commandline.log_file.then_some(logfile_path.as_str()),
+        commandline.log_level,
+    )
+    .unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    });
+
+    if let Some(port) = commandline.serve_port {
+        let converter = Converter::new(commandline.conversion.clone()).unwrap_or_else(|error| {
+            log::error!("Cannot load resources: {}", error);
+            std::process::exit(1);
+        });
+        let upload_size_max_bytes =
+            (commandline.serve_upload_size_max_megabytes * 1024.0 * 1024.0) as usize;
+        let worker_count = commandline
+            .worker_count
+            .unwrap_or_else(rayon::current_num_threads);
+        if let Err(error) = serve(&converter, port, upload_size_max_bytes, worker_count) {
+            log::error!("{}", error);
+        }
+        std::process::exit(1);
+    }
+
+    let image_filepaths = collect_image_filepaths(
+        &commandline.image_filepaths,
+        commandline.glob_pattern.as_deref(),
+        commandline.recursive,
+    );
+    if image_filepaths.is_empty() && !commandline.watch {
+        log::error!("No GIF or PNG images found in the given files and directories");
+        std::process::exit(1);
+    }
+
+    // NOTE: Outputs are named after the image filename, so images from different directories
+    //       may clash
+    let mut output_names = std::collections::HashSet::new();
+    for image_filepath in &image_filepaths {
+        if !output_names.insert(path_to_filename_without_extension(image_filepath)) {
+            log::warn!(
+                "'{}' has the same name as another image - their outputs will overwrite each other",
+                image_filepath
+            );
+        }
+    }
+
+    // NOTE: We load resources only once, even in watch mode
+    let converter = Converter::new(commandline.conversion.clone()).unwrap_or_else(|error| {
+        log::error!("Cannot load resources: {}", error);
+        std::process::exit(1);
+    });
+
+    // NOTE: Rendering an image already uses rayon internally. By running everything in our own
+    //       pool the worker count also bounds the threads used for rendering.
+    let mut thread_pool_builder = rayon::ThreadPoolBuilder::new();
+    if let Some(worker_count) = commandline.worker_count {
+        thread_pool_builder = thread_pool_builder.num_threads(worker_count);
+    }
+    let thread_pool = thread_pool_builder
+        .build()
+        .unwrap_or_else(|error| {
+            log::error!("Cannot create worker threads: {}", error);
+            std::process::exit(1);
+        });
+
+    // NOTE: We take the times before converting so that we don't miss changes made meanwhile
+    let last_modified_times = collect_last_modified_times(&image_filepaths);
+    let results = convert_image_files(
+        &converter,
+        &thread_pool,
+        &image_filepaths,
+        commandline.zip_bundle,
+    );
+
+    log::info!(
+        "Conversion done in {:.1} secs",
+        timer_current_time_seconds()
+    );
+    if !image_filepaths.is_empty() {
+        log_summary_table(&image_filepaths, &results);
+    }
+
+    if commandline.watch {
+        watch_image_files(&converter, &thread_pool, &commandline, last_modified_times);
+    }
+
+    let failure_count = results.iter().filter(|result| result.is_err()).count();
+    if failure_count > 0 {
+        log::error!(
+            "{} of {} images failed to convert",
+            failure_count,
+            image_filepaths.len()
+        );
+        std::process::exit(1);
+    }
 }
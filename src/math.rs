@@ -36,8 +36,8 @@ use num_traits::Num;
 pub const PI: f32 = std::f32::consts::PI;
 pub const PI64: f64 = std::f64::consts::PI;
 
-pub const RADIANS_TO_DEGREE: f32 = 57.2957795;
-pub const DEGREE_TO_RADIANS: f32 = 1.0 / 57.2957795;
+pub const RADIANS_TO_DEGREE: f32 = 57.295_78;
+pub const DEGREE_TO_RADIANS: f32 = 1.0 / 57.295_78;
 
 pub trait Lerp: Copy {
     fn lerp_value(start: Self, end: Self, percent: f32) -> Self;
@@ -287,10 +287,10 @@ pub enum AlignmentVertical {
     Bottom,
 }
 
-impl std::convert::Into<Alignment> for AlignmentHorizontal {
+impl std::convert::From<AlignmentHorizontal> for Alignment {
     #[inline]
-    fn into(self) -> Alignment {
-        match self {
+    fn from(val: AlignmentHorizontal) -> Self {
+        match val {
             AlignmentHorizontal::Left => Alignment::Begin,
             AlignmentHorizontal::Center => Alignment::Center,
             AlignmentHorizontal::Right => Alignment::End,
@@ -298,10 +298,10 @@ impl std::convert::Into<Alignment> for AlignmentHorizontal {
     }
 }
 
-impl std::convert::Into<Alignment> for AlignmentVertical {
+impl std::convert::From<AlignmentVertical> for Alignment {
     #[inline]
-    fn into(self) -> Alignment {
-        match self {
+    fn from(val: AlignmentVertical) -> Self {
+        match val {
             AlignmentVertical::Top => Alignment::Begin,
             AlignmentVertical::Center => Alignment::Center,
             AlignmentVertical::Bottom => Alignment::End,
@@ -844,9 +844,7 @@ pub fn linear_motion_integrate(
 
 #[inline]
 fn vel_quantized(vel: f32, ticks_per_second: f32) -> f32 {
-    let result = if vel >= ticks_per_second {
-        round_to_multiple_of_target(vel, ticks_per_second)
-    } else if vel <= -ticks_per_second {
+    let result = if vel >= ticks_per_second || vel <= -ticks_per_second {
         round_to_multiple_of_target(vel, ticks_per_second)
     } else {
         round_to_multiple_of_target(vel, ticks_per_second / 2.0)
@@ -986,7 +984,7 @@ pub fn linear_motion_integrate_quantized_vel(
     let mut vel = *inout_vel;
     let mut pos = *inout_pos;
 
-    vel = vel + acc * deltatime;
+    vel += acc * deltatime;
     vel = clampf_absolute(vel, vel_max_abs);
     pos += vel_quantized(vel, ticks_per_second) * deltatime;
 
@@ -1020,7 +1018,7 @@ pub fn linear_motion_integrate_with_drag_quantized_vel(
         vel = add_or_zero_when_changing_sign(vel, acc_final * deltatime);
     } else {
         // Normal acceleration with possible velocity sign-flip
-        vel = vel + acc_final * deltatime;
+        vel += acc_final * deltatime;
     }
     vel = clampf_absolute(vel, vel_max_abs);
     pos += vel_quantized(vel, ticks_per_second) * deltatime;
@@ -1052,14 +1050,14 @@ pub fn linear_motion_integrate_with_drag_quantized_vel_v2(
         vel.x = add_or_zero_when_changing_sign(vel.x, acc_final.x * deltatime);
     } else {
         // Normal acceleration with possible velocity sign-flip
-        vel.x = vel.x + acc_final.x * deltatime;
+        vel.x += acc_final.x * deltatime;
     }
     if f32::abs(drag_final.y) > f32::abs(acc.y) {
         // Prevent velocity sign flips if we decelerate via drag forces
         vel.y = add_or_zero_when_changing_sign(vel.y, acc_final.y * deltatime);
     } else {
         // Normal acceleration with possible velocity sign-flip
-        vel.y = vel.y + acc_final.y * deltatime;
+        vel.y += acc_final.y * deltatime;
     }
     vel = Vec2::clamped_abs(vel, vel_max_abs);
     pos.x += vel_quantized(vel.x, ticks_per_second) * deltatime;
@@ -1234,7 +1232,7 @@ pub fn ease(percent: f32, easing_type: EasingType) -> f32 {
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
-/// Tests
+// Tests
 
 #[cfg(test)]
 mod tests {
@@ -365,7 +365,7 @@ pub fn intersections_line_circle(
     let t_min = (-b - discriminant) * recip_a;
     let t_max = (-b + discriminant) * recip_a;
 
-    let t_min_result = if 0.0 <= t_min && t_min <= 1.0 {
+    let t_min_result = if (0.0..=1.0).contains(&t_min) {
         let point = line.start + t_min * line_dir;
         let normal = (point - circle.center).normalized();
         Some(Intersection {
@@ -377,7 +377,7 @@ pub fn intersections_line_circle(
         None
     };
 
-    let t_max_result = if 0.0 <= t_max && t_max <= 1.0 {
+    let t_max_result = if (0.0..=1.0).contains(&t_max) {
         let point = line.start + t_max * line_dir;
         let normal = (point - circle.center).normalized();
         Some(Intersection {
@@ -418,7 +418,7 @@ pub fn intersection_line_line(a: Line, b: Line) -> Option<Intersection> {
         let time_b = Vec2::cross_z(diff_start_b_a, dir_a) / dir_a_x_dir_b;
 
         // Check if t in [0, 1] and u in [0, 1]
-        if time_a >= 0.0 && time_a <= 1.0 && time_b >= 0.0 && time_b <= 1.0 {
+        if (0.0..=1.0).contains(&time_a) && (0.0..=1.0).contains(&time_b) {
             let intersection = Intersection {
                 point: a.start + time_a * dir_a,
                 normal: dir_b.perpendicular().normalized(),
@@ -226,7 +226,7 @@ impl Mul<Vec4> for Mat4 {
 
 impl Mul<Mat4> for Mat4 {
     type Output = Mat4;
-    #[must_use]
+    
     #[inline]
     fn mul(self, rhs: Mat4) -> Mat4 {
         Mat4 {
@@ -26,7 +26,7 @@ impl Random {
         ElemType: Copy + Clone,
     {
         assert!(
-            slice.len() < std::u32::MAX as usize,
+            slice.len() < u32::MAX as usize,
             "Shufflebag only supports u32 sized containers"
         );
         let index = self.u32_bounded_exclusive(slice.len() as u32) as usize;
@@ -45,13 +45,13 @@ impl Random {
         self.generator.rand_float()
     }
 
-    /// Returns a uniformly distributed integer in [std::i32::MIN, std::i32::MAX]
+    /// Returns a uniformly distributed integer in [i32::MIN, i32::MAX]
     #[inline]
     pub fn i32(&mut self) -> i32 {
         self.generator.rand_i32()
     }
 
-    /// Returns a uniformly distributed integer in [0, std::u32::MAX]
+    /// Returns a uniformly distributed integer in [0, u32::MAX]
     #[inline]
     pub fn u32(&mut self) -> u32 {
         self.generator.rand_u32()
@@ -143,7 +143,7 @@ where
     #[inline]
     pub fn new(elems: Vec<ElemType>) -> Shufflebag<ElemType> {
         assert!(
-            elems.len() <= std::u32::MAX as usize,
+            elems.len() <= u32::MAX as usize,
             "Shufflebag only supports u32 sized containers"
         );
         let elem_count = elems.len();
@@ -159,7 +159,7 @@ where
 
         for (elem, count) in elems_and_counts {
             assert!(
-                *count <= std::u32::MAX as usize,
+                *count <= u32::MAX as usize,
                 "Shufflebag only supports u32 sized containers"
             );
             for _ in 0..*count {
@@ -168,7 +168,7 @@ where
         }
 
         assert!(
-            elems.len() <= std::u32::MAX as usize,
+            elems.len() <= u32::MAX as usize,
             "Shufflebag only supports u32 sized containers"
         );
         let elem_count = elems.len();
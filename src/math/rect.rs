@@ -518,6 +518,6 @@ impl Rect {
         let stretched_width = rect.width() * scale_factor;
         let stretched_height = rect.height() * scale_factor;
 
-        return Rect::from_pos_width_height(rect.pos, stretched_width, stretched_height);
+        Rect::from_pos_width_height(rect.pos, stretched_width, stretched_height)
     }
 }
@@ -466,7 +466,7 @@ impl Vec2 {
     {
         coordinates
             .iter()
-            .map(|&point| Vec2::from(point.into()).transformed(pivot, xform))
+            .map(|&point| point.into().transformed(pivot, xform))
             .collect()
     }
 
@@ -199,8 +199,8 @@ impl Add<i32> for Vec2i {
     #[inline]
     fn add(self, rhs: i32) -> Vec2i {
         Vec2i {
-            x: self.x + rhs as i32,
-            y: self.y + rhs as i32,
+            x: self.x + rhs,
+            y: self.y + rhs,
         }
     }
 }
@@ -209,8 +209,8 @@ impl Add<Vec2i> for i32 {
     #[inline]
     fn add(self, rhs: Vec2i) -> Vec2i {
         Vec2i {
-            x: self as i32 + rhs.x,
-            y: self as i32 + rhs.y,
+            x: self + rhs.x,
+            y: self + rhs.y,
         }
     }
 }
@@ -218,8 +218,8 @@ impl AddAssign<i32> for Vec2i {
     #[inline]
     fn add_assign(&mut self, rhs: i32) {
         *self = Vec2i {
-            x: self.x + rhs as i32,
-            y: self.y + rhs as i32,
+            x: self.x + rhs,
+            y: self.y + rhs,
         }
     }
 }
@@ -0,0 +1,261 @@
+use crate::core::serde_derive::{Deserialize, Serialize};
+use crate::error::ConversionError;
+use crate::image::{Bitmap, Color, ColorBlendMode, PixelRGBA};
+use crate::math::{clampf, Recti, Vec2, Vec2i};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Constants
+
+/// Fabric count used for mockups if no preview fabric count was given
+pub const MOCKUP_FABRIC_COUNT_DEFAULT: f32 = 14.0;
+/// Commonly sold embroidery hoop diameters
+const HOOP_DIAMETERS_INCHES: [f32; 9] = [4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 12.0, 14.0];
+/// Minimum distance between the corners of the design and the inner edge of a hoop
+const HOOP_DESIGN_MARGIN_INCHES: f32 = 0.3;
+/// Fabric that is visible around the design inside a frame's mat
+pub const FRAME_FABRIC_MARGIN_INCHES: f32 = 0.75;
+
+/// Diameter of the inner edge of the hoop in `mockup_hoop.png` in pixels
+const HOOP_IMAGE_INNER_DIAMETER: f32 = 1200.0;
+/// Center of the inner edge of the hoop in `mockup_hoop.png`
+const HOOP_IMAGE_CENTER: Vec2 = Vec2::new(685.0, 755.0);
+/// Resolution `mockup_frame.png` is drawn at, so that its frame and mat keep their physical size
+const FRAME_IMAGE_DPI: f32 = 200.0;
+/// Distance between the outer edge of `mockup_frame.png` and its window in pixels
+const FRAME_IMAGE_BORDER: i32 = 480;
+/// Size of the corners of `mockup_frame.png` in pixels. These reach a bit into the window so that
+/// the shadow the mat casts onto the fabric is not stretched with the window.
+const FRAME_IMAGE_CORNER_SIZE: i32 = 488;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Preview setup
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MockupType {
+    /// The design stretched in a wooden embroidery hoop
+    Hoop,
+    /// The design behind a mat in a simple wooden frame
+    Frame,
+}
+
+/// Determines how the stitched preview looks and the physical size of the real-scale preview
+/// and mockups
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreviewSetup {
+    /// Seed for picking stitch variants, jitter and hand-dyed fabric. The same seed always
+    /// results in the same preview
+    pub seed: u64,
+    /// Directory with stitch tiles `NAME.png` and their luminance `NAME_lum.png` that replace the
+    /// bundled tiles, i.e. for a different strand count
+    pub stitch_tiles_dir: Option<String>,
+    /// Maximum brightness deviation of a single stitch, i.e. 0.04 means up to 4% darker or lighter.
+    /// Disabled by default
+    pub color_jitter: f32,
+    /// Maximum rotation of a single stitch in degrees. Disabled by default
+    pub rotation_jitter_degrees: f32,
+    /// Renders an additional preview at the finished size of the design on fabric with this
+    /// many stitches per inch
+    pub fabric_count: Option<f32>,
+    /// Resolution the real-scale preview and mockups are rendered and printed at
+    pub dpi: f32,
+    pub mockups: Vec<MockupType>,
+}
+
+impl Default for PreviewSetup {
+    fn default() -> PreviewSetup {
+        PreviewSetup {
+            seed: 1234,
+            stitch_tiles_dir: None,
+            color_jitter: 0.0,
+            rotation_jitter_degrees: 0.0,
+            fabric_count: None,
+            dpi: 300.0,
+            mockups: Vec::new(),
+        }
+    }
+}
+
+impl MockupType {
+    /// Name of the resource image the design is composited into
+    pub fn image_filename(self) -> &'static str {
+        match self {
+            MockupType::Hoop => "mockup_hoop.png",
+            MockupType::Frame => "mockup_frame.png",
+        }
+    }
+}
+
+impl PreviewSetup {
+    pub const MOCKUP_NAMES: [&'static str; 2] = ["hoop", "frame"];
+
+    pub fn mockup_type_from_name(name: &str) -> Result<MockupType, String> {
+        match name {
+            "hoop" => Ok(MockupType::Hoop),
+            "frame" => Ok(MockupType::Frame),
+            _ => Err(format!(
+                "Unknown mockup '{}' - valid mockups are: {}",
+                name,
+                PreviewSetup::MOCKUP_NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Hoop
+
+/// Returns the smallest common hoop that fits a design of the given size
+pub fn hoop_diameter_inches(design_width_inches: f32, design_height_inches: f32) -> f32 {
+    let diameter_min = (design_width_inches * design_width_inches
+        + design_height_inches * design_height_inches)
+        .sqrt()
+        + 2.0 * HOOP_DESIGN_MARGIN_INCHES;
+    HOOP_DIAMETERS_INCHES
+        .iter()
+        .copied()
+        .find(|diameter| *diameter >= diameter_min)
+        .unwrap_or_else(|| diameter_min.ceil())
+}
+
+/// Places the center of the given stitched fabric into the hoop of `mockup_hoop.png` which is
+/// scaled to the given inner diameter. The fabric must be at least as big as the hoop.
+pub fn create_hoop_mockup(
+    hoop_image: &Bitmap,
+    fabric: &Bitmap,
+    diameter_inches: f32,
+    dpi: f32,
+) -> Result<Bitmap, ConversionError> {
+    let inner_radius = 0.5 * diameter_inches * dpi;
+    // NOTE: Scaling the fabric to the dpi may round away a pixel which is not visible
+    let diameter_min = (2.0 * inner_radius).floor() - 1.0;
+    if (fabric.width as f32) < diameter_min || (fabric.height as f32) < diameter_min {
+        return Err(ConversionError::InvalidOptions(format!(
+            "Fabric of {}x{} pixels is too small for a hoop of {} pixels",
+            fabric.width,
+            fabric.height,
+            2.0 * inner_radius
+        )));
+    }
+
+    let scale = 2.0 * inner_radius / HOOP_IMAGE_INNER_DIAMETER;
+    let hoop = scaled_premultiplied(&hoop_image.to_premultiplied_alpha(), scale);
+    let center = scale * HOOP_IMAGE_CENTER;
+    let fabric_offset_x = (0.5 * fabric.width as f32 - center.x).round() as i32;
+    let fabric_offset_y = (0.5 * fabric.height as f32 - center.y).round() as i32;
+
+    // NOTE: Only the fabric inside the inner ring is visible, the rest is stretched around it
+    let mut result = Bitmap::new(hoop.width as u32, hoop.height as u32);
+    for y in 0..result.height {
+        for x in 0..result.width {
+            let offset_x = x as f32 + 0.5 - center.x;
+            let offset_y = y as f32 + 0.5 - center.y;
+            let radius = (offset_x * offset_x + offset_y * offset_y).sqrt();
+            let fabric_coverage = clampf(inner_radius - radius + 0.5, 0.0, 1.0);
+            if fabric_coverage > 0.0 {
+                let fabric_x = i32::clamp(x + fabric_offset_x, 0, fabric.width - 1);
+                let fabric_y = i32::clamp(y + fabric_offset_y, 0, fabric.height - 1);
+                let fabric_color = Color::from_pixelrgba(fabric.get(fabric_x, fabric_y));
+                let color = Color {
+                    a: fabric_color.a * fabric_coverage,
+                    ..fabric_color
+                };
+                result.set(x, y, color.to_premultiplied_alpha().to_pixelrgba());
+            }
+        }
+    }
+
+    hoop.blit_to_alpha_blended_premultiplied(
+        &mut result,
+        Vec2i::zero(),
+        false,
+        ColorBlendMode::Normal,
+    );
+    Ok(result.to_unpremultiplied_alpha())
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Frame
+
+/// Surrounds the given stitched fabric with the mat and frame of `mockup_frame.png`. The corners
+/// of the frame image are kept as they are while its sides are stretched to fit the fabric.
+pub fn create_frame_mockup(frame_image: &Bitmap, fabric: &Bitmap, dpi: f32) -> Bitmap {
+    let scale = dpi / FRAME_IMAGE_DPI;
+    let frame = scaled_premultiplied(&frame_image.to_premultiplied_alpha(), scale);
+    let border = (FRAME_IMAGE_BORDER as f32 * scale).round() as i32;
+    let corner_size = (FRAME_IMAGE_CORNER_SIZE as f32 * scale).round() as i32;
+
+    let mut result = fabric
+        .extended(border, border, border, border, PixelRGBA::transparent())
+        .to_premultiplied_alpha();
+    let source_spans = |length: i32| {
+        [
+            (0, corner_size),
+            (corner_size, length - corner_size),
+            (length - corner_size, length),
+        ]
+    };
+    let sources_x = source_spans(frame.width);
+    let sources_y = source_spans(frame.height);
+    let destinations_x = source_spans(result.width);
+    let destinations_y = source_spans(result.height);
+    for (index_y, (source_y, destination_y)) in sources_y.iter().zip(&destinations_y).enumerate() {
+        for (index_x, (source_x, destination_x)) in
+            sources_x.iter().zip(&destinations_x).enumerate()
+        {
+            // NOTE: The center of the frame image is the window which shows the fabric
+            if index_x == 1 && index_y == 1 {
+                continue;
+            }
+            let destination_width = destination_x.1 - destination_x.0;
+            let destination_height = destination_y.1 - destination_y.0;
+            if destination_width <= 0 || destination_height <= 0 {
+                continue;
+            }
+            let piece = frame.cropped_by_rect(Recti::from_xy_width_height(
+                source_x.0,
+                source_y.0,
+                source_x.1 - source_x.0,
+                source_y.1 - source_y.0,
+            ));
+            let piece = if piece.dim() == Vec2i::new(destination_width, destination_height) {
+                piece
+            } else {
+                piece.scaled_bilinear(destination_width as u32, destination_height as u32)
+            };
+            piece.blit_to_alpha_blended_premultiplied(
+                &mut result,
+                Vec2i::new(destination_x.0, destination_y.0),
+                false,
+                ColorBlendMode::Normal,
+            );
+        }
+    }
+    result.to_unpremultiplied_alpha()
+}
+
+/// Scales a premultiplied image by the given factor with as little blur as possible
+fn scaled_premultiplied(image: &Bitmap, scale: f32) -> Bitmap {
+    let width = i32::max(1, (scale * image.width as f32).round() as i32) as u32;
+    let height = i32::max(1, (scale * image.height as f32).round() as i32) as u32;
+    if scale < 1.0 {
+        image.scaled_area_average(width, height)
+    } else {
+        image.scaled_bilinear(width, height)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hoop_diameter_fits_design_diagonal() {
+        assert_eq!(hoop_diameter_inches(2.0, 2.0), 4.0);
+        assert_eq!(hoop_diameter_inches(4.0, 3.0), 6.0);
+        assert_eq!(hoop_diameter_inches(12.0, 9.0), 16.0);
+    }
+}
@@ -0,0 +1,598 @@
+use std::collections::HashMap;
+
+use crate::bricks::BrickSetup;
+use crate::chart_style::ChartStyle;
+use crate::color_mapping::ColorMetric;
+use crate::core::platform::read_file_whole;
+use crate::core::serde_derive::{Deserialize, Serialize};
+use crate::craft::Craft;
+use crate::diamond_painting::DiamondSetup;
+use crate::fabric::Fabric;
+use crate::fuse_beads::BeadSetup;
+use crate::knitting::KnittingSetup;
+use crate::mockup::PreviewSetup;
+use crate::page_layout::PageSetup;
+use crate::paint_by_numbers::PaintByNumbersSetup;
+use crate::serve::{SERVE_PORT_DEFAULT, SERVE_UPLOAD_SIZE_MAX_MEGABYTES_DEFAULT};
+use crate::written_instructions::InstructionsSetup;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Constants
+
+/// Charts of bigger cells get too big to render even for small images
+pub const CELL_SIZE_MAX: i32 = 64;
+/// Neighbouring pages sharing more stitches than this is not useful for anyone
+pub const PAGE_OVERLAP_MAX: i32 = 100;
+/// Knitting gauges with stitches more than this many times wider than tall (or the other way
+/// round) would blow up the image when it is resized to the gauge
+pub const GAUGE_ASPECT_MAX: f32 = 5.0;
+/// The real-scale preview and mockups are rendered at this resolution, so it must stay sane
+pub const PREVIEW_DPI_MAX: f32 = 1200.0;
+/// Real fabrics have at least this many stitches per inch. Coarser counts would blow up the
+/// real-scale preview and mockups.
+pub const PREVIEW_FABRIC_COUNT_MIN: f32 = 1.0;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Metadata
+
+/// Describes the pattern on its cover page and in the footer of every chart and legend page
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PatternMetadata {
+    pub title: Option<String>,
+    pub designer: Option<String>,
+    pub copyright: Option<String>,
+    pub description: Option<String>,
+    pub website: Option<String>,
+    pub version: Option<String>,
+    pub date: Option<String>,
+}
+
+impl PatternMetadata {
+    /// Returns the short entries that fit into a page footer
+    pub fn footer_entries(&self) -> Vec<String> {
+        let mut result = Vec::new();
+        if let Some(title) = &self.title {
+            result.push(title.clone());
+        }
+        if let Some(designer) = &self.designer {
+            result.push(format!("by {}", designer));
+        }
+        if let Some(version) = &self.version {
+            result.push(format!("Version {}", version));
+        }
+        if let Some(date) = &self.date {
+            result.push(date.clone());
+        }
+        if let Some(copyright) = &self.copyright {
+            result.push(copyright.clone());
+        }
+        if let Some(website) = &self.website {
+            result.push(website.clone());
+        }
+        result
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Options
+
+/// Everything that determines how images are converted into patterns. These can be set via
+/// commandline arguments, a json config file or directly when using this crate as a library.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConversionOptions {
+    /// What the pattern is made with
+    pub craft: Craft,
+    /// TrueType font whose glyphs are used to generate additional cross stitch symbols
+    pub symbol_font_filepath: Option<String>,
+    /// PNG image of 16x16 symbol tiles (black on white) that replaces the bundled symbols
+    pub symbol_sheet_filepath: Option<String>,
+    /// Maps DMC thread numbers to symbol numbers (starting at 1) that must always be used for them
+    pub pinned_symbols: HashMap<String, usize>,
+    /// Used to find the closest thread color for every pixel and to measure the conversion error
+    pub color_metric: ColorMetric,
+    pub chart_style: ChartStyle,
+    /// Appends a legend of the colors used on each pattern part to its page
+    pub page_legends: bool,
+    pub page_setup: PageSetup,
+    pub metadata: PatternMetadata,
+    pub fabric: Fabric,
+    pub preview: PreviewSetup,
+    pub paint_by_numbers: PaintByNumbersSetup,
+    pub diamond_painting: DiamondSetup,
+    pub fuse_beads: BeadSetup,
+    pub knitting: KnittingSetup,
+    pub bricks: BrickSetup,
+    pub instructions: InstructionsSetup,
+}
+
+impl ConversionOptions {
+    /// Bead boards and baseplates always list the parts they need, so that every board can be
+    /// filled on its own
+    pub fn has_page_legends(&self) -> bool {
+        self.page_legends || matches!(self.craft, Craft::FuseBeads | Craft::Bricks)
+    }
+
+    /// Checks option combinations and values that cannot be rejected while parsing, i.e. because
+    /// they come from a config file or json. Returns a message for the user if an option is
+    /// invalid.
+    pub fn validate(&self) -> Result<(), String> {
+        let positive = |name: &str, value: f32| {
+            if value > 0.0 && value.is_finite() {
+                Ok(())
+            } else {
+                Err(format!("Option '{}' must be a positive number - got {}", name, value))
+            }
+        };
+        let non_negative = |name: &str, value: f32| {
+            if value >= 0.0 && value.is_finite() {
+                Ok(())
+            } else {
+                Err(format!("Option '{}' must be a number >= 0 - got {}", name, value))
+            }
+        };
+        let in_range = |name: &str, value: i32, min: i32, max: i32| {
+            if (min..=max).contains(&value) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "Option '{}' must be a whole number from {} to {} - got {}",
+                    name, min, max, value
+                ))
+            }
+        };
+
+        in_range("cell-size", self.chart_style.cell_size, 8, CELL_SIZE_MAX)?;
+        positive("cell-aspect", self.chart_style.cell_aspect)?;
+        in_range(
+            "major-grid-interval",
+            self.chart_style.major_grid_interval,
+            2,
+            i32::MAX,
+        )?;
+
+        let mut pinned_threads: HashMap<usize, &String> = HashMap::new();
+        for (thread, &symbol_number) in &self.pinned_symbols {
+            if symbol_number == 0 {
+                return Err(format!(
+                    "Cannot pin symbol 0 to DMC {} - symbol numbers start at 1",
+                    thread
+                ));
+            }
+            if let Some(other_thread) = pinned_threads.insert(symbol_number, thread) {
+                // NOTE: Hashmaps are not ordered, so we sort the threads for a stable message
+                let (first, second) = if other_thread < thread {
+                    (other_thread, thread)
+                } else {
+                    (thread, other_thread)
+                };
+                return Err(format!(
+                    "Symbol {} is pinned to both DMC {} and DMC {}",
+                    symbol_number, first, second
+                ));
+            }
+        }
+
+        if let Some((width_mm, height_mm)) = self.page_setup.paper_size_mm {
+            positive("paper width", width_mm)?;
+            positive("paper height", height_mm)?;
+        }
+        non_negative("paper-margin-mm", self.page_setup.margin_mm)?;
+        positive("print-dpi", self.page_setup.print_dpi)?;
+        in_range("page-overlap", self.page_setup.overlap, 0, PAGE_OVERLAP_MAX)?;
+
+        positive("preview-dpi", self.preview.dpi)?;
+        if self.preview.dpi > PREVIEW_DPI_MAX {
+            return Err(format!(
+                "Option 'preview-dpi' must not be bigger than {} - got {}",
+                PREVIEW_DPI_MAX, self.preview.dpi
+            ));
+        }
+        if let Some(fabric_count) = self.preview.fabric_count {
+            if fabric_count < PREVIEW_FABRIC_COUNT_MIN || !fabric_count.is_finite() {
+                return Err(format!(
+                    "Option 'preview-fabric-count' must be at least {} - got {}",
+                    PREVIEW_FABRIC_COUNT_MIN, fabric_count
+                ));
+            }
+        }
+        non_negative("color-jitter", self.preview.color_jitter)?;
+        non_negative("rotation-jitter", self.preview.rotation_jitter_degrees)?;
+
+        if self.paint_by_numbers.min_region_size == 0 {
+            return Err("Option 'pbn-min-region-size' must be a whole number >= 1 - got 0".to_owned());
+        }
+        non_negative("drill-spare-percent", self.diamond_painting.spare_percent)?;
+        in_range("pegboard-size", self.fuse_beads.pegboard_size, 4, i32::MAX)?;
+        positive("gauge-stitches", self.knitting.stitches_per_10cm)?;
+        positive("gauge-rows", self.knitting.rows_per_10cm)?;
+        let gauge_aspect = self.knitting.stitch_aspect();
+        if !(1.0 / GAUGE_ASPECT_MAX..=GAUGE_ASPECT_MAX).contains(&gauge_aspect) {
+            return Err(format!(
+                "A gauge of {} stitches and {} rows makes stitches more than {} times wider than tall or the other way round",
+                self.knitting.stitches_per_10cm, self.knitting.rows_per_10cm, GAUGE_ASPECT_MAX
+            ));
+        }
+        non_negative("yarn-spare-percent", self.knitting.spare_percent)?;
+        if !BrickSetup::BASEPLATE_SIZES.contains(&self.bricks.baseplate_size) {
+            return Err(format!(
+                "Option 'baseplate-size' must be one of the baseplate sizes {:?} - got {}",
+                BrickSetup::BASEPLATE_SIZES,
+                self.bricks.baseplate_size
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Applies a single `--name=value` or `--name` commandline argument
+    pub fn apply_commandline_argument(&mut self, argument: &str) -> Result<(), String> {
+        let (name, value) = split_commandline_argument(argument);
+
+        let value_or_error = || {
+            value
+                .map(|value| value.to_owned())
+                .ok_or_else(|| format!("Option '--{}' needs a value like '--{}=...'", name, name))
+        };
+
+        let integer_or_error = |min: i32| {
+            let value = value_or_error()?;
+            value
+                .parse::<i32>()
+                .ok()
+                .filter(|number| *number >= min)
+                .ok_or_else(|| {
+                    format!(
+                        "Option '--{}' needs a whole number >= {} - got '{}'",
+                        name, min, value
+                    )
+                })
+        };
+
+        let number_or_error = || {
+            let value = value_or_error()?;
+            value
+                .parse::<f32>()
+                .ok()
+                .filter(|number| *number > 0.0)
+                .ok_or_else(|| {
+                    format!(
+                        "Option '--{}' needs a positive number - got '{}'",
+                        name, value
+                    )
+                })
+        };
+
+        let non_negative_number_or_error = || {
+            let value = value_or_error()?;
+            value
+                .parse::<f32>()
+                .ok()
+                .filter(|number| *number >= 0.0)
+                .ok_or_else(|| {
+                    format!(
+                        "Option '--{}' needs a number >= 0 - got '{}'",
+                        name, value
+                    )
+                })
+        };
+
+        match name {
+            // NOTE: This replaces all options, see `get_commandline_options` for why this does not
+            //       reset options given before it
+            "config" => {
+                let config_filepath = value_or_error()?;
+                let config_content = read_file_whole(&config_filepath)?;
+                *self = serde_json::from_slice(&config_content).map_err(|error| {
+                    format!("Invalid config file '{}': {}", config_filepath, error)
+                })?
+            }
+            "symbol-font" => self.symbol_font_filepath = Some(value_or_error()?),
+            "symbol-sheet" => self.symbol_sheet_filepath = Some(value_or_error()?),
+            "pin-symbols" => {
+                // Example: `--pin-symbols=310:1,3713:4`
+                for pin in value_or_error()?.split(',') {
+                    let (thread, symbol_number) = pin
+                        .split_once(':')
+                        .ok_or_else(|| format!("Symbol pin '{}' must look like 'DMC:SYMBOL'", pin))?;
+                    let symbol_number = symbol_number
+                        .trim()
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|number| *number > 0)
+                        .ok_or_else(|| format!("Invalid symbol number in pin '{}'", pin))?;
+                    let thread = thread.trim().to_owned();
+                    let pinned_thread = self
+                        .pinned_symbols
+                        .iter()
+                        .find(|(other_thread, other_number)| {
+                            **other_number == symbol_number && **other_thread != thread
+                        });
+                    if let Some((other_thread, _)) = pinned_thread {
+                        return Err(format!(
+                            "Symbol {} is pinned to both DMC {} and DMC {}",
+                            symbol_number, other_thread, thread
+                        ));
+                    }
+                    self.pinned_symbols.insert(thread, symbol_number);
+                }
+            }
+            "craft" => self.craft = Craft::from_name(&value_or_error()?)?,
+            "color-metric" => self.color_metric = ColorMetric::from_name(&value_or_error()?)?,
+            // NOTE: This replaces the whole style, see `get_commandline_options` for why this does
+            //       not reset style options given before it
+            "chart-style" => self.chart_style = ChartStyle::from_preset_name(&value_or_error()?)?,
+            "cell-size" => self.chart_style.cell_size = integer_or_error(8)?,
+            "major-grid-interval" => self.chart_style.major_grid_interval = integer_or_error(2)?,
+            "page-legend" => self.page_legends = true,
+            "paper" => {
+                self.page_setup.paper_size_mm =
+                    Some(PageSetup::paper_size_from_name(&value_or_error()?)?)
+            }
+            "paper-margin-mm" => self.page_setup.margin_mm = number_or_error()?,
+            "print-dpi" => self.page_setup.print_dpi = number_or_error()?,
+            "page-overlap" => self.page_setup.overlap = integer_or_error(0)?,
+            "balanced-pages" => self.page_setup.balanced = true,
+            "title" => self.metadata.title = Some(value_or_error()?),
+            "designer" => self.metadata.designer = Some(value_or_error()?),
+            "copyright" => self.metadata.copyright = Some(value_or_error()?),
+            "description" => self.metadata.description = Some(value_or_error()?),
+            "website" => self.metadata.website = Some(value_or_error()?),
+            "pattern-version" => self.metadata.version = Some(value_or_error()?),
+            "date" => self.metadata.date = Some(value_or_error()?),
+            "fabric" => self.fabric.fabric_type = Fabric::fabric_type_from_name(&value_or_error()?)?,
+            "fabric-color" => self.fabric.color = Fabric::color_from_name(&value_or_error()?)?,
+            "hand-dyed" => self.fabric.hand_dyed = true,
+            "preview-seed" => {
+                let value = value_or_error()?;
+                self.preview.seed = value.parse::<u64>().map_err(|_| {
+                    format!("Option '--{}' needs a whole number >= 0 - got '{}'", name, value)
+                })?
+            }
+            "stitch-tiles" => self.preview.stitch_tiles_dir = Some(value_or_error()?),
+            "color-jitter" => self.preview.color_jitter = non_negative_number_or_error()?,
+            "rotation-jitter" => {
+                self.preview.rotation_jitter_degrees = non_negative_number_or_error()?
+            }
+            "preview-fabric-count" => self.preview.fabric_count = Some(number_or_error()?),
+            "preview-dpi" => self.preview.dpi = number_or_error()?,
+            "mockup" => {
+                // Example: `--mockup=hoop,frame`
+                for mockup_name in value_or_error()?.split(',') {
+                    let mockup = PreviewSetup::mockup_type_from_name(mockup_name.trim())?;
+                    if !self.preview.mockups.contains(&mockup) {
+                        self.preview.mockups.push(mockup);
+                    }
+                }
+            }
+            "pbn-min-region-size" => {
+                self.paint_by_numbers.min_region_size = integer_or_error(1)? as usize
+            }
+            "drill-shape" => {
+                self.diamond_painting.drill_shape =
+                    DiamondSetup::drill_shape_from_name(&value_or_error()?)?
+            }
+            "drill-spare-percent" => {
+                self.diamond_painting.spare_percent = non_negative_number_or_error()?
+            }
+            "drill-palette" => self.diamond_painting.palette_filepath = Some(value_or_error()?),
+            "bead-brand" => self.fuse_beads.brand = BeadSetup::brand_from_name(&value_or_error()?)?,
+            "pegboard-size" => self.fuse_beads.pegboard_size = integer_or_error(4)?,
+            "bead-palette" => self.fuse_beads.palette_filepath = Some(value_or_error()?),
+            "gauge-stitches" => self.knitting.stitches_per_10cm = number_or_error()?,
+            "gauge-rows" => self.knitting.rows_per_10cm = number_or_error()?,
+            "yarn-spare-percent" => self.knitting.spare_percent = non_negative_number_or_error()?,
+            "yarn-palette" => self.knitting.palette_filepath = Some(value_or_error()?),
+            "brick-part" => self.bricks.part = BrickSetup::part_from_name(&value_or_error()?)?,
+            "baseplate-size" => {
+                let size = integer_or_error(1)?;
+                if !BrickSetup::BASEPLATE_SIZES.contains(&size) {
+                    return Err(format!(
+                        "Option '--{}' needs one of the baseplate sizes {:?} - got '{}'",
+                        name,
+                        BrickSetup::BASEPLATE_SIZES,
+                        size
+                    ));
+                }
+                self.bricks.baseplate_size = size;
+            }
+            "optimize-runs" => self.bricks.optimize_runs = true,
+            "brick-palette" => self.bricks.palette_filepath = Some(value_or_error()?),
+            "instructions" => {
+                // Example: `--instructions=markdown,pdf`
+                for format_name in value_or_error()?.split(',') {
+                    let format = InstructionsSetup::format_from_name(format_name.trim())?;
+                    if !self.instructions.formats.contains(&format) {
+                        self.instructions.formats.push(format);
+                    }
+                }
+            }
+            "instruction-order" => {
+                self.instructions.order = InstructionsSetup::order_from_name(&value_or_error()?)?
+            }
+            "same-row-direction" => self.instructions.alternate_direction = false,
+            _ => return Err(format!("Unknown option '--{}'", name)),
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the name and optional value of a `--name=value` or `--name` commandline argument
+fn split_commandline_argument(argument: &str) -> (&str, Option<&str>) {
+    let argument = argument.trim_start_matches("--");
+    match argument.split_once('=') {
+        Some((name, value)) => (name, Some(value)),
+        None => (argument, None),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Commandline
+
+/// Everything given on the commandline. Besides the conversion options this contains settings
+/// that only concern running the executable.
+#[derive(Debug, Clone)]
+pub struct CommandlineOptions {
+    pub conversion: ConversionOptions,
+    pub log_level: log::Level,
+    /// Additionally writes all log messages into a file in the output directory
+    pub log_file: bool,
+    /// Only images matching this pattern are picked from given directories, i.e. `**/*.png`
+    pub glob_pattern: Option<String>,
+    /// Also picks images from subdirectories of given directories
+    pub recursive: bool,
+    /// Number of images converted in parallel. Uses all cores if not set.
+    pub worker_count: Option<usize>,
+    /// Packs all outputs of an image into a single zip file with a manifest
+    pub zip_bundle: bool,
+    /// Keeps running and converts images again whenever they change
+    pub watch: bool,
+    pub watch_interval_seconds: f32,
+    /// Runs a local HTTP service on this port instead of converting the given images
+    pub serve_port: Option<u16>,
+    pub serve_upload_size_max_megabytes: f32,
+    /// Image files or directories containing images
+    pub image_filepaths: Vec<String>,
+}
+
+impl Default for CommandlineOptions {
+    fn default() -> CommandlineOptions {
+        CommandlineOptions {
+            conversion: ConversionOptions::default(),
+            log_level: log::Level::Info,
+            log_file: false,
+            glob_pattern: None,
+            recursive: false,
+            worker_count: None,
+            zip_bundle: false,
+            watch: false,
+            watch_interval_seconds: 1.0,
+            serve_port: None,
+            serve_upload_size_max_megabytes: SERVE_UPLOAD_SIZE_MAX_MEGABYTES_DEFAULT,
+            image_filepaths: Vec::new(),
+        }
+    }
+}
+
+impl CommandlineOptions {
+    /// Applies a single commandline argument. Anything that is not an option (i.e. does not start
+    /// with `--`) is treated as an image filepath.
+    pub fn apply_commandline_argument(&mut self, argument: &str) -> Result<(), String> {
+        if !argument.starts_with("--") {
+            self.image_filepaths.push(argument.to_owned());
+            return Ok(());
+        }
+
+        let (name, value) = split_commandline_argument(argument);
+        match name {
+            "log-level" => {
+                // Example: `--log-level=debug`
+                let value = value.unwrap_or_default();
+                self.log_level = value.parse::<log::Level>().map_err(|_| {
+                    format!(
+                        "Option '--{}' needs one of 'error', 'warn', 'info', 'debug', 'trace' - got '{}'",
+                        name, value
+                    )
+                })?
+            }
+            "log-file" => self.log_file = true,
+            "glob" => {
+                // Example: `--glob=**/*_final.png`
+                let glob_pattern = value
+                    .ok_or_else(|| format!("Option '--{}' needs a value like '--{}=...'", name, name))?;
+                glob::Pattern::new(glob_pattern).map_err(|error| {
+                    format!("Invalid glob pattern '{}': {}", glob_pattern, error)
+                })?;
+                self.glob_pattern = Some(glob_pattern.to_owned());
+            }
+            "recursive" => self.recursive = true,
+            "jobs" => {
+                let value = value.unwrap_or_default();
+                let worker_count = value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|count| *count > 0)
+                    .ok_or_else(|| {
+                        format!("Option '--{}' needs a whole number >= 1 - got '{}'", name, value)
+                    })?;
+                self.worker_count = Some(worker_count);
+            }
+            "zip" => self.zip_bundle = true,
+            "watch" => self.watch = true,
+            "serve" => {
+                // Example: `--serve` or `--serve=8080`
+                self.serve_port = Some(match value {
+                    Some(port) => port.parse::<u16>().map_err(|_| {
+                        format!("Option '--{}' needs a port number - got '{}'", name, port)
+                    })?,
+                    None => SERVE_PORT_DEFAULT,
+                })
+            }
+            "max-upload-mb" => {
+                let value = value.unwrap_or_default();
+                self.serve_upload_size_max_megabytes = value
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|megabytes| *megabytes > 0.0)
+                    .ok_or_else(|| {
+                        format!("Option '--{}' needs a positive number - got '{}'", name, value)
+                    })?;
+            }
+            "watch-interval" => {
+                let value = value.unwrap_or_default();
+                self.watch_interval_seconds = value
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|seconds| *seconds > 0.0)
+                    .ok_or_else(|| {
+                        format!("Option '--{}' needs a positive number of seconds - got '{}'", name, value)
+                    })?;
+            }
+            _ => self.conversion.apply_commandline_argument(argument)?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the arguments the executable was started with. Returns a message for the user if an
+/// option is invalid or no images were given.
+pub fn get_commandline_options() -> Result<CommandlineOptions, String> {
+    let mut args: Vec<String> = std::env::args().collect();
+
+    // NOTE: The first argument is the executable path
+    args.remove(0);
+
+    // NOTE: Config files and presets replace all or a whole group of options, so we apply them
+    //       before all other arguments. This way `--cell-size=20 --chart-style=print` keeps the
+    //       cell size of 20 and options given next to a config file override the file.
+    let config_count = args
+        .iter()
+        .filter(|arg| split_commandline_argument(arg).0 == "config")
+        .count();
+    if config_count > 1 {
+        return Err("Option '--config' can only be given once".to_owned());
+    }
+    args.sort_by_key(|arg| match split_commandline_argument(arg).0 {
+        "config" => 0,
+        "chart-style" => 1,
+        _ => 2,
+    });
+
+    let mut options = CommandlineOptions::default();
+    for arg in args {
+        options.apply_commandline_argument(&arg)?;
+    }
+    options.conversion.validate()?;
+
+    if options.image_filepaths.is_empty() && options.serve_port.is_none() {
+        return Err(
+            "Please drag and drop one (or more) image(s) or directories onto the executable"
+                .to_owned(),
+        );
+    }
+
+    Ok(options)
+}
@@ -0,0 +1,266 @@
+use crate::core::serde_derive::{Deserialize, Serialize};
+use crate::math::{Recti, Vec2i};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Page setup
+
+const MILLIMETERS_PER_INCH: f32 = 25.4;
+
+/// Determines how a pattern is split into printable pages
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PageSetup {
+    /// Width and height of the paper in millimeters. If this is not set we split patterns into
+    /// parts of a fixed stitch count instead
+    pub paper_size_mm: Option<(f32, f32)>,
+    pub margin_mm: f32,
+    /// Resolution the pattern images are printed at. Together with the chart cell size this
+    /// determines the printed size of a stitch
+    pub print_dpi: f32,
+    /// Number of stitch rows/columns that neighbouring pages share
+    pub overlap: i32,
+    /// Splits the pattern into equally sized pages instead of filling all pages but the last
+    pub balanced: bool,
+}
+
+impl Default for PageSetup {
+    fn default() -> PageSetup {
+        PageSetup {
+            paper_size_mm: None,
+            margin_mm: 10.0,
+            print_dpi: 150.0,
+            overlap: 0,
+            balanced: false,
+        }
+    }
+}
+
+impl PageSetup {
+    pub const PAPER_NAMES: [&'static str; 5] = ["a3", "a4", "a5", "letter", "legal"];
+
+    /// Accepts either a paper name like `a4` or a custom size in millimeters like `200x250`
+    pub fn paper_size_from_name(paper_name: &str) -> Result<(f32, f32), String> {
+        match paper_name.to_lowercase().as_str() {
+            "a3" => Ok((297.0, 420.0)),
+            "a4" => Ok((210.0, 297.0)),
+            "a5" => Ok((148.0, 210.0)),
+            "letter" => Ok((215.9, 279.4)),
+            "legal" => Ok((215.9, 355.6)),
+            custom_size => {
+                let parsed_size = custom_size.split_once('x').and_then(|(width, height)| {
+                    let width = width.trim().parse::<f32>().ok()?;
+                    let height = height.trim().parse::<f32>().ok()?;
+                    if width > 0.0 && height > 0.0 {
+                        Some((width, height))
+                    } else {
+                        None
+                    }
+                });
+                parsed_size.ok_or_else(|| {
+                    format!(
+                        "Unknown paper '{}' - use one of {} or a size in millimeters like '200x250'",
+                        paper_name,
+                        PageSetup::PAPER_NAMES.join(", ")
+                    )
+                })
+            }
+        }
+    }
+
+    /// Returns how many stitches fit on a page horizontally and vertically if a paper size is
+    /// set. `cell_size` is the width and height of a stitch cell in pixels and `reserved_pixels` is
+    /// the space needed for everything but the stitch cells (labels, headers, ..). Fails if the
+    /// paper is too small to fit more than the overlap.
+    pub fn stitches_per_page(
+        &self,
+        cell_size: Vec2i,
+        reserved_pixels: Vec2i,
+    ) -> Result<Option<Vec2i>, String> {
+        let (paper_width_mm, paper_height_mm) = match self.paper_size_mm {
+            Some(paper_size_mm) => paper_size_mm,
+            None => return Ok(None),
+        };
+        let printable_pixels = |paper_length_mm: f32, reserved_pixels: i32| {
+            let printable_mm = paper_length_mm - 2.0 * self.margin_mm;
+            (printable_mm / MILLIMETERS_PER_INCH * self.print_dpi) as i32 - reserved_pixels
+        };
+        let stitches_x = printable_pixels(paper_width_mm, reserved_pixels.x) / cell_size.x;
+        let stitches_y = printable_pixels(paper_height_mm, reserved_pixels.y) / cell_size.y;
+        if stitches_x <= 2 * self.overlap || stitches_y <= 2 * self.overlap {
+            return Err(format!(
+                "The printable area of {}x{}mm paper with {}mm margins at {} DPI only fits {}x{} stitches which is too small for an overlap of {} stitches",
+                paper_width_mm,
+                paper_height_mm,
+                self.margin_mm,
+                self.print_dpi,
+                stitches_x.max(0),
+                stitches_y.max(0),
+                self.overlap,
+            ));
+        }
+        Ok(Some(Vec2i::new(stitches_x, stitches_y)))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Page segments
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageSegment {
+    /// Column and row of the page in the page overview
+    pub layout_index: Vec2i,
+    /// Stitches shown on this page
+    pub rect: Recti,
+    /// Stitches shown on this page that are not repeated on a neighbouring page
+    pub rect_unshared: Recti,
+    /// Stitches this page is responsible for, such that every stitch belongs to exactly one page.
+    /// This is used for stitch counts
+    pub rect_owned: Recti,
+}
+
+/// Splits an image into pages of at most `page_stitches` stitches where neighbouring pages share
+/// `overlap` rows/columns. Pages are ordered row by row.
+pub fn compute_page_segments(
+    image_dim: Vec2i,
+    page_stitches: Vec2i,
+    overlap: i32,
+    balanced: bool,
+) -> Vec<PageSegment> {
+    let spans_x = split_axis(image_dim.x, page_stitches.x, overlap, balanced);
+    let spans_y = split_axis(image_dim.y, page_stitches.y, overlap, balanced);
+
+    let mut result = Vec::new();
+    for (index_y, span_y) in spans_y.iter().enumerate() {
+        for (index_x, span_x) in spans_x.iter().enumerate() {
+            let rect_from_spans = |(left, right): (i32, i32), (top, bottom): (i32, i32)| {
+                Recti::from_xy_width_height(left, top, right - left, bottom - top)
+            };
+            result.push(PageSegment {
+                layout_index: Vec2i::new(index_x as i32, index_y as i32),
+                rect: rect_from_spans(span_x.window, span_y.window),
+                rect_unshared: rect_from_spans(span_x.unshared, span_y.unshared),
+                rect_owned: rect_from_spans(span_x.owned, span_y.owned),
+            });
+        }
+    }
+    result
+}
+
+/// Start (inclusive) and end (exclusive) stitch coordinates of a page along one axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct AxisSpan {
+    window: (i32, i32),
+    unshared: (i32, i32),
+    owned: (i32, i32),
+}
+
+fn split_axis(length: i32, page_length: i32, overlap: i32, balanced: bool) -> Vec<AxisSpan> {
+    assert!(length > 0);
+    assert!(overlap >= 0);
+    assert!(
+        page_length > 2 * overlap,
+        "A page of {} stitches is too small for an overlap of {} stitches",
+        page_length,
+        overlap
+    );
+
+    let page_count = if length <= page_length {
+        1
+    } else {
+        let step = page_length - overlap;
+        1 + (length - page_length + step - 1) / step
+    };
+
+    // NOTE: In balanced mode we use the smallest window size that still covers the whole length
+    //       with the same page count, so that the last page is not just a few stitches wide
+    let window_length = if balanced {
+        (length + (page_count - 1) * overlap + page_count - 1) / page_count
+    } else {
+        page_length
+    };
+    let step = window_length - overlap;
+
+    let windows: Vec<(i32, i32)> = (0..page_count)
+        .map(|index| {
+            let start = index * step;
+            (start, i32::min(start + window_length, length))
+        })
+        .collect();
+
+    windows
+        .iter()
+        .enumerate()
+        .map(|(index, &(start, end))| {
+            let previous_end = if index > 0 { windows[index - 1].1 } else { start };
+            let next_start = windows.get(index + 1).map(|next| next.0).unwrap_or(end);
+            AxisSpan {
+                window: (start, end),
+                unshared: (previous_end, next_start),
+                owned: (start, next_start),
+            }
+        })
+        .collect()
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn windows(length: i32, page_length: i32, overlap: i32, balanced: bool) -> Vec<(i32, i32)> {
+        split_axis(length, page_length, overlap, balanced)
+            .iter()
+            .map(|span| span.window)
+            .collect()
+    }
+
+    #[test]
+    fn split_axis_fills_pages() {
+        assert_eq!(windows(50, 60, 0, false), vec![(0, 50)]);
+        assert_eq!(windows(63, 60, 0, false), vec![(0, 60), (60, 63)]);
+        assert_eq!(windows(63, 60, 3, false), vec![(0, 60), (57, 63)]);
+    }
+
+    #[test]
+    fn split_axis_balanced_avoids_tiny_last_page() {
+        assert_eq!(windows(63, 60, 0, true), vec![(0, 32), (32, 63)]);
+        assert_eq!(windows(63, 60, 3, true), vec![(0, 33), (30, 63)]);
+        assert_eq!(windows(120, 60, 0, true), vec![(0, 60), (60, 120)]);
+    }
+
+    #[test]
+    fn split_axis_owned_spans_cover_length_once() {
+        let spans = split_axis(100, 30, 2, false);
+        let mut next_owned_start = 0;
+        for span in &spans {
+            assert_eq!(span.owned.0, next_owned_start);
+            assert!(span.unshared.0 >= span.window.0 && span.unshared.1 <= span.window.1);
+            next_owned_start = span.owned.1;
+        }
+        assert_eq!(next_owned_start, 100);
+    }
+
+    #[test]
+    fn stitches_per_page_rejects_overlap_larger_than_page() {
+        let mut page_setup = PageSetup {
+            paper_size_mm: Some((210.0, 297.0)),
+            ..PageSetup::default()
+        };
+        assert_eq!(
+            page_setup.stitches_per_page(Vec2i::filled(16), Vec2i::zero()),
+            Ok(Some(Vec2i::new(70, 102)))
+        );
+        assert_eq!(
+            page_setup.stitches_per_page(Vec2i::new(16, 32), Vec2i::zero()),
+            Ok(Some(Vec2i::new(70, 51)))
+        );
+
+        page_setup.overlap = 35;
+        assert!(page_setup.stitches_per_page(Vec2i::filled(16), Vec2i::zero()).is_err());
+
+        page_setup.paper_size_mm = None;
+        assert_eq!(page_setup.stitches_per_page(Vec2i::filled(16), Vec2i::zero()), Ok(None));
+    }
+}
@@ -0,0 +1,527 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::core::serde_derive::{Deserialize, Serialize};
+use crate::image::{Bitmap, BitmapFont, Color, GluePosition, Grid, PixelRGBA, TextAlignment};
+use crate::math::Vec2i;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Constants
+
+/// Marks transparent cells which belong to no region
+pub const NO_REGION: usize = usize::MAX;
+
+const COLOR_REGION_BOUNDARY: PixelRGBA = PixelRGBA::new(128, 128, 128, 255);
+const PALETTE_SWATCH_SIZE: i32 = 32;
+const PALETTE_ENTRY_PADDING: i32 = 8;
+
+/// Chamfer distances of a straight and a diagonal step, approximating 1 and sqrt(2)
+const DISTANCE_STRAIGHT: u32 = 3;
+const DISTANCE_DIAGONAL: u32 = 4;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Paint by numbers setup
+
+/// Determines how an image is split into paint by numbers regions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaintByNumbersSetup {
+    /// Regions with fewer cells than this are merged into the neighbouring region they share the
+    /// longest border with, which removes specks that are too small to paint
+    pub min_region_size: usize,
+}
+
+impl Default for PaintByNumbersSetup {
+    fn default() -> PaintByNumbersSetup {
+        PaintByNumbersSetup { min_region_size: 4 }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Regions
+
+/// A connected area of cells with the same color
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaintRegion {
+    pub color: PixelRGBA,
+    pub cell_count: usize,
+    /// The cell that is farthest away from the region's boundary. This is where its number goes.
+    pub label_pos: Vec2i,
+}
+
+/// An image split into paint by numbers regions
+#[derive(Debug, Clone)]
+pub struct PaintRegions {
+    /// Region index of every cell or NO_REGION for transparent cells
+    pub region_indices: Grid<usize>,
+    pub regions: Vec<PaintRegion>,
+}
+
+/// A paint color and the number that labels its regions
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaletteEntry {
+    /// Starts at 1
+    pub number: usize,
+    pub color: PixelRGBA,
+    /// DMC thread number of the color
+    pub thread: String,
+    pub region_count: usize,
+}
+
+impl PaintRegions {
+    /// Splits the given image into regions of 4-connected same colored cells. Regions smaller than
+    /// `min_region_size` are repeatedly merged into their neighbours until none are left, except
+    /// for regions that are surrounded by transparent cells only.
+    pub fn from_image(image: &Bitmap, min_region_size: usize) -> PaintRegions {
+        let mut colors = image.clone();
+        loop {
+            let (region_indices, region_colors, region_cells) = label_connected_regions(&colors);
+            if !merge_small_regions(
+                &region_indices,
+                &region_colors,
+                &region_cells,
+                min_region_size,
+                &mut colors,
+            ) {
+                let label_positions = find_most_interior_cells(&region_indices, &region_cells);
+                let regions = region_colors
+                    .iter()
+                    .zip(region_cells.iter())
+                    .zip(label_positions)
+                    .map(|((color, cells), label_pos)| PaintRegion {
+                        color: *color,
+                        cell_count: cells.len(),
+                        label_pos,
+                    })
+                    .collect();
+                return PaintRegions {
+                    region_indices,
+                    regions,
+                };
+            }
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.region_indices.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.region_indices.height
+    }
+
+    /// The image with every cell painted in the color of its region
+    pub fn to_bitmap(&self) -> Bitmap {
+        let mut result = Bitmap::new_filled(
+            self.width() as u32,
+            self.height() as u32,
+            PixelRGBA::transparent(),
+        );
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let region_index = self.region_indices.get(x, y);
+                if region_index != NO_REGION {
+                    result.set(x, y, self.regions[region_index].color);
+                }
+            }
+        }
+        result
+    }
+
+    /// Numbers the colors that are still used after merging in the order they are given.
+    /// `thread_of_color` returns the thread name of a color.
+    pub fn palette(
+        &self,
+        color_order: &[PixelRGBA],
+        thread_of_color: impl Fn(PixelRGBA) -> String,
+    ) -> Vec<PaletteEntry> {
+        let mut region_counts: HashMap<PixelRGBA, usize> = HashMap::new();
+        for region in &self.regions {
+            *region_counts.entry(region.color).or_insert(0) += 1;
+        }
+
+        color_order
+            .iter()
+            .filter_map(|color| region_counts.get(color).map(|count| (*color, *count)))
+            .enumerate()
+            .map(|(index, (color, region_count))| PaletteEntry {
+                number: index + 1,
+                color,
+                thread: thread_of_color(color),
+                region_count,
+            })
+            .collect()
+    }
+}
+
+/// Flood fills all 4-connected areas of the same opaque color. Returns the region index of every
+/// cell together with the color and the cells of every region.
+fn label_connected_regions(image: &Bitmap) -> (Grid<usize>, Vec<PixelRGBA>, Vec<Vec<Vec2i>>) {
+    let mut region_indices = Grid::new_filled(image.width as u32, image.height as u32, NO_REGION);
+    let mut region_colors = Vec::new();
+    let mut region_cells = Vec::new();
+
+    let mut queue = VecDeque::new();
+    for start_y in 0..image.height {
+        for start_x in 0..image.width {
+            let color = image.get(start_x, start_y);
+            if color.a == 0 || region_indices.get(start_x, start_y) != NO_REGION {
+                continue;
+            }
+
+            let region_index = region_colors.len();
+            let mut cells = Vec::new();
+            region_indices.set(start_x, start_y, region_index);
+            queue.push_back(Vec2i::new(start_x, start_y));
+            while let Some(pos) = queue.pop_front() {
+                cells.push(pos);
+                for neighbour in neighbours_4(pos) {
+                    if image.contains_coordinate(neighbour.x, neighbour.y)
+                        && region_indices.get(neighbour.x, neighbour.y) == NO_REGION
+                        && image.get(neighbour.x, neighbour.y) == color
+                    {
+                        region_indices.set(neighbour.x, neighbour.y, region_index);
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+            region_colors.push(color);
+            region_cells.push(cells);
+        }
+    }
+
+    (region_indices, region_colors, region_cells)
+}
+
+/// Recolors every region that is smaller than `min_region_size` with the color of the neighbour
+/// it shares the longest border with, smallest regions first. Returns false if nothing changed.
+fn merge_small_regions(
+    region_indices: &Grid<usize>,
+    region_colors: &[PixelRGBA],
+    region_cells: &[Vec<Vec2i>],
+    min_region_size: usize,
+    colors: &mut Bitmap,
+) -> bool {
+    // NOTE: Merged regions point to the region they were merged into. We resolve these chains to
+    //       find the region a cell currently belongs to.
+    let mut merged_into: Vec<usize> = (0..region_colors.len()).collect();
+    let mut sizes: Vec<usize> = region_cells.iter().map(|cells| cells.len()).collect();
+    let mut members: Vec<Vec<usize>> = (0..region_colors.len()).map(|index| vec![index]).collect();
+    let find_root = |merged_into: &[usize], mut index: usize| {
+        while merged_into[index] != index {
+            index = merged_into[index];
+        }
+        index
+    };
+
+    let mut small_regions: Vec<usize> = (0..region_colors.len())
+        .filter(|&index| sizes[index] < min_region_size)
+        .collect();
+    small_regions.sort_by_key(|&index| (sizes[index], index));
+
+    let mut changed = false;
+    for region in small_regions {
+        if sizes[region] >= min_region_size {
+            continue;
+        }
+
+        let mut border_lengths: HashMap<usize, usize> = HashMap::new();
+        for &member in &members[region] {
+            for &cell in &region_cells[member] {
+                for neighbour in neighbours_4(cell) {
+                    if !region_indices.contains_coordinate(neighbour.x, neighbour.y) {
+                        continue;
+                    }
+                    let neighbour_region = region_indices.get(neighbour.x, neighbour.y);
+                    if neighbour_region == NO_REGION {
+                        continue;
+                    }
+                    let neighbour_region = find_root(&merged_into, neighbour_region);
+                    if neighbour_region != region {
+                        *border_lengths.entry(neighbour_region).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+
+        // NOTE: On equal border lengths we prefer the bigger region, then the earlier one to stay
+        //       deterministic
+        let Some(target) = border_lengths
+            .iter()
+            .max_by_key(|(&index, &length)| (length, sizes[index], std::cmp::Reverse(index)))
+            .map(|(&index, _)| index)
+        else {
+            continue;
+        };
+
+        merged_into[region] = target;
+        sizes[target] += sizes[region];
+        let region_members = std::mem::take(&mut members[region]);
+        members[target].extend(region_members);
+        changed = true;
+    }
+
+    for (region, cells) in region_cells.iter().enumerate() {
+        let color = region_colors[find_root(&merged_into, region)];
+        for cell in cells {
+            colors.set(cell.x, cell.y, color);
+        }
+    }
+
+    changed
+}
+
+/// Returns the cell of every region with the greatest chamfer distance to the region's boundary.
+/// Ties are broken by the distance to the region's center of mass.
+fn find_most_interior_cells(region_indices: &Grid<usize>, region_cells: &[Vec<Vec2i>]) -> Vec<Vec2i> {
+    let width = region_indices.width;
+    let height = region_indices.height;
+    let same_region = |x: i32, y: i32, region: usize| {
+        region_indices.contains_coordinate(x, y) && region_indices.get(x, y) == region
+    };
+
+    // Cells touching another region or the image border have the smallest distance
+    let mut distances = Grid::new_filled(width as u32, height as u32, u32::MAX);
+    for y in 0..height {
+        for x in 0..width {
+            let region = region_indices.get(x, y);
+            if region != NO_REGION
+                && !neighbours_4(Vec2i::new(x, y)).iter().all(|pos| same_region(pos.x, pos.y, region))
+            {
+                distances.set(x, y, DISTANCE_STRAIGHT);
+            }
+        }
+    }
+
+    let forward_steps = [
+        (Vec2i::new(-1, 0), DISTANCE_STRAIGHT),
+        (Vec2i::new(0, -1), DISTANCE_STRAIGHT),
+        (Vec2i::new(-1, -1), DISTANCE_DIAGONAL),
+        (Vec2i::new(1, -1), DISTANCE_DIAGONAL),
+    ];
+    let mut relax = |x: i32, y: i32, direction: i32| {
+        let region = region_indices.get(x, y);
+        if region == NO_REGION {
+            return;
+        }
+        let mut distance = distances.get(x, y);
+        for (step, step_distance) in forward_steps {
+            let neighbour_x = x + direction * step.x;
+            let neighbour_y = y + direction * step.y;
+            if same_region(neighbour_x, neighbour_y, region) {
+                let neighbour_distance = distances.get(neighbour_x, neighbour_y);
+                distance = distance.min(neighbour_distance.saturating_add(step_distance));
+            }
+        }
+        distances.set(x, y, distance);
+    };
+    for y in 0..height {
+        for x in 0..width {
+            relax(x, y, 1);
+        }
+    }
+    for y in (0..height).rev() {
+        for x in (0..width).rev() {
+            relax(x, y, -1);
+        }
+    }
+
+    region_cells
+        .iter()
+        .map(|cells| {
+            let center_x = cells.iter().map(|cell| cell.x as i64).sum::<i64>() / cells.len() as i64;
+            let center_y = cells.iter().map(|cell| cell.y as i64).sum::<i64>() / cells.len() as i64;
+            *cells
+                .iter()
+                .max_by_key(|cell| {
+                    let offset_x = cell.x as i64 - center_x;
+                    let offset_y = cell.y as i64 - center_y;
+                    (
+                        distances.get(cell.x, cell.y),
+                        std::cmp::Reverse(offset_x * offset_x + offset_y * offset_y),
+                        std::cmp::Reverse((cell.y, cell.x)),
+                    )
+                })
+                .unwrap()
+        })
+        .collect()
+}
+
+fn neighbours_4(pos: Vec2i) -> [Vec2i; 4] {
+    [
+        Vec2i::new(pos.x - 1, pos.y),
+        Vec2i::new(pos.x + 1, pos.y),
+        Vec2i::new(pos.x, pos.y - 1),
+        Vec2i::new(pos.x, pos.y + 1),
+    ]
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Rendering
+
+/// Draws the outlines of all regions on white paper and places the palette number of every
+/// region at its label position
+pub fn render_regions_chart(
+    regions: &PaintRegions,
+    palette: &[PaletteEntry],
+    font: &BitmapFont,
+    cell_size: i32,
+) -> Bitmap {
+    let numbers: HashMap<PixelRGBA, usize> =
+        palette.iter().map(|entry| (entry.color, entry.number)).collect();
+    let region_at = |x: i32, y: i32| {
+        regions
+            .region_indices
+            .get_or_default(x, y, NO_REGION)
+    };
+
+    let mut chart = Bitmap::new_filled(
+        (cell_size * regions.width() + 1) as u32,
+        (cell_size * regions.height() + 1) as u32,
+        PixelRGBA::white(),
+    );
+
+    // NOTE: We check the edges between every cell and its left and top neighbour. The extra
+    //       column and row close the regions at the right and bottom border.
+    for y in 0..=regions.height() {
+        for x in 0..=regions.width() {
+            let region = region_at(x, y);
+            if y < regions.height() && region != region_at(x - 1, y) {
+                chart.draw_rect_filled(
+                    cell_size * x,
+                    cell_size * y,
+                    1,
+                    cell_size + 1,
+                    COLOR_REGION_BOUNDARY,
+                );
+            }
+            if x < regions.width() && region != region_at(x, y - 1) {
+                chart.draw_rect_filled(
+                    cell_size * x,
+                    cell_size * y,
+                    cell_size + 1,
+                    1,
+                    COLOR_REGION_BOUNDARY,
+                );
+            }
+        }
+    }
+
+    for region in &regions.regions {
+        let Some(number) = numbers.get(&region.color) else {
+            continue;
+        };
+        chart.draw_text_aligned_in_point(
+            font,
+            &number.to_string(),
+            1,
+            cell_size * region.label_pos + Vec2i::filled(cell_size / 2),
+            Vec2i::zero(),
+            Some(TextAlignment::centered(false, true)),
+        );
+    }
+
+    chart
+}
+
+/// Lists the numbered paint colors with a swatch and the matching thread
+pub fn render_palette(palette: &[PaletteEntry], font: &BitmapFont) -> Bitmap {
+    let entries: Vec<Bitmap> = palette
+        .iter()
+        .map(|entry| {
+            let mut number = Bitmap::new_filled(
+                PALETTE_SWATCH_SIZE as u32,
+                PALETTE_SWATCH_SIZE as u32,
+                PixelRGBA::white(),
+            );
+            number.draw_text_aligned_in_point(
+                font,
+                &entry.number.to_string(),
+                1,
+                Vec2i::filled(PALETTE_SWATCH_SIZE / 2),
+                Vec2i::zero(),
+                Some(TextAlignment::centered(false, true)),
+            );
+
+            let mut swatch = Bitmap::new_filled(
+                PALETTE_SWATCH_SIZE as u32,
+                PALETTE_SWATCH_SIZE as u32,
+                entry.color,
+            );
+            swatch.draw_rect(
+                0,
+                0,
+                PALETTE_SWATCH_SIZE,
+                PALETTE_SWATCH_SIZE,
+                PixelRGBA::from_color(Color::black()),
+            );
+
+            let description = Bitmap::create_from_text(
+                font,
+                &format!(
+                    " DMC {} - {} region{}",
+                    entry.thread,
+                    entry.region_count,
+                    if entry.region_count == 1 { "" } else { "s" }
+                ),
+                1,
+                PixelRGBA::white(),
+            );
+
+            let number_and_swatch =
+                swatch.glued_to(&number, GluePosition::RightCenter, 0, PixelRGBA::white());
+            description.glued_to(
+                &number_and_swatch,
+                GluePosition::RightCenter,
+                0,
+                PixelRGBA::white(),
+            )
+        })
+        .collect();
+
+    if entries.is_empty() {
+        return Bitmap::new_filled(1, 1, PixelRGBA::white());
+    }
+    Bitmap::glue_together_multiple(
+        &entries,
+        GluePosition::BottomLeft,
+        PALETTE_ENTRY_PADDING,
+        PixelRGBA::white(),
+    )
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_small_regions_into_longest_border_neighbour() {
+        let red = PixelRGBA::new(255, 0, 0, 255);
+        let blue = PixelRGBA::new(0, 0, 255, 255);
+        let green = PixelRGBA::new(0, 255, 0, 255);
+
+        // A single green speck inside a red area next to a blue column and a transparent cell
+        let mut image = Bitmap::new_filled(6, 5, red);
+        for y in 0..5 {
+            image.set(5, y, blue);
+        }
+        image.set(2, 2, green);
+        image.set(0, 0, PixelRGBA::transparent());
+
+        let regions = PaintRegions::from_image(&image, 4);
+        assert_eq!(regions.regions.len(), 2);
+        assert_eq!(regions.region_indices.get(0, 0), NO_REGION);
+
+        let red_region = &regions.regions[regions.region_indices.get(2, 2)];
+        assert_eq!(red_region.color, red);
+        assert_eq!(red_region.cell_count, 24);
+        assert_eq!(red_region.label_pos, Vec2i::new(2, 2));
+
+        let palette = regions.palette(&[green, blue, red], |_| "310".to_owned());
+        let numbers: Vec<(usize, PixelRGBA)> =
+            palette.iter().map(|entry| (entry.number, entry.color)).collect();
+        assert_eq!(numbers, vec![(1, blue), (2, red)]);
+    }
+}
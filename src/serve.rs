@@ -0,0 +1,461 @@
+use std::io::Read;
+
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::core::TimerScoped;
+use crate::error::ConversionError;
+use crate::options::ConversionOptions;
+use crate::stitch_images::{decode_image, Converter, PatternType};
+use crate::written_instructions::InstructionsSetup;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Constants
+
+/// NOTE: We only listen on localhost. The service is meant to sit behind a web backend.
+const SERVE_ADDRESS: &str = "127.0.0.1";
+pub const SERVE_PORT_DEFAULT: u16 = 8080;
+pub const SERVE_UPLOAD_SIZE_MAX_MEGABYTES_DEFAULT: f32 = 16.0;
+
+/// Limits the work of decoding and converting a single upload
+const SERVE_IMAGE_SIZE_MAX: i32 = 500;
+/// Limits the memory usage of a single request. Knitting gauges and the cell size of the options
+/// determine how big a chart gets, so this is checked after conversion.
+const SERVE_CHART_PIXEL_COUNT_MAX: i64 = 8000 * 8000;
+/// Same for the real-scale preview and the mockups whose size depends on the dpi and fabric count
+/// of the options
+const SERVE_PREVIEW_PIXEL_COUNT_MAX: i64 = 8000 * 8000;
+
+const SERVE_USAGE: &str = "\
+POST /convert
+    Converts an image into a pattern. The body is either a raw PNG or GIF image or
+    `multipart/form-data` with an `image` part and an optional `options` part which contains
+    conversion options as json. Options must not reference files on the server.
+
+    Query parameters:
+    file=zip|chart|legend|preview|cover|palette|instructions
+                                                  What to return, defaults to a zip with the same
+                                                  files and manifest as the `--zip` option
+    type=colorized|black_and_white|...            Pattern type for `file=chart`, defaults to colorized
+    format=text|markdown|pdf                      Format for `file=instructions`, defaults to markdown
+    centered=true                                 Labels the chart of `file=chart` relative to the
+                                                  image center
+    name=...                                      Title of the pattern and name of the returned file
+";
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Server
+
+/// Runs a HTTP server on localhost that converts uploaded images into patterns. The worker count
+/// limits how many requests are handled at the same time. This only returns if the server could
+/// not be started.
+pub fn serve(
+    converter: &Converter,
+    port: u16,
+    upload_size_max_bytes: usize,
+    worker_count: usize,
+) -> Result<(), String> {
+    let server = tiny_http::Server::http((SERVE_ADDRESS, port))
+        .map_err(|error| format!("Could not start server on port {}: {}", port, error))?;
+    log::info!(
+        "Serving on http://{}:{} with {} workers - press Ctrl+C to stop",
+        SERVE_ADDRESS,
+        port,
+        worker_count
+    );
+
+    // NOTE: Every worker handles one request at a time. Other requests wait until a worker is free.
+    std::thread::scope(|scope| {
+        for _ in 0..worker_count {
+            scope.spawn(|| {
+                for request in server.incoming_requests() {
+                    handle_request(request, converter, upload_size_max_bytes);
+                }
+            });
+        }
+    });
+
+    Ok(())
+}
+
+struct Reply {
+    content_type: &'static str,
+    /// Makes clients save the reply as file
+    filename: Option<String>,
+    data: Vec<u8>,
+}
+
+struct HttpError {
+    status_code: u16,
+    message: String,
+}
+
+impl HttpError {
+    fn new(status_code: u16, message: &str) -> HttpError {
+        HttpError {
+            status_code,
+            message: message.to_owned(),
+        }
+    }
+}
+
+impl From<ConversionError> for HttpError {
+    fn from(error: ConversionError) -> HttpError {
+        let status_code = match error {
+            ConversionError::UnsupportedImageFormat { .. } | ConversionError::FileLoad { .. } => 400,
+            ConversionError::NotEnoughSymbols { .. }
+            | ConversionError::InvalidPinnedSymbol { .. }
+            | ConversionError::InvalidOptions(_) => 422,
+            ConversionError::InvalidResources(_)
+            | ConversionError::FileWrite { .. }
+            | ConversionError::UnmappedColor(_) => 500,
+        };
+        HttpError::new(status_code, &error.to_string())
+    }
+}
+
+fn handle_request(mut request: Request, converter: &Converter, upload_size_max_bytes: usize) {
+    let request_description = format!("{} {}", request.method(), request.url());
+    let _timer = TimerScoped::new_scoped(&request_description, true);
+    log::info!("{}", request_description);
+
+    // NOTE: A bug in handling a single request must not take down the worker
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        route_request(&mut request, converter, upload_size_max_bytes)
+    }))
+    .unwrap_or_else(|_| Err(HttpError::new(500, "Internal error while converting the image")));
+    let response = match result {
+        Ok(reply) => {
+            let mut response = Response::from_data(reply.data)
+                .with_header(create_header("Content-Type", reply.content_type));
+            if let Some(filename) = reply.filename {
+                response = response.with_header(create_header(
+                    "Content-Disposition",
+                    &format!("attachment; filename=\"{}\"", filename),
+                ));
+            }
+            response
+        }
+        Err(error) => {
+            log::warn!(
+                "{} failed with {}: {}",
+                request_description,
+                error.status_code,
+                error.message
+            );
+            Response::from_data(error.message.into_bytes())
+                .with_status_code(error.status_code)
+                .with_header(create_header("Content-Type", "text/plain; charset=utf-8"))
+        }
+    };
+
+    if let Err(error) = request.respond(response) {
+        log::warn!("Could not respond to {}: {}", request_description, error);
+    }
+}
+
+fn route_request(
+    request: &mut Request,
+    converter: &Converter,
+    upload_size_max_bytes: usize,
+) -> Result<Reply, HttpError> {
+    let url = request.url().to_owned();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    match (request.method(), path) {
+        (Method::Get, "/") => Ok(Reply {
+            content_type: "text/plain; charset=utf-8",
+            filename: None,
+            data: SERVE_USAGE.as_bytes().to_vec(),
+        }),
+        (Method::Post, "/convert") => {
+            let (image_data, options_json) = read_upload(request, upload_size_max_bytes)?;
+            convert_upload(converter, &image_data, options_json.as_deref(), query)
+        }
+        _ => Err(HttpError::new(
+            404,
+            &format!("Unknown endpoint '{}' - see 'GET /' for usage", path),
+        )),
+    }
+}
+
+fn convert_upload(
+    converter: &Converter,
+    image_data: &[u8],
+    options_json: Option<&str>,
+    query: &str,
+) -> Result<Reply, HttpError> {
+    let query_value = |key: &str| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(pair_key, _)| *pair_key == key)
+            .map(|(_, value)| value)
+    };
+    let name = sanitized_filename(query_value("name").unwrap_or("pattern"));
+    let centered = query_value("centered") == Some("true");
+
+    // NOTE: Options that differ from the servers options need their own converter which shares
+    //       the resources that don't depend on them
+    let request_converter;
+    let converter = if let Some(options_json) = options_json {
+        let options: ConversionOptions = serde_json::from_str(options_json)
+            .map_err(|error| HttpError::new(400, &format!("Invalid options: {}", error)))?;
+        if options.symbol_font_filepath.is_some()
+            || options.symbol_sheet_filepath.is_some()
+            || options.preview.stitch_tiles_dir.is_some()
+            || options.diamond_painting.palette_filepath.is_some()
+            || options.fuse_beads.palette_filepath.is_some()
+            || options.knitting.palette_filepath.is_some()
+            || options.bricks.palette_filepath.is_some()
+        {
+            return Err(HttpError::new(400, "Options must not reference files on the server"));
+        }
+        options
+            .validate()
+            .map_err(|error| HttpError::new(422, &error))?;
+        request_converter = converter.with_options(options)?;
+        &request_converter
+    } else {
+        converter
+    };
+
+    let image = decode_image(image_data, &name)?;
+    if image.width > SERVE_IMAGE_SIZE_MAX || image.height > SERVE_IMAGE_SIZE_MAX {
+        return Err(HttpError::new(
+            413,
+            &format!(
+                "Image is {}x{} but must not be bigger than {}x{}",
+                image.width, image.height, SERVE_IMAGE_SIZE_MAX, SERVE_IMAGE_SIZE_MAX
+            ),
+        ));
+    }
+    let pattern = converter.convert(&image, &name)?;
+    let chart_style = &converter.options().chart_style;
+    let chart_pixel_count = pattern.image().width as i64
+        * chart_style.cell_size as i64
+        * pattern.image().height as i64
+        * chart_style.cell_height() as i64;
+    if chart_pixel_count > SERVE_CHART_PIXEL_COUNT_MAX {
+        return Err(HttpError::new(
+            413,
+            &format!(
+                "The chart of this {}x{} stitch pattern would be too big to render - use a smaller image or cell size",
+                pattern.image().width,
+                pattern.image().height
+            ),
+        ));
+    }
+
+    let png_reply = |bitmap: crate::Bitmap, suffix: &str| Reply {
+        content_type: "image/png",
+        filename: Some(format!("{}_{}.png", name, suffix)),
+        data: bitmap.encoded_as_png(),
+    };
+    match query_value("file").unwrap_or("zip") {
+        "zip" => {
+            // NOTE: Only the bundle contains the real-scale preview and the mockups
+            if pattern.real_scale_previews_pixel_count_max() > SERVE_PREVIEW_PIXEL_COUNT_MAX {
+                return Err(HttpError::new(
+                    413,
+                    &format!(
+                        "The real-scale preview of this {}x{} stitch pattern would be too big to render - use a smaller image, dpi or mockup, or a higher fabric count",
+                        pattern.image().width,
+                        pattern.image().height
+                    ),
+                ));
+            }
+            let bundle = pattern.render_bundle(&name)?;
+            Ok(Reply {
+                content_type: "application/zip",
+                filename: Some(name.clone() + ".zip"),
+                data: bundle,
+            })
+        }
+        "chart" => {
+            let pattern_type = PatternType::from_name(query_value("type").unwrap_or("colorized"))
+                .map_err(|error| HttpError::new(400, &error))?;
+            let chart = pattern.render_chart(pattern_type, centered)?;
+            Ok(png_reply(chart, &format!("chart_{}", pattern_type.name())))
+        }
+        "legend" => Ok(png_reply(pattern.render_legend(), "legend")),
+        "preview" => Ok(png_reply(pattern.render_preview()?, "preview")),
+        "cover" => Ok(png_reply(pattern.render_cover_page()?, "cover")),
+        "palette" => Ok(png_reply(pattern.render_paint_by_numbers_palette()?, "palette")),
+        "instructions" => {
+            let format = InstructionsSetup::format_from_name(query_value("format").unwrap_or("markdown"))
+                .map_err(|error| HttpError::new(400, &error))?;
+            Ok(Reply {
+                content_type: format.content_type(),
+                filename: Some(format!("{}_instructions.{}", name, format.file_extension())),
+                data: pattern.render_instructions(format),
+            })
+        }
+        file => Err(HttpError::new(400, &format!("Unknown file '{}'", file))),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Request parsing
+
+/// Returns the uploaded image data and the options json if any
+fn read_upload(
+    request: &mut Request,
+    upload_size_max_bytes: usize,
+) -> Result<(Vec<u8>, Option<String>), HttpError> {
+    let too_large_error = || {
+        HttpError::new(
+            413,
+            &format!("Upload must not be bigger than {} bytes", upload_size_max_bytes),
+        )
+    };
+    if request
+        .body_length()
+        .is_some_and(|length| length > upload_size_max_bytes)
+    {
+        return Err(too_large_error());
+    }
+
+    // NOTE: The body length is not known for chunked uploads, so we read at most one byte more
+    //       than allowed to detect oversized bodies
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(upload_size_max_bytes as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|error| HttpError::new(400, &format!("Could not read upload: {}", error)))?;
+    if body.len() > upload_size_max_bytes {
+        return Err(too_large_error());
+    }
+
+    let content_type = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Content-Type"))
+        .map(|header| header.value.as_str().to_owned())
+        .unwrap_or_default();
+    if !content_type.starts_with("multipart/form-data") {
+        return Ok((body, None));
+    }
+
+    let boundary = content_type
+        .split(';')
+        .filter_map(|parameter| parameter.trim().strip_prefix("boundary="))
+        .map(|boundary| boundary.trim_matches('"'))
+        .next()
+        .ok_or_else(|| HttpError::new(400, "Multipart upload has no boundary"))?;
+    let parts =
+        parse_multipart_form_data(&body, boundary).map_err(|error| HttpError::new(400, &error))?;
+
+    let mut image_data = None;
+    let mut options_json = None;
+    for (name, content) in parts {
+        match name.as_str() {
+            "image" => image_data = Some(content.to_vec()),
+            "options" => {
+                options_json = Some(String::from_utf8(content.to_vec()).map_err(|_| {
+                    HttpError::new(400, "Options must be utf-8 encoded json")
+                })?)
+            }
+            _ => {}
+        }
+    }
+    let image_data =
+        image_data.ok_or_else(|| HttpError::new(400, "Multipart upload has no 'image' part"))?;
+    Ok((image_data, options_json))
+}
+
+/// Returns the name and content of every part of a `multipart/form-data` body
+fn parse_multipart_form_data<'body>(
+    body: &'body [u8],
+    boundary: &str,
+) -> Result<Vec<(String, &'body [u8])>, String> {
+    let delimiter = format!("--{}", boundary);
+    let delimiter = delimiter.as_bytes();
+
+    let mut parts = Vec::new();
+    let mut remaining = match find_subslice(body, delimiter) {
+        Some(start) => &body[start + delimiter.len()..],
+        None => return Err("Multipart body does not contain its boundary".to_owned()),
+    };
+    loop {
+        // NOTE: The last delimiter is followed by `--`
+        if remaining.starts_with(b"--") {
+            return Ok(parts);
+        }
+        let part_end = find_subslice(remaining, delimiter)
+            .ok_or_else(|| "Multipart body is not terminated".to_owned())?;
+        let part = &remaining[..part_end];
+        remaining = &remaining[part_end + delimiter.len()..];
+
+        let part = part.strip_prefix(b"\r\n").unwrap_or(part);
+        let part = part.strip_suffix(b"\r\n").unwrap_or(part);
+        let headers_end = find_subslice(part, b"\r\n\r\n")
+            .ok_or_else(|| "Multipart part has no headers".to_owned())?;
+        let headers = String::from_utf8_lossy(&part[..headers_end]);
+        let content = &part[headers_end + 4..];
+
+        let name = headers
+            .lines()
+            .filter(|line| {
+                line.to_ascii_lowercase()
+                    .starts_with("content-disposition:")
+            })
+            .flat_map(|line| line.split(';'))
+            .filter_map(|parameter| parameter.trim().strip_prefix("name="))
+            .map(|name| name.trim_matches('"').to_owned())
+            .next()
+            .ok_or_else(|| "Multipart part has no name".to_owned())?;
+        parts.push((name, content));
+    }
+}
+
+fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn sanitized_filename(name: &str) -> String {
+    let result: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .collect();
+    if result.is_empty() {
+        "pattern".to_owned()
+    } else {
+        result
+    }
+}
+
+fn create_header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field.as_bytes(), value.as_bytes())
+        .unwrap_or_else(|_| panic!("Invalid header '{}: {}'", field, value))
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_multipart_form_data() {
+        let body = b"--XyZ\r\n\
+            Content-Disposition: form-data; name=\"options\"\r\n\
+            \r\n\
+            {\"page_legends\":true}\r\n\
+            --XyZ\r\n\
+            Content-Disposition: form-data; name=\"image\"; filename=\"cat.png\"\r\n\
+            Content-Type: image/png\r\n\
+            \r\n\
+            \x89PNG\r\n\x1a\n\r\n\
+            --XyZ--\r\n";
+
+        let parts = parse_multipart_form_data(body, "XyZ").unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].0, "options");
+        assert_eq!(parts[0].1, b"{\"page_legends\":true}");
+        assert_eq!(parts[1].0, "image");
+        assert_eq!(parts[1].1, b"\x89PNG\r\n\x1a\n");
+    }
+}
@@ -1,37 +1,225 @@
-use std::{fs::File, time::Instant};
+use std::fs::File;
 use std::collections::HashMap;
-use color_art::{distance, Color as ArtColor};
+use std::sync::OnceLock;
+use std::time::SystemTime;
 use gif::SetParameter;
 use indexmap::IndexMap;
 use rayon::prelude::*;
 
 use crate::core::platform::*;
-use crate::image::{Bitmap, BitmapFont, Codepoint, Color, ColorBlendMode, Font, GluePosition, PixelRGBA, TextAlignment, FONT_DEFAULT_REGULAR_NAME, FONT_DEFAULT_REGULAR_PIXEL_HEIGHT, FONT_DEFAULT_REGULAR_RASTER_OFFSET, FONT_DEFAULT_REGULAR_TTF, FONT_DEFAULT_TINY_NAME, FONT_DEFAULT_TINY_PIXEL_HEIGHT, FONT_DEFAULT_TINY_RASTER_OFFSET, FONT_DEFAULT_TINY_TTF};
-use crate::math::{block_centered_in_block, ceil_to_multiple_of_target_i32, floor_to_multiple_of_target_i32, make_even_upwards, AlignmentHorizontal, AlignmentVertical, Random, Vec2i};
+use crate::core::serde_derive::Serialize;
+use crate::bricks::{self, count_parts, create_baseplate_tile_8x8, create_part_tiles, BrickSetup, PartCounts, STUD_PITCH_MM};
+use crate::bundle::{create_pattern_bundle, Manifest, ManifestEntry, OutputFile};
+use crate::core::TimerScoped;
+use crate::image::{Bitmap, BitmapFont, Codepoint, Color, ColorBlendMode, Font, GlyphStyle, GluePosition, PixelRGBA, TextAlignment, FONT_DEFAULT_REGULAR_NAME, FONT_DEFAULT_REGULAR_PIXEL_HEIGHT, FONT_DEFAULT_REGULAR_RASTER_OFFSET, FONT_DEFAULT_REGULAR_TTF, FONT_DEFAULT_TINY_NAME, FONT_DEFAULT_TINY_PIXEL_HEIGHT, FONT_DEFAULT_TINY_RASTER_OFFSET, FONT_DEFAULT_TINY_TTF};
+use crate::math::{block_centered_in_block, ceil_to_multiple_of_target_i32, floor_to_multiple_of_target_i32, make_even_upwards, AlignmentHorizontal, AlignmentVertical, clampf, Random, Recti, Vec2, Vec2i};
 use crate::core::platform::{collect_files_by_extension_recursive, path_exists, path_join, path_to_extension, path_to_filename_without_extension, path_without_filename};
-use crate::color_mapping;
+use crate::chart_style::{ChartStyle, GridLabels, LabelFont};
+use crate::color_mapping::{self, ColorMetric};
+use crate::craft::Craft;
+use crate::diamond_painting::{self, canvas_size_cm, create_canvas_tile_8x8, create_drill_tiles, DiamondSetup, DRILL_PITCH_MM};
+use crate::error::ConversionError;
+use crate::fabric::{apply_hand_dyed_variation, create_fabric_tile_8x8, Fabric};
+use crate::fuse_beads::{self, create_bead_tiles, create_pegboard_tile_8x8, BeadSetup, BEAD_PITCH_MM};
+use crate::knitting::{create_knit_background_tile_8x8, create_knit_stitch_tiles, is_right_side_row, KnittingSetup};
+use crate::mockup::{create_frame_mockup, create_hoop_mockup, hoop_diameter_inches, MockupType, PreviewSetup, FRAME_FABRIC_MARGIN_INCHES, MOCKUP_FABRIC_COUNT_DEFAULT};
+use crate::symbol_assignment::{self, PinnedSymbol};
+use crate::options::{CommandlineOptions, ConversionOptions, PatternMetadata};
+use crate::page_layout::{compute_page_segments, PageSegment};
+use crate::paint_by_numbers::{render_palette, render_regions_chart, PaintRegions, PaletteEntry};
+use crate::written_instructions::{letter_key, InstructionFormat, Instructions};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 // Constants
 
+pub const LOG_FILENAME: &str = "pixie_stitch.log";
+
 const TILE_SIZE: i32 = 16;
+const SYMBOL_FONT_PIXEL_HEIGHT: i32 = TILE_SIZE - 4;
+const SYMBOL_GLYPH_PIXEL_COUNT_MIN: usize = 5;
 const LEGEND_BLOCK_ENTRY_COUNT: usize = 5;
+const LEGEND_PAGE_BLOCK_COLUMN_COUNT: usize = 4;
 const SPLIT_SEGMENT_WIDTH: i32 = 60;
 const SPLIT_SEGMENT_HEIGHT: i32 = 80;
-const COLOR_GRID_THIN: PixelRGBA = PixelRGBA::new(128, 128, 128, 255);
-const COLOR_GRID_THICK: PixelRGBA = PixelRGBA::new(64, 64, 64, 255);
-
-enum PatternType {
+/// Aida fabric counts (stitches per inch) to show the finished size for on the cover page
+const FABRIC_COUNTS: [i32; 4] = [11, 14, 16, 18];
+const COVER_PAGE_TEXT_WIDTH_MIN: i32 = 400;
+/// Unstitched fabric around the stitched preview
+const PREVIEW_MARGIN_STITCHES: i32 = 10;
+const COVER_PAGE_TEXT_WIDTH_MAX: i32 = 1000;
+/// Comparison panels of smaller images are scaled up to at least this size
+const COMPARISON_PANEL_SIZE_MIN: i32 = 256;
+const COMPARISON_HEATMAP_BAR_HEIGHT: i32 = 12;
+/// Number of differently rotated copies we create of every stitch tile for rotation jitter
+const STITCH_ROTATION_VARIANT_COUNT: usize = 5;
+const COLOR_OVERLAP_SHADE: PixelRGBA = PixelRGBA::new(96, 96, 96, 255);
+const OVERLAP_SHADE_PERCENT: f32 = 0.3;
+/// Thread colors lighter than this get an outline when drawn as symbols on white paper. This
+/// corresponds to a contrast ratio of about 3:1 against white
+const SYMBOL_COLOR_RELATIVE_LUMINANCE_MAX: f32 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternType {
     BlackAndWhite,
     Colorized,
     ColorizedNoSymbols,
+    ColoredSymbols,
     PaintByNumbers,
 }
 
+impl PatternType {
+    pub const ALL: [PatternType; 5] = [
+        PatternType::Colorized,
+        PatternType::BlackAndWhite,
+        PatternType::ColorizedNoSymbols,
+        PatternType::ColoredSymbols,
+        PatternType::PaintByNumbers,
+    ];
+
+    pub fn from_name(name: &str) -> Result<PatternType, String> {
+        PatternType::ALL
+            .iter()
+            .find(|pattern_type| pattern_type.name() == name)
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<&str> = PatternType::ALL.iter().map(|pattern_type| pattern_type.name()).collect();
+                format!(
+                    "Unknown pattern type '{}' - valid types are: {}",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PatternType::BlackAndWhite => "black_and_white",
+            PatternType::Colorized => "colorized",
+            PatternType::ColorizedNoSymbols => "colorized_no_symbols",
+            PatternType::ColoredSymbols => "colored_symbols",
+            PatternType::PaintByNumbers => "paint_by_numbers",
+        }
+    }
+
+    /// Chart files of this type are named `<image>_<prefix>complete.png` and
+    /// `<image>_<prefix>segment_<n>.png`. Centered charts have `centered_` after the prefix.
+    pub fn output_filename_prefix(self) -> &'static str {
+        match self {
+            PatternType::BlackAndWhite => "cross_stitch_",
+            PatternType::Colorized => "cross_stitch_colorized_",
+            PatternType::ColorizedNoSymbols => "cross_stitch_colorized_no_symbols_",
+            PatternType::ColoredSymbols => "cross_stitch_colored_symbols_",
+            PatternType::PaintByNumbers => "paint_by_numbers_",
+        }
+    }
+}
+
 struct Resources {
     font: BitmapFont,
     font_big: BitmapFont,
     stitch_background_image_8x8_premultiplied_alpha: Bitmap,
+    fabric: Fabric,
+    preview: PreviewSetup,
+    craft: Craft,
+    diamond_painting: DiamondSetup,
+    fuse_beads: BeadSetup,
+    knitting: KnittingSetup,
+    bricks: BrickSetup,
+    mockup_images: Vec<(MockupType, Bitmap)>,
+}
+
+impl Resources {
+    fn font_for_label(&self, label_font: LabelFont) -> &BitmapFont {
+        match label_font {
+            LabelFont::Regular => &self.font,
+            LabelFont::Big => &self.font_big,
+        }
+    }
+
+    /// Height of a stitch relative to its width. Only knitted and crocheted stitches are not square.
+    fn stitch_aspect(&self) -> f32 {
+        if self.craft.uses_gauge() {
+            self.knitting.stitch_aspect()
+        } else {
+            1.0
+        }
+    }
+
+    /// Describes how much material a color needs
+    fn material_count_text(&self, info: &ColorInfo) -> String {
+        let count = info.count;
+        match self.craft {
+            Craft::CrossStitch => format!("{} stitches", count),
+            Craft::DiamondPainting => format!(
+                "{} drills ({} with spare)",
+                count,
+                self.diamond_painting.drill_count_with_spare(count)
+            ),
+            Craft::FuseBeads => format!("{} beads", count),
+            Craft::Knitting | Craft::Crochet => {
+                let (meters, yards) = self.knitting.yarn_length(self.craft, count);
+                format!("{} stitches ({:.1} m / {:.1} yd)", count, meters, yards)
+            }
+            Craft::Bricks => {
+                let part_name = self.bricks.part.name_plural();
+                match info.brick_parts {
+                    Some(parts) if self.bricks.optimize_runs => format!(
+                        "{} studs as {} {} ({})",
+                        count,
+                        parts.total(),
+                        part_name,
+                        parts.to_text()
+                    ),
+                    _ => format!("{} 1x1 {}", count, part_name),
+                }
+            }
+        }
+    }
+
+    /// Returns how a palette color is sold, i.e. `DMC 310`
+    fn color_label(&self, color_name: &str) -> String {
+        match self.craft {
+            Craft::CrossStitch | Craft::DiamondPainting => format!("DMC {}", color_name),
+            Craft::FuseBeads => format!("{} {}", self.fuse_beads.brand.display_name(), color_name),
+            // NOTE: Yarn colors have no common numbering, so without a yarn palette we name the
+            //       closest floss color
+            Craft::Knitting | Craft::Crochet => match self.knitting.palette_filepath {
+                Some(_) => color_name.to_owned(),
+                None => format!("DMC {}", color_name),
+            },
+            Craft::Bricks => color_name.to_owned(),
+        }
+    }
+}
+
+/// A single page of a pattern that is split into multiple parts
+struct PatternPage {
+    /// Starts at 1
+    number: usize,
+    /// Stitches that are not repeated on a neighbouring page, relative to the page
+    unshaded_rect: Recti,
+    neighbour_page_numbers: NeighbourPageNumbers,
+    /// Colors used on this page
+    legend: Option<Bitmap>,
+}
+
+struct NeighbourPageNumbers {
+    left: Option<usize>,
+    top: Option<usize>,
+    right: Option<usize>,
+    bottom: Option<usize>,
+}
+
+/// Everything that is shared by all charts of a pattern, regardless of the page they show
+struct ChartSetup<'a> {
+    font_grid_label: &'a BitmapFont,
+    font_segment_index_indicator: &'a BitmapFont,
+    color_mappings: &'a IndexMap<PixelRGBA, ColorInfo>,
+    chart_style: &'a ChartStyle,
+    footer: Option<&'a Bitmap>,
+    add_major_grid: bool,
+    add_origin_grid_bars: bool,
+    symbol_mask_color: PixelRGBA,
 }
 
 #[derive(Clone)]
@@ -39,15 +227,18 @@ struct ColorInfo {
     pub color: PixelRGBA,
     pub count: usize,
     pub symbol: Bitmap,
-    pub symbol_alphanum: Bitmap,
+    /// Letters that identify the color in written instructions, see `letter_key`
+    pub key: String,
     pub stitches_premultiplied: Vec<Bitmap>,
+    /// Parts needed for this color, only used for brick mosaics
+    pub brick_parts: Option<PartCounts>,
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 // Paths
 
-fn get_executable_dir() -> String {
-    if let Some(executable_path) = std::env::current_exe().ok() {
+pub fn get_executable_dir() -> String {
+    if let Ok(executable_path) = std::env::current_exe() {
         path_without_filename(executable_path.to_string_borrowed_or_panic())
     } else {
         ".".to_owned()
@@ -68,43 +259,35 @@ fn get_image_output_dir(image_filepath: &str) -> String {
     path_join(&output_dir_root, &image_filename)
 }
 
-// NOTE: This is for quicker testing to keep images open in imageviewer
-#[cfg(debug_assertions)]
-fn create_image_output_dir(image_filepath: &str) {
-    use crate::core::platform::path_exists;
-
+fn create_image_output_dir(image_filepath: &str) -> Result<(), ConversionError> {
     let output_dir = get_image_output_dir(image_filepath);
-    if !path_exists(&output_dir) {
-        std::fs::create_dir_all(&output_dir)
-            .expect(&format!("Cannot create directory '{}'", &output_dir));
-    }
+    std::fs::create_dir_all(&output_dir).map_err(|error| ConversionError::FileWrite {
+        filepath: output_dir.clone(),
+        reason: error.to_string(),
+    })
 }
 
-fn get_image_output_filepath(image_filepath: &str) -> String {
-    let output_dir = get_image_output_dir(image_filepath);
-    let image_filename = path_to_filename_without_extension(image_filepath);
-    path_join(&output_dir, &image_filename)
+fn write_output_file(filepath: &str, data: &[u8]) -> Result<(), ConversionError> {
+    std::fs::write(filepath, data).map_err(|error| ConversionError::FileWrite {
+        filepath: filepath.to_owned(),
+        reason: error.to_string(),
+    })
 }
 
-
-fn get_image_filepaths_from_commandline() -> Vec<String> {
-    let mut args: Vec<String> = std::env::args().collect();
-
-    // NOTE: The first argument is the executable path
-    args.remove(0);
-
-    assert!(
-        !args.is_empty(),
-        "Please drag and drop one (or more) image(s) onto the executable"
-    );
-
-    args
+/// Serializes the given data as json. The filename is only used for error messages.
+fn encode_json<T: serde::Serialize>(data: &T, json_filename: &str) -> Result<Vec<u8>, ConversionError> {
+    serde_json::to_string_pretty(data)
+        .map(String::into_bytes)
+        .map_err(|error| ConversionError::FileWrite {
+            filepath: json_filename.to_owned(),
+            reason: error.to_string(),
+        })
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 // Loading resources
 
-fn get_resource_dir_path() -> String {
+fn get_resource_dir_path() -> Result<String, ConversionError> {
     let executable_dir_path = get_executable_dir();
     let resource_dir_path = {
         let candidate = path_join(&executable_dir_path, "resources");
@@ -117,39 +300,218 @@ fn get_resource_dir_path() -> String {
         }
     };
 
-    assert!(
-        path_exists(&resource_dir_path),
-        "Missing `resources` path in '{}'",
-        executable_dir_path
-    );
+    if !path_exists(&resource_dir_path) {
+        return Err(ConversionError::InvalidResources(format!(
+            "Missing `resources` path in '{}'",
+            executable_dir_path
+        )));
+    }
 
-    resource_dir_path
+    Ok(resource_dir_path)
 }
 
-fn load_stitch_preview_images_premultiplied_alpha() -> (Vec<Bitmap>, Vec<Bitmap>, Bitmap) {
-    let resource_dir_path = get_resource_dir_path();
-    let background_tile_image_8x8 =
-        Bitmap::from_png_file_or_panic(&path_join(&resource_dir_path, "aida_8x8.png"))
-            .to_premultiplied_alpha();
-    let stitch_tile_images = ["stitch1.png", "stitch2.png", "stitch3.png"]
-        .iter()
-        .map(|filename| {
-            Bitmap::from_png_file_or_panic(&path_join(&resource_dir_path, filename))
-                .to_premultiplied_alpha()
-        })
-        .collect();
-    let stitch_tile_images_luminance = ["stitch1_lum.png", "stitch2_lum.png", "stitch3_lum.png"]
-        .iter()
-        .map(|filename| {
-            Bitmap::from_png_file_or_panic(&path_join(&resource_dir_path, filename))
-                .to_premultiplied_alpha()
-        })
-        .collect();
-    (
+fn load_png_file(png_filepath: &str) -> Result<Bitmap, ConversionError> {
+    Bitmap::from_png_file(png_filepath).map_err(|reason| ConversionError::FileLoad {
+        filepath: png_filepath.to_owned(),
+        reason,
+    })
+}
+
+fn load_stitch_preview_images_premultiplied_alpha(
+    fabric: &Fabric,
+    preview_setup: &PreviewSetup,
+    craft: Craft,
+    diamond_setup: &DiamondSetup,
+    brick_setup: &BrickSetup,
+) -> Result<(Vec<Bitmap>, Vec<Bitmap>, Bitmap), ConversionError> {
+    let tile_size = |aida_tile_image_8x8: &Bitmap| aida_tile_image_8x8.width / 8;
+    let resource_dir_path = get_resource_dir_path()?;
+    let aida_tile_image_8x8 = load_png_file(&path_join(&resource_dir_path, "aida_8x8.png"))?;
+
+    // NOTE: Every entry is (stitch tile, luminance tile)
+    let (background_tile_image_8x8, stitch_tiles) = match craft {
+        Craft::CrossStitch => (
+            create_fabric_tile_8x8(fabric, &aida_tile_image_8x8),
+            load_stitch_tiles(&resource_dir_path, preview_setup)?,
+        ),
+        Craft::DiamondPainting => (
+            create_canvas_tile_8x8(aida_tile_image_8x8.width),
+            vec![create_drill_tiles(
+                diamond_setup.drill_shape,
+                tile_size(&aida_tile_image_8x8),
+            )],
+        ),
+        Craft::FuseBeads => (
+            create_pegboard_tile_8x8(aida_tile_image_8x8.width),
+            vec![create_bead_tiles(tile_size(&aida_tile_image_8x8))],
+        ),
+        Craft::Knitting | Craft::Crochet => (
+            create_knit_background_tile_8x8(aida_tile_image_8x8.width),
+            vec![create_knit_stitch_tiles(tile_size(&aida_tile_image_8x8))],
+        ),
+        Craft::Bricks => (
+            create_baseplate_tile_8x8(aida_tile_image_8x8.width),
+            vec![create_part_tiles(
+                brick_setup.part,
+                tile_size(&aida_tile_image_8x8),
+            )],
+        ),
+    };
+    let background_tile_image_8x8 = background_tile_image_8x8.to_premultiplied_alpha();
+
+    // NOTE: We create rotated copies of every tile upfront so that rotation jitter does not cost
+    //       anything when rendering. Colorizing the tiles later keeps the copies in sync.
+    // NOTE: Brick parts sit firmly on their studs and never rotate
+    let rotation_max = if craft == Craft::Bricks {
+        0.0
+    } else {
+        preview_setup.rotation_jitter_degrees.to_radians()
+    };
+    let rotations: Vec<f32> = if rotation_max > 0.0 {
+        (0..STITCH_ROTATION_VARIANT_COUNT)
+            .map(|index| {
+                -rotation_max
+                    + 2.0 * rotation_max * index as f32 / (STITCH_ROTATION_VARIANT_COUNT - 1) as f32
+            })
+            .collect()
+    } else {
+        vec![0.0]
+    };
+
+    let mut stitch_tile_images = Vec::new();
+    let mut stitch_tile_images_luminance = Vec::new();
+    for (stitch, stitch_luminance) in stitch_tiles {
+        let stitch = stitch.to_premultiplied_alpha();
+        let stitch_luminance = stitch_luminance.to_premultiplied_alpha();
+        for &rotation in &rotations {
+            if rotation == 0.0 {
+                stitch_tile_images.push(stitch.clone());
+                stitch_tile_images_luminance.push(stitch_luminance.clone());
+            } else {
+                stitch_tile_images.push(stitch.rotated_bilinear(rotation));
+                stitch_tile_images_luminance.push(stitch_luminance.rotated_bilinear(rotation));
+            }
+        }
+    }
+    Ok((
         stitch_tile_images,
         stitch_tile_images_luminance,
         background_tile_image_8x8,
-    )
+    ))
+}
+
+/// Loads the hoop and frame images of the requested mockups
+fn load_mockup_images(
+    preview_setup: &PreviewSetup,
+) -> Result<Vec<(MockupType, Bitmap)>, ConversionError> {
+    if preview_setup.mockups.is_empty() {
+        return Ok(Vec::new());
+    }
+    let resource_dir_path = get_resource_dir_path()?;
+    let mut mockup_images = Vec::new();
+    for &mockup in &preview_setup.mockups {
+        let image = load_png_file(&path_join(&resource_dir_path, mockup.image_filename()))?;
+        mockup_images.push((mockup, image));
+    }
+    Ok(mockup_images)
+}
+
+/// Returns the bundled stitch tiles or the tiles of a user supplied tile set together with their
+/// luminance tiles
+fn load_stitch_tiles(
+    resource_dir_path: &str,
+    preview_setup: &PreviewSetup,
+) -> Result<Vec<(Bitmap, Bitmap)>, ConversionError> {
+    let stitch_tile_filepaths = match &preview_setup.stitch_tiles_dir {
+        Some(stitch_tiles_dir) => collect_stitch_tile_filepaths(stitch_tiles_dir)?,
+        None => ["stitch1", "stitch2", "stitch3"]
+            .iter()
+            .map(|name| {
+                (
+                    path_join(resource_dir_path, &(name.to_string() + ".png")),
+                    path_join(resource_dir_path, &(name.to_string() + "_lum.png")),
+                )
+            })
+            .collect(),
+    };
+
+    let mut stitch_tiles = Vec::new();
+    for (filepath, filepath_luminance) in &stitch_tile_filepaths {
+        let stitch = load_png_file(filepath)?;
+        let stitch_luminance = load_png_file(filepath_luminance)?;
+        if stitch.dim() != stitch_luminance.dim() {
+            return Err(ConversionError::InvalidResources(format!(
+                "Stitch tile '{}' and its luminance tile '{}' must have the same size",
+                filepath, filepath_luminance
+            )));
+        }
+        stitch_tiles.push((stitch, stitch_luminance));
+    }
+    Ok(stitch_tiles)
+}
+
+/// Returns the pairs of stitch tile and luminance tile filepaths in a user supplied tile set
+fn collect_stitch_tile_filepaths(
+    stitch_tiles_dir: &str,
+) -> Result<Vec<(String, String)>, ConversionError> {
+    if !path_exists(stitch_tiles_dir) {
+        return Err(ConversionError::InvalidResources(format!(
+            "Stitch tile directory '{}' does not exist",
+            stitch_tiles_dir
+        )));
+    }
+    let mut filepaths: Vec<String> = collect_files_by_extension_recursive(stitch_tiles_dir, ".png")
+        .into_iter()
+        .filter(|filepath| !filepath.ends_with("_lum.png"))
+        .collect();
+    filepaths.sort();
+    if filepaths.is_empty() {
+        return Err(ConversionError::InvalidResources(format!(
+            "Stitch tile directory '{}' does not contain any png files",
+            stitch_tiles_dir
+        )));
+    }
+
+    filepaths
+        .into_iter()
+        .map(|filepath| {
+            let filepath_luminance = filepath.trim_end_matches(".png").to_owned() + "_lum.png";
+            if !path_exists(&filepath_luminance) {
+                return Err(ConversionError::InvalidResources(format!(
+                    "Stitch tile '{}' is missing its luminance tile '{}'",
+                    filepath, filepath_luminance
+                )));
+            }
+            Ok((filepath, filepath_luminance))
+        })
+        .collect()
+}
+
+/// Returns the user supplied palette file of the craft if any
+fn palette_filepath(options: &ConversionOptions) -> Option<&String> {
+    match options.craft {
+        Craft::CrossStitch => None,
+        Craft::DiamondPainting => options.diamond_painting.palette_filepath.as_ref(),
+        Craft::FuseBeads => options.fuse_beads.palette_filepath.as_ref(),
+        Craft::Knitting | Craft::Crochet => options.knitting.palette_filepath.as_ref(),
+        Craft::Bricks => options.bricks.palette_filepath.as_ref(),
+    }
+}
+
+/// Returns the colors images are converted to, mapped to their thread or part names
+fn load_palette(options: &ConversionOptions) -> Result<HashMap<PixelRGBA, String>, ConversionError> {
+    let palette = match (options.craft, palette_filepath(options)) {
+        (_, Some(palette_filepath)) => color_mapping::load_palette_file(palette_filepath)
+            .map_err(|reason| ConversionError::FileLoad {
+                filepath: palette_filepath.clone(),
+                reason,
+            })?,
+        (Craft::DiamondPainting, None) => diamond_painting::make_palette(),
+        (Craft::FuseBeads, None) => fuse_beads::make_palette(options.fuse_beads.brand),
+        (Craft::Bricks, None) => bricks::make_palette(),
+        _ => color_mapping::make(),
+    };
+    Ok(palette)
 }
 
 fn load_fonts() -> (BitmapFont, BitmapFont) {
@@ -158,20 +520,14 @@ fn load_fonts() -> (BitmapFont, BitmapFont) {
         FONT_DEFAULT_TINY_TTF,
         FONT_DEFAULT_TINY_PIXEL_HEIGHT,
         FONT_DEFAULT_TINY_RASTER_OFFSET,
-        0,
-        0,
-        PixelRGBA::black(),
-        PixelRGBA::transparent(),
+        GlyphStyle::default(),
     );
     let mut font_big = BitmapFont::new(
         FONT_DEFAULT_REGULAR_NAME,
         FONT_DEFAULT_REGULAR_TTF,
         2 * FONT_DEFAULT_REGULAR_PIXEL_HEIGHT,
         FONT_DEFAULT_REGULAR_RASTER_OFFSET,
-        0,
-        0,
-        PixelRGBA::black(),
-        PixelRGBA::transparent(),
+        GlyphStyle::default(),
     );
 
     // NOTE: Because 0 looks like an 8 in this font on crappy printers we replace it with an O (big o)
@@ -187,73 +543,246 @@ fn load_fonts() -> (BitmapFont, BitmapFont) {
     (font_regular, font_big)
 }
 
-fn collect_symbols() -> Vec<Bitmap> {
-    let resource_dir_path = get_resource_dir_path();
-    let symbols_filepaths = collect_files_by_extension_recursive(&resource_dir_path, ".png");
+/// Returns the hand-drawn symbols or the symbols of a user supplied symbol sheet, followed by
+/// symbols generated from font glyphs
+fn collect_symbols(options: &ConversionOptions) -> Result<Vec<Bitmap>, ConversionError> {
+    let mut symbols = if let Some(symbol_sheet_filepath) = &options.symbol_sheet_filepath {
+        load_symbols_from_sheet(symbol_sheet_filepath)?
+    } else {
+        load_bundled_symbols()?
+    };
+
+    // NOTE: There are only a handful of hand-drawn symbols, so we append font glyphs to be able to
+    //       map images with lots of colors
+    let symbol_font = load_symbol_font(options)?;
+    for symbol in create_font_symbols(&symbol_font) {
+        if !symbols.contains(&symbol) {
+            symbols.push(symbol);
+        }
+    }
+
+    Ok(symbols)
+}
+
+fn load_bundled_symbols() -> Result<Vec<Bitmap>, ConversionError> {
+    let resource_dir_path = get_resource_dir_path()?;
+    let mut symbols_filepaths: Vec<(u32, String)> =
+        collect_files_by_extension_recursive(&resource_dir_path, ".png")
+            .into_iter()
+            .filter_map(|filepath| {
+                path_to_filename_without_extension(&filepath)
+                    .parse::<u32>()
+                    .ok()
+                    .map(|symbol_index| (symbol_index, filepath))
+            })
+            .collect();
+
+    // NOTE: The directory walker does not guarantee any order but we want our symbols to be
+    //       assigned in the same order on every machine
+    symbols_filepaths.sort();
+
+    symbols_filepaths
+        .into_iter()
+        .map(|(_symbol_index, symbol_filepath)| load_png_file(&symbol_filepath))
+        .collect()
+}
+
+/// Cuts a symbol sheet into TILE_SIZE x TILE_SIZE symbols in row-major order. Empty tiles are
+/// skipped. Transparent pixels are treated as white background.
+fn load_symbols_from_sheet(symbol_sheet_filepath: &str) -> Result<Vec<Bitmap>, ConversionError> {
+    let sheet = load_png_file(symbol_sheet_filepath)?;
+    if sheet.width % TILE_SIZE != 0 || sheet.height % TILE_SIZE != 0 {
+        return Err(ConversionError::InvalidResources(format!(
+            "Dimensions of symbol sheet '{}' must be a multiple of {}x{} - got {}x{}",
+            symbol_sheet_filepath, TILE_SIZE, TILE_SIZE, sheet.width, sheet.height
+        )));
+    }
 
-    let sym_paths: Vec<Bitmap> = symbols_filepaths
+    let (tiles, _tile_coordinates) = sheet.to_segments(TILE_SIZE, TILE_SIZE);
+    let symbols = tiles
         .into_iter()
-        .filter(|filepath| {
-            path_to_filename_without_extension(filepath)
-                .parse::<u32>()
-                .is_ok()
+        .map(|mut tile| {
+            for pixel in tile.data.iter_mut() {
+                if pixel.a == 0 {
+                    *pixel = PixelRGBA::white();
+                }
+            }
+            tile
         })
-        .map(|symbol_filepath| Bitmap::from_png_file_or_panic(&symbol_filepath))
+        .filter(|tile| tile.data.iter().any(|pixel| *pixel != PixelRGBA::white()))
         .collect();
+    Ok(symbols)
+}
 
-    sym_paths
-}
-
-fn create_alphanumeric_symbols(font: &BitmapFont) -> Vec<Bitmap> {
-    let mut symbols = Vec::new();
-    for c in "123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ".chars() {
-        let mut bitmap =
-            Bitmap::new_filled(TILE_SIZE as u32, TILE_SIZE as u32, PixelRGBA::transparent());
-        // NOTE: We can unwrap here because we own the font and know that all glyphs exist
-        let glyph_bitmap = font
-            .glyphs
-            .get(&(c as Codepoint))
-            .as_ref()
-            .unwrap()
-            .bitmap
-            .as_ref()
-            .unwrap();
+fn load_symbol_font(options: &ConversionOptions) -> Result<BitmapFont, ConversionError> {
+    let font = if let Some(symbol_font_filepath) = &options.symbol_font_filepath {
+        let font_ttf_bytes =
+            read_file_whole(symbol_font_filepath).map_err(|reason| ConversionError::FileLoad {
+                filepath: symbol_font_filepath.clone(),
+                reason,
+            })?;
+        BitmapFont::new(
+            &path_to_filename_without_extension(symbol_font_filepath),
+            &font_ttf_bytes,
+            SYMBOL_FONT_PIXEL_HEIGHT,
+            Vec2::zero(),
+            GlyphStyle::default(),
+        )
+    } else {
+        BitmapFont::new(
+            FONT_DEFAULT_REGULAR_NAME,
+            FONT_DEFAULT_REGULAR_TTF,
+            FONT_DEFAULT_REGULAR_PIXEL_HEIGHT,
+            FONT_DEFAULT_REGULAR_RASTER_OFFSET,
+            GlyphStyle::default(),
+        )
+    };
+    Ok(font)
+}
+
+/// Renders every visible glyph of the given font as black on white symbol. Glyphs that are too
+/// big, too small to be recognizable or that look exactly like a previous glyph are skipped.
+fn create_font_symbols(font: &BitmapFont) -> Vec<Bitmap> {
+    let mut symbols: Vec<Bitmap> = Vec::new();
+    for glyph in font.glyphs.values() {
+        if glyph.codepoint.is_whitespace() || glyph.codepoint.is_control() {
+            continue;
+        }
+        let glyph_bitmap = if let Some(glyph_bitmap) = &glyph.bitmap {
+            glyph_bitmap
+        } else {
+            continue;
+        };
+
+        // NOTE: We keep a one pixel margin so that the glyph does not touch the grid lines
+        if glyph_bitmap.width > TILE_SIZE - 2 || glyph_bitmap.height > TILE_SIZE - 2 {
+            continue;
+        }
+        let glyph_pixel_count = glyph_bitmap
+            .data
+            .iter()
+            .filter(|pixel| **pixel != PixelRGBA::transparent())
+            .count();
+        if glyph_pixel_count < SYMBOL_GLYPH_PIXEL_COUNT_MIN {
+            continue;
+        }
+
+        let mut symbol =
+            Bitmap::new_filled(TILE_SIZE as u32, TILE_SIZE as u32, PixelRGBA::white());
         let pos = Vec2i::new(
             block_centered_in_block(glyph_bitmap.width, TILE_SIZE),
             block_centered_in_block(glyph_bitmap.height, TILE_SIZE),
         );
-        blit_symbol(glyph_bitmap, &mut bitmap, pos, PixelRGBA::transparent());
-        symbols.push(bitmap);
+        glyph_bitmap.blit_to_masked(&mut symbol, pos, false, Some(PixelRGBA::transparent()));
+
+        if !symbols.contains(&symbol) {
+            symbols.push(symbol);
+        }
     }
 
     symbols
 }
 
-fn open_image(image_filepath: &str) -> Bitmap {
-    if path_to_extension(&image_filepath).ends_with("gif") {
-        bitmap_create_from_gif_file(&image_filepath)
-    } else if path_to_extension(&image_filepath).ends_with("png") {
-        Bitmap::from_png_file_or_panic(&image_filepath)
+fn open_image(image_filepath: &str) -> Result<Bitmap, ConversionError> {
+    if path_to_extension(image_filepath).ends_with("gif") {
+        bitmap_create_from_gif_file(image_filepath)
+    } else if path_to_extension(image_filepath).ends_with("png") {
+        load_png_file(image_filepath)
+    } else {
+        Err(ConversionError::UnsupportedImageFormat {
+            filepath: image_filepath.to_owned(),
+        })
+    }
+}
+
+/// Decodes an in-memory GIF or PNG image. The name is only used for error messages.
+pub fn decode_image(image_data: &[u8], name: &str) -> Result<Bitmap, ConversionError> {
+    if image_data.starts_with(b"GIF8") {
+        bitmap_create_from_gif_data(image_data, name)
+    } else if image_data.starts_with(b"\x89PNG") {
+        Bitmap::from_png_data(image_data).map_err(|reason| ConversionError::FileLoad {
+            filepath: name.to_owned(),
+            reason,
+        })
     } else {
-        panic!("We only support GIF or PNG images");
+        Err(ConversionError::UnsupportedImageFormat {
+            filepath: name.to_owned(),
+        })
     }
 }
 
-fn convert_image(image: &Bitmap, stitch_colors_mapping: &HashMap<PixelRGBA, String>) -> Bitmap {
-    Bitmap {
+fn convert_image(image: &Bitmap, stitch_colors_mapping: &HashMap<PixelRGBA, String>, color_metric: ColorMetric) -> Result<Bitmap, ConversionError> {
+    let data = image.data
+        .iter()
+        .map(|pixel| {
+            // NOTE: Transparent pixels are left unstitched
+            if pixel.a == 0 {
+                Ok(PixelRGBA::transparent())
+            } else {
+                find_closest_color(pixel, stitch_colors_mapping, color_metric).ok_or_else(|| {
+                    ConversionError::InvalidResources("The color palette is empty".to_owned())
+                })
+            }
+        })
+        .collect::<Result<Vec<PixelRGBA>, ConversionError>>()?;
+    Ok(Bitmap {
         width: image.width,
         height: image.height,
-        data: image.data
-            .iter()
-            .map(|pixel| find_closest_color(pixel, stitch_colors_mapping))
-            .collect()
-    }
+        data,
+    })
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 // Low level bitmap helper function
 
 fn blit_symbol(symbol_bitmap: &Bitmap, image: &mut Bitmap, pos: Vec2i, mask_color: PixelRGBA) {
+    let dest_color = image.get(pos.x, pos.y);
+    let relative_luminance = Color::from_pixelrgba(dest_color).to_relative_luminance();
+    let blit_color = if relative_luminance > 0.2 {
+        PixelRGBA::black()
+    } else {
+        PixelRGBA::white()
+    };
+
+    blit_symbol_with_color(symbol_bitmap, image, pos, mask_color, blit_color, None);
+}
+
+/// Draws the symbol in the given thread color. Thread colors that are too light to read on white
+/// paper get an outline in a darkened version of the thread color
+fn blit_symbol_colored(
+    symbol_bitmap: &Bitmap,
+    image: &mut Bitmap,
+    pos: Vec2i,
+    mask_color: PixelRGBA,
+    thread_color: PixelRGBA,
+) {
+    let mut outline_color = Color::from_pixelrgba(thread_color);
+    if outline_color.to_relative_luminance() <= SYMBOL_COLOR_RELATIVE_LUMINANCE_MAX {
+        blit_symbol_with_color(symbol_bitmap, image, pos, mask_color, thread_color, None);
+        return;
+    }
+
+    while outline_color.to_relative_luminance() > SYMBOL_COLOR_RELATIVE_LUMINANCE_MAX {
+        outline_color = outline_color.with_color_multiplied_by(0.9);
+    }
+    blit_symbol_with_color(
+        symbol_bitmap,
+        image,
+        pos,
+        mask_color,
+        thread_color,
+        Some(outline_color.to_pixelrgba()),
+    );
+}
+
+fn blit_symbol_with_color(
+    symbol_bitmap: &Bitmap,
+    image: &mut Bitmap,
+    pos: Vec2i,
+    mask_color: PixelRGBA,
+    blit_color: PixelRGBA,
+    outline_color: Option<PixelRGBA>,
+) {
     let symbol_rect = symbol_bitmap.rect();
 
     assert!(pos.x >= 0);
@@ -261,49 +790,173 @@ fn blit_symbol(symbol_bitmap: &Bitmap, image: &mut Bitmap, pos: Vec2i, mask_colo
     assert!(pos.x + symbol_rect.width() <= image.width);
     assert!(pos.y + symbol_rect.height() <= image.height);
 
-    let dest_color = image.get(pos.x, pos.y);
-    let relative_luminance = Color::from_pixelrgba(dest_color).to_relative_luminance();
-    let blit_color = if relative_luminance > 0.2 {
-        PixelRGBA::black()
-    } else {
-        PixelRGBA::white()
+    // NOTE: We assume the symbols-images are black on white backround. We don't want to
+    //       draw the white background so we treat it as transparent
+    let is_symbol_pixel = |x: i32, y: i32| {
+        symbol_bitmap.contains_coordinate(x, y) && symbol_bitmap.get(x, y) != mask_color
     };
 
     for y in 0..symbol_rect.height() {
         for x in 0..symbol_rect.width() {
-            let symbol_pixel_color = symbol_bitmap.get(x, y);
-            // NOTE: We assume the symbols-images are black on white backround. We don't want to
-            //       draw the white background so we treat it as transparent
-            if symbol_pixel_color != mask_color {
+            if is_symbol_pixel(x, y) {
                 image.set(pos.x + x, pos.y + y, blit_color);
+            } else if let Some(outline_color) = outline_color {
+                let is_next_to_symbol = is_symbol_pixel(x - 1, y)
+                    || is_symbol_pixel(x + 1, y)
+                    || is_symbol_pixel(x, y - 1)
+                    || is_symbol_pixel(x, y + 1);
+                if is_next_to_symbol {
+                    image.set(pos.x + x, pos.y + y, outline_color);
+                }
             }
         }
     }
 }
 
-fn bitmap_create_from_gif_file(image_filepath: &str) -> Bitmap {
-    let mut decoder = gif::Decoder::new(
-        File::open(image_filepath).expect(&format!("Cannot open file '{}'", image_filepath)),
-    );
+/// Draws a filled arrow head into a square bitmap of the given size
+fn create_arrow_bitmap(size: i32, direction: Vec2i, color: PixelRGBA) -> Bitmap {
+    let mut result = Bitmap::new_filled(size as u32, size as u32, PixelRGBA::white());
+    let half_size = size / 2;
+    for depth in 0..half_size {
+        // NOTE: The arrow gets narrower towards its tip
+        let half_width = half_size - depth;
+        for offset in -half_width..=half_width {
+            let (x, y) = match (direction.x, direction.y) {
+                (1, _) => (half_size / 2 + depth, half_size + offset),
+                (-1, _) => (size - 1 - half_size / 2 - depth, half_size + offset),
+                (_, 1) => (half_size + offset, half_size / 2 + depth),
+                _ => (half_size + offset, size - 1 - half_size / 2 - depth),
+            };
+            if result.contains_coordinate(x, y) {
+                result.set(x, y, color);
+            }
+        }
+    }
+    result
+}
+
+/// Creates a "continued on page N" marker with an arrow pointing in the given direction
+fn create_neighbour_page_marker(font: &BitmapFont, page_number: usize, direction: Vec2i) -> Bitmap {
+    let arrow_size = 2 * font.vertical_advance;
+    let arrow = create_arrow_bitmap(arrow_size, direction, PixelRGBA::black());
+    if direction.x == 0 {
+        let text = Bitmap::create_from_text(
+            font,
+            &format!(" continued on page {} ", page_number),
+            1,
+            PixelRGBA::white(),
+        );
+        text.glued_to(&arrow, GluePosition::LeftCenter, 0, PixelRGBA::white())
+    } else {
+        // NOTE: Side markers are stacked vertically to not waste horizontal space
+        let text = Bitmap::create_from_text(
+            font,
+            &format!(" continued \n on page \n {} ", page_number),
+            1,
+            PixelRGBA::white(),
+        );
+        arrow.glued_to(&text, GluePosition::TopCenter, 0, PixelRGBA::white())
+    }
+}
+
+/// Adds "continued on page N" markers to each edge of a pattern page that has a neighbour
+fn add_neighbour_page_markers(
+    bitmap: &Bitmap,
+    font: &BitmapFont,
+    neighbour_page_numbers: &NeighbourPageNumbers,
+) -> Bitmap {
+    let create_marker = |page_number: usize, direction: Vec2i| {
+        create_neighbour_page_marker(font, page_number, direction)
+    };
+
+    let mut result = bitmap.clone();
+    if let Some(page_number) = neighbour_page_numbers.left {
+        result = create_marker(page_number, Vec2i::new(-1, 0)).glued_to(
+            &result,
+            GluePosition::LeftCenter,
+            0,
+            PixelRGBA::white(),
+        );
+    }
+    if let Some(page_number) = neighbour_page_numbers.right {
+        result = create_marker(page_number, Vec2i::new(1, 0)).glued_to(
+            &result,
+            GluePosition::RightCenter,
+            0,
+            PixelRGBA::white(),
+        );
+    }
+    if let Some(page_number) = neighbour_page_numbers.top {
+        result = create_marker(page_number, Vec2i::new(0, -1)).glued_to(
+            &result,
+            GluePosition::TopCenter,
+            0,
+            PixelRGBA::white(),
+        );
+    }
+    if let Some(page_number) = neighbour_page_numbers.bottom {
+        result = create_marker(page_number, Vec2i::new(0, 1)).glued_to(
+            &result,
+            GluePosition::BottomCenter,
+            0,
+            PixelRGBA::white(),
+        );
+    }
+    result
+}
+
+fn shade_cell(image: &mut Bitmap, pos_x: i32, pos_y: i32, cell_width: i32, cell_height: i32) {
+    let shade_color = Color::from_pixelrgba(COLOR_OVERLAP_SHADE);
+    for y in pos_y..(pos_y + cell_height) {
+        for x in pos_x..(pos_x + cell_width) {
+            let color = Color::from_pixelrgba(image.get(x, y));
+            image.set(
+                x,
+                y,
+                Color::mix(color, shade_color, OVERLAP_SHADE_PERCENT).to_pixelrgba(),
+            );
+        }
+    }
+}
+
+fn bitmap_create_from_gif_file(image_filepath: &str) -> Result<Bitmap, ConversionError> {
+    let file = File::open(image_filepath).map_err(|error| ConversionError::FileLoad {
+        filepath: image_filepath.to_owned(),
+        reason: error.to_string(),
+    })?;
+    bitmap_create_from_gif_data(file, image_filepath)
+}
+
+/// NOTE: The `image_filepath` is only used for error messages
+fn bitmap_create_from_gif_data<R: std::io::Read>(
+    gif_data: R,
+    image_filepath: &str,
+) -> Result<Bitmap, ConversionError> {
+    let file_load_error = |reason: &str| ConversionError::FileLoad {
+        filepath: image_filepath.to_owned(),
+        reason: reason.to_owned(),
+    };
+
+    let mut decoder = gif::Decoder::new(gif_data);
 
     decoder.set(gif::ColorOutput::RGBA);
     let mut decoder = decoder
         .read_info()
-        .expect(&format!("Cannot decode file '{}'", image_filepath));
+        .map_err(|error| file_load_error(&format!("Cannot decode file: {}", error)))?;
     let frame = decoder
         .read_next_frame()
-        .expect(&format!(
-            "Cannot decode first frame in '{}'",
-            image_filepath
-        ))
-        .expect(&format!("No frame found in '{}'", image_filepath));
+        .map_err(|error| file_load_error(&format!("Cannot decode first frame: {}", error)))?
+        .ok_or_else(|| file_load_error("No frame found"))?;
     let buffer: Vec<PixelRGBA> = frame
         .buffer
         .chunks_exact(4)
-        .into_iter()
         .map(|color| PixelRGBA::new(color[0], color[1], color[2], color[3]))
         .collect();
-    Bitmap::new_from_buffer(frame.width as u32, frame.height as u32, buffer)
+    Ok(Bitmap::new_from_buffer(
+        frame.width as u32,
+        frame.height as u32,
+        buffer,
+    ))
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -323,17 +976,21 @@ fn draw_origin_line_horizontal(bitmap: &mut Bitmap, pos_y: i32) {
 ///       grid_cell_size
 fn place_grid_labels_in_pattern(
     scaled_bitmap: &Bitmap,
-    grid_cell_size: i32,
+    grid_cell_size: Vec2i,
     font: &BitmapFont,
+    font_scale: i32,
+    major_grid_interval: i32,
     logical_first_coordinate_x: i32,
     logical_first_coordinate_y: i32,
 ) -> Bitmap {
-    let grid_width = scaled_bitmap.width / grid_cell_size;
-    let grid_height = scaled_bitmap.height / grid_cell_size;
+    let grid_width = scaled_bitmap.width / grid_cell_size.x;
+    let grid_height = scaled_bitmap.height / grid_cell_size.y;
 
     let logical_last_coordinate_x = logical_first_coordinate_x + grid_width;
     let logical_last_coordinate_y = logical_first_coordinate_y + grid_height;
 
+    let partial_block_label_threshold = (3 * major_grid_interval) / 10;
+
     // Determine how much image-padding we need by calculating the maximum label text dimension
     let label_padding = {
         let max_logical_coordinates = [
@@ -348,7 +1005,7 @@ fn place_grid_labels_in_pattern(
             .max()
             .unwrap();
 
-        font.horizontal_advance_max * (max_text_charcount + 4) as i32
+        font_scale * font.horizontal_advance_max * (max_text_charcount + 4) as i32
     };
 
     let mut result_bitmap = scaled_bitmap.extended(
@@ -364,25 +1021,25 @@ fn place_grid_labels_in_pattern(
         let mut result = Vec::new();
         for bitmap_coord_x in 0..(grid_width + 1) {
             let logical_coord_x = logical_first_coordinate_x + bitmap_coord_x;
-            if logical_coord_x % 10 == 0 {
+            if logical_coord_x % major_grid_interval == 0 {
                 result.push((bitmap_coord_x, logical_coord_x));
             }
         }
 
         // Add label for first and last horizontal grid pixel so that we don't mix up a remaining
-        // 7, 8 or 9 pixel block with a 10 block
+        // partial block (i.e. 7, 8 or 9 pixels for 10 blocks) with a full block
         let pixel_count_in_first_block_horizontal = i32::abs(
-            ceil_to_multiple_of_target_i32(logical_first_coordinate_x, 10)
+            ceil_to_multiple_of_target_i32(logical_first_coordinate_x, major_grid_interval)
                 - logical_first_coordinate_x,
         );
-        if pixel_count_in_first_block_horizontal > 3 {
+        if pixel_count_in_first_block_horizontal > partial_block_label_threshold {
             result.push((0, logical_first_coordinate_x));
         }
         let pixel_count_in_last_block_horizontal = i32::abs(
-            floor_to_multiple_of_target_i32(logical_last_coordinate_x, 10)
+            floor_to_multiple_of_target_i32(logical_last_coordinate_x, major_grid_interval)
                 - logical_last_coordinate_x,
         );
-        if pixel_count_in_last_block_horizontal > 3 {
+        if pixel_count_in_last_block_horizontal > partial_block_label_threshold {
             result.push((grid_width, logical_last_coordinate_x));
         }
 
@@ -392,14 +1049,14 @@ fn place_grid_labels_in_pattern(
     // Draw x labels
     for (bitmap_coord_x, logical_coord_x) in label_coords_x {
         let text = logical_coord_x.to_string();
-        let draw_x = label_padding + grid_cell_size * bitmap_coord_x;
+        let draw_x = label_padding + grid_cell_size.x * bitmap_coord_x;
         let draw_pos_top = Vec2i::new(draw_x, label_padding / 2);
         let draw_pos_bottom = Vec2i::new(draw_x, result_bitmap.height - label_padding / 2);
 
         result_bitmap.draw_text_aligned_in_point(
             font,
             &text,
-            1,
+            font_scale,
             draw_pos_top,
             Vec2i::zero(),
             Some(TextAlignment {
@@ -412,7 +1069,7 @@ fn place_grid_labels_in_pattern(
         result_bitmap.draw_text_aligned_in_point(
             font,
             &text,
-            1,
+            font_scale,
             draw_pos_bottom,
             Vec2i::zero(),
             Some(TextAlignment {
@@ -429,25 +1086,25 @@ fn place_grid_labels_in_pattern(
         let mut result = Vec::new();
         for bitmap_coord_y in 0..(grid_height + 1) {
             let logical_coord_y = logical_first_coordinate_y + bitmap_coord_y;
-            if logical_coord_y % 10 == 0 {
+            if logical_coord_y % major_grid_interval == 0 {
                 result.push((bitmap_coord_y, logical_coord_y));
             }
         }
 
         // Add label for first and last vertical grid pixel so that we don't mix up a remaining
-        // 7, 8 or 9 pixel block with a 10 block
+        // partial block (i.e. 7, 8 or 9 pixels for 10 blocks) with a full block
         let pixel_count_in_first_block_vertical = i32::abs(
-            ceil_to_multiple_of_target_i32(logical_first_coordinate_y, 10)
+            ceil_to_multiple_of_target_i32(logical_first_coordinate_y, major_grid_interval)
                 - logical_first_coordinate_y,
         );
-        if pixel_count_in_first_block_vertical > 3 {
+        if pixel_count_in_first_block_vertical > partial_block_label_threshold {
             result.push((0, logical_first_coordinate_y));
         }
         let pixel_count_in_last_block_vertical = i32::abs(
-            floor_to_multiple_of_target_i32(logical_last_coordinate_y, 10)
+            floor_to_multiple_of_target_i32(logical_last_coordinate_y, major_grid_interval)
                 - logical_last_coordinate_y,
         );
-        if pixel_count_in_last_block_vertical > 3 {
+        if pixel_count_in_last_block_vertical > partial_block_label_threshold {
             result.push((grid_height, logical_last_coordinate_y));
         }
 
@@ -458,14 +1115,14 @@ fn place_grid_labels_in_pattern(
     for (bitmap_coord_y, logical_coord_y) in label_coords_y {
         // NOTE: In pixel space our y-coordinates are y-down. We want cartesian y-up so we negate y
         let text = (-logical_coord_y).to_string();
-        let draw_y = label_padding + grid_cell_size * bitmap_coord_y;
+        let draw_y = label_padding + grid_cell_size.y * bitmap_coord_y;
         let draw_pos_left = Vec2i::new(label_padding / 2, draw_y);
         let draw_pos_right = Vec2i::new(result_bitmap.width - label_padding / 2, draw_y);
 
         result_bitmap.draw_text_aligned_in_point(
             font,
             &text,
-            1,
+            font_scale,
             draw_pos_left,
             Vec2i::zero(),
             Some(TextAlignment {
@@ -478,7 +1135,7 @@ fn place_grid_labels_in_pattern(
         result_bitmap.draw_text_aligned_in_point(
             font,
             &text,
-            1,
+            font_scale,
             draw_pos_right,
             Vec2i::zero(),
             Some(TextAlignment {
@@ -493,31 +1150,147 @@ fn place_grid_labels_in_pattern(
     result_bitmap
 }
 
-fn create_cross_stitch_pattern(
-    bitmap: &Bitmap,
-    font_grid_label: &BitmapFont,
-    font_segment_index_indicator: &BitmapFont,
-    image_filepath: &str,
-    output_filename_suffix: &str,
-    color_mappings: &IndexMap<PixelRGBA, ColorInfo>,
-    segment_index: Option<usize>,
+/// Numbers the rows and stitches of a knitting chart in the order they are worked. The logical
+/// coordinates are negative and count from the bottom right stitch which is stitch 1 of row 1.
+/// Right side rows are worked from right to left, so their numbers are placed on the right while
+/// wrong side row numbers are placed on the left.
+fn place_row_numbers_in_pattern(
+    scaled_bitmap: &Bitmap,
+    grid_cell_size: Vec2i,
+    font: &BitmapFont,
+    font_scale: i32,
+    major_grid_interval: i32,
     logical_first_coordinate_x: i32,
     logical_first_coordinate_y: i32,
-    pattern_type: PatternType,
-    add_thick_ten_grid: bool,
-    add_origin_grid_bars: bool,
-    symbol_mask_color: PixelRGBA,
-) {
-    let (colorize, add_symbol, use_alphanum) = match pattern_type {
-        PatternType::BlackAndWhite => (false, true, false),
-        PatternType::Colorized => (true, true, false),
-        PatternType::ColorizedNoSymbols => (true, false, false),
-        PatternType::PaintByNumbers => (false, true, true),
+) -> Bitmap {
+    let grid_width = scaled_bitmap.width / grid_cell_size.x;
+    let grid_height = scaled_bitmap.height / grid_cell_size.y;
+
+    let label_padding = {
+        let max_number = i32::max(-logical_first_coordinate_x, -logical_first_coordinate_y);
+        font_scale * font.horizontal_advance_max * (max_number.to_string().len() + 2) as i32
     };
 
-    let mut scaled_bitmap = Bitmap::new(
-        (TILE_SIZE * bitmap.width) as u32,
-        (TILE_SIZE * bitmap.height) as u32,
+    let mut result_bitmap = scaled_bitmap.extended(
+        label_padding,
+        label_padding,
+        label_padding,
+        label_padding,
+        PixelRGBA::white(),
+    );
+    let alignment = Some(TextAlignment {
+        horizontal: AlignmentHorizontal::Center,
+        vertical: AlignmentVertical::Center,
+        origin_is_baseline: false,
+        ignore_whitespace: false,
+    });
+
+    // Draw stitch numbers for the first stitch and every major grid block
+    for bitmap_coord_x in 0..grid_width {
+        let stitch_number = -(logical_first_coordinate_x + bitmap_coord_x);
+        if stitch_number != 1 && stitch_number % major_grid_interval != 0 {
+            continue;
+        }
+        let text = stitch_number.to_string();
+        let draw_x = label_padding + grid_cell_size.x * bitmap_coord_x + grid_cell_size.x / 2;
+        for draw_y in [label_padding / 2, result_bitmap.height - label_padding / 2] {
+            result_bitmap.draw_text_aligned_in_point(
+                font,
+                &text,
+                font_scale,
+                Vec2i::new(draw_x, draw_y),
+                Vec2i::zero(),
+                alignment,
+            );
+        }
+    }
+
+    // Draw row numbers on the side where the row starts
+    for bitmap_coord_y in 0..grid_height {
+        let row_number = -(logical_first_coordinate_y + bitmap_coord_y);
+        let draw_x = if is_right_side_row(row_number) {
+            result_bitmap.width - label_padding / 2
+        } else {
+            label_padding / 2
+        };
+        let draw_y = label_padding + grid_cell_size.y * bitmap_coord_y + grid_cell_size.y / 2;
+        result_bitmap.draw_text_aligned_in_point(
+            font,
+            &row_number.to_string(),
+            font_scale,
+            Vec2i::new(draw_x, draw_y),
+            Vec2i::zero(),
+            alignment,
+        );
+    }
+
+    result_bitmap
+}
+
+/// Renders the given stitches as a chart. `logical_first_coordinate` is the label of the top left
+/// stitch.
+fn render_cross_stitch_pattern(
+    setup: &ChartSetup,
+    bitmap: &Bitmap,
+    page: Option<&PatternPage>,
+    logical_first_coordinate: Vec2i,
+    pattern_type: PatternType,
+) -> Result<Bitmap, ConversionError> {
+    let ChartSetup {
+        font_grid_label,
+        font_segment_index_indicator,
+        color_mappings,
+        chart_style,
+        footer,
+        add_major_grid,
+        add_origin_grid_bars,
+        symbol_mask_color,
+    } = *setup;
+    let logical_first_coordinate_x = logical_first_coordinate.x;
+    let logical_first_coordinate_y = logical_first_coordinate.y;
+
+    let (colorize, add_symbol, color_symbols) = match pattern_type {
+        PatternType::BlackAndWhite => (false, true, false),
+        PatternType::Colorized => (true, true, false),
+        PatternType::ColorizedNoSymbols => (true, false, false),
+        PatternType::ColoredSymbols => (false, true, true),
+        PatternType::PaintByNumbers => {
+            return Err(ConversionError::InvalidOptions(
+                "Paint by numbers charts are rendered from regions instead of cells".to_owned(),
+            ))
+        }
+    };
+
+    let cell_size = chart_style.cell_size;
+    let cell_height = chart_style.cell_height();
+    let major_grid_interval = chart_style.major_grid_interval;
+    let thickness_thin = chart_style.grid_thickness_thin;
+    let thickness_thick = chart_style.grid_thickness_thick;
+
+    // NOTE: Our symbols are made for TILE_SIZE cells so we need to resize them for other cell sizes.
+    //       Symbols stay square and are centered in cells that are not square.
+    let symbol_size = i32::min(cell_size, cell_height);
+    let symbol_offset = Vec2i::new((cell_size - symbol_size) / 2, (cell_height - symbol_size) / 2);
+    let symbols_scaled: HashMap<PixelRGBA, Bitmap> = if add_symbol {
+        color_mappings
+            .iter()
+            .map(|(color, info)| {
+                let symbol = &info.symbol;
+                let symbol_scaled = if symbol.width == symbol_size && symbol.height == symbol_size {
+                    symbol.clone()
+                } else {
+                    symbol.scaled_sample_nearest_neighbor(symbol_size as u32, symbol_size as u32)
+                };
+                (*color, symbol_scaled)
+            })
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
+    let mut scaled_bitmap = Bitmap::new(
+        (cell_size * bitmap.width) as u32,
+        (cell_height * bitmap.height) as u32,
     );
     let scaled_bitmap_width = scaled_bitmap.width;
     let scaled_bitmap_height = scaled_bitmap.height;
@@ -529,10 +1302,10 @@ fn create_cross_stitch_pattern(
             // Colorize pixels
             if colorize {
                 scaled_bitmap.draw_rect_filled(
-                    TILE_SIZE * x,
-                    TILE_SIZE * y,
-                    TILE_SIZE,
-                    TILE_SIZE,
+                    cell_size * x,
+                    cell_height * y,
+                    cell_size,
+                    cell_height,
                     if color.a == 0 {
                         PixelRGBA::white()
                     } else {
@@ -541,98 +1314,120 @@ fn create_cross_stitch_pattern(
                 );
             } else {
                 scaled_bitmap.draw_rect_filled(
-                    TILE_SIZE * x,
-                    TILE_SIZE * y,
-                    TILE_SIZE,
-                    TILE_SIZE,
+                    cell_size * x,
+                    cell_height * y,
+                    cell_size,
+                    cell_height,
                     PixelRGBA::white(),
                 );
             }
 
             // Add symbol
             if add_symbol && color.a != 0 {
-                let symbol = if use_alphanum {
-                    &color_mappings.get(&color).unwrap().symbol_alphanum
+                let symbol = symbols_scaled
+                    .get(&color)
+                    .ok_or(ConversionError::UnmappedColor(color))?;
+                let pos = Vec2i::new(cell_size * x, cell_height * y) + symbol_offset;
+                if color_symbols {
+                    blit_symbol_colored(symbol, &mut scaled_bitmap, pos, symbol_mask_color, color);
                 } else {
-                    &color_mappings.get(&color).unwrap().symbol
-                };
+                    blit_symbol(symbol, &mut scaled_bitmap, pos, symbol_mask_color);
+                }
+            }
 
-                blit_symbol(
-                    symbol,
-                    &mut scaled_bitmap,
-                    Vec2i::new(TILE_SIZE * x, TILE_SIZE * y),
-                    symbol_mask_color,
-                );
+            // Shade stitches that are repeated on a neighbouring page
+            if let Some(page) = page {
+                if !page.unshaded_rect.contains_point(Vec2i::new(x, y)) {
+                    shade_cell(
+                        &mut scaled_bitmap,
+                        cell_size * x,
+                        cell_height * y,
+                        cell_size,
+                        cell_height,
+                    );
+                }
             }
         }
     }
 
     // Add 1x1 grid
     for x in 0..bitmap.width {
-        scaled_bitmap.draw_rect_filled(TILE_SIZE * x, 0, 1, scaled_bitmap_height, COLOR_GRID_THIN);
+        scaled_bitmap.draw_rect_filled(
+            cell_size * x,
+            0,
+            thickness_thin,
+            scaled_bitmap_height,
+            chart_style.grid_color_thin,
+        );
     }
     for y in 0..bitmap.height {
-        scaled_bitmap.draw_rect_filled(0, TILE_SIZE * y, scaled_bitmap_width, 1, COLOR_GRID_THIN);
+        scaled_bitmap.draw_rect_filled(
+            0,
+            cell_height * y,
+            scaled_bitmap_width,
+            thickness_thin,
+            chart_style.grid_color_thin,
+        );
     }
     // Close 1x1 grid line on bottom-right bitmap border
     scaled_bitmap.draw_rect_filled(
-        scaled_bitmap_width - 1,
+        scaled_bitmap_width - thickness_thin,
         0,
-        1,
+        thickness_thin,
         scaled_bitmap_height,
-        COLOR_GRID_THIN,
+        chart_style.grid_color_thin,
     );
     scaled_bitmap.draw_rect_filled(
         0,
-        scaled_bitmap_height - 1,
+        scaled_bitmap_height - thickness_thin,
         scaled_bitmap_width,
-        1,
-        COLOR_GRID_THIN,
+        thickness_thin,
+        chart_style.grid_color_thin,
     );
 
-    // Add 10x10 grid
-    if add_thick_ten_grid {
+    // Add major grid
+    if add_major_grid {
         for bitmap_x in 0..bitmap.width {
             let logical_x = logical_first_coordinate_x + bitmap_x;
-            if logical_x % 10 == 0 {
+            if logical_x % major_grid_interval == 0 {
                 scaled_bitmap.draw_rect_filled(
-                    TILE_SIZE * bitmap_x,
+                    cell_size * bitmap_x,
                     0,
-                    2,
+                    thickness_thick,
                     scaled_bitmap_height,
-                    COLOR_GRID_THICK,
+                    chart_style.grid_color_thick,
                 );
             }
         }
         for bitmap_y in 0..bitmap.height {
             let logical_y = logical_first_coordinate_y + bitmap_y;
-            if logical_y % 10 == 0 {
+            if logical_y % major_grid_interval == 0 {
                 scaled_bitmap.draw_rect_filled(
                     0,
-                    TILE_SIZE * bitmap_y,
+                    cell_height * bitmap_y,
                     scaled_bitmap_width,
-                    2,
-                    COLOR_GRID_THICK,
+                    thickness_thick,
+                    chart_style.grid_color_thick,
                 );
             }
         }
-        // Close 10x10 grid line on bottom-right bitmap border if necessary
-        if (logical_first_coordinate_x + bitmap.width) % 10 == 0 {
+        // Close major grid line on bottom-right bitmap border if necessary
+        if (logical_first_coordinate_x + bitmap.width) % major_grid_interval == 0 {
             scaled_bitmap.draw_rect_filled(
-                scaled_bitmap_width - 2,
+                scaled_bitmap_width - thickness_thick,
                 0,
-                2,
+                thickness_thick,
                 scaled_bitmap_height,
-                COLOR_GRID_THICK,
+                chart_style.grid_color_thick,
             );
         }
-        if (logical_first_coordinate_y + bitmap.height) % 10 == 0 {
+        if (logical_first_coordinate_y + bitmap.height) % major_grid_interval == 0 {
             scaled_bitmap.draw_rect_filled(
                 0,
-                scaled_bitmap_height - 2,
+                scaled_bitmap_height - thickness_thick,
                 scaled_bitmap_width,
-                2,
-                COLOR_GRID_THICK,
+                thickness_thick,
+                chart_style.grid_color_thick,
             );
         }
     }
@@ -641,12 +1436,12 @@ fn create_cross_stitch_pattern(
     if add_origin_grid_bars {
         let origin_bitmap_coord_x = -logical_first_coordinate_x;
         if 0 < origin_bitmap_coord_x && origin_bitmap_coord_x < bitmap.width {
-            draw_origin_line_vertical(&mut scaled_bitmap, TILE_SIZE * origin_bitmap_coord_x);
+            draw_origin_line_vertical(&mut scaled_bitmap, cell_size * origin_bitmap_coord_x);
         }
 
         let origin_bitmap_coord_y = -logical_first_coordinate_y;
         if 0 < origin_bitmap_coord_y && origin_bitmap_coord_y < bitmap.height {
-            draw_origin_line_horizontal(&mut scaled_bitmap, TILE_SIZE * origin_bitmap_coord_y);
+            draw_origin_line_horizontal(&mut scaled_bitmap, cell_height * origin_bitmap_coord_y);
         }
 
         // NOTE: If our origin grid is located on the edge of our image we want to extend our image
@@ -683,17 +1478,23 @@ fn create_cross_stitch_pattern(
         }
     }
 
-    // Add 10-grid labels
-    let final_bitmap = if add_thick_ten_grid {
+    // Add major grid labels
+    let final_bitmap = if add_major_grid {
         // NOTE: At this point the scaled bitmap might not be an exact multiple of the original
         //       bitmap because we may have padded it while drawing the origin grid bars. Therefore
         //       the placement of the labels might be incorrectly shifted by two pixels. This is
         //       okay because it is not really visible and the code complexity to fix this is not
         //       worth it.
-        place_grid_labels_in_pattern(
+        let place_labels = match chart_style.grid_labels {
+            GridLabels::Coordinates => place_grid_labels_in_pattern,
+            GridLabels::KnittingRows => place_row_numbers_in_pattern,
+        };
+        place_labels(
             &scaled_bitmap,
-            TILE_SIZE,
+            Vec2i::new(cell_size, cell_height),
             font_grid_label,
+            chart_style.label_font_scale,
+            major_grid_interval,
             logical_first_coordinate_x,
             logical_first_coordinate_y,
         )
@@ -701,117 +1502,81 @@ fn create_cross_stitch_pattern(
         scaled_bitmap
     };
 
-    // Add segment index indicator if necessary
-    let final_bitmap = if let Some(segment_index) = segment_index {
+    // Add page markers if necessary
+    let final_bitmap = if let Some(page) = page {
+        let final_bitmap =
+            add_neighbour_page_markers(&final_bitmap, font_grid_label, &page.neighbour_page_numbers);
+
         let text_bitmap = Bitmap::create_from_text(
             font_segment_index_indicator,
-            &format!("\n Pattern Part {} \n", segment_index),
+            &format!("\n Pattern Part {} \n", page.number),
             1,
             PixelRGBA::white(),
         );
-        text_bitmap.glued_to(
+        let final_bitmap = text_bitmap.glued_to(
             &final_bitmap,
             GluePosition::TopCenter,
             0,
             PixelRGBA::white(),
-        )
+        );
+
+        // Add legend of the colors used on this page if necessary
+        if let Some(legend) = &page.legend {
+            legend.glued_to(
+                &final_bitmap,
+                GluePosition::BottomLeft,
+                TILE_SIZE,
+                PixelRGBA::white(),
+            )
+        } else {
+            final_bitmap
+        }
     } else {
         final_bitmap
     };
 
-    // Write out png image
-    let output_filepath = get_image_output_filepath(&image_filepath)
-        + "_"
-        + output_filename_suffix
-        + ".png";
-    Bitmap::write_to_png_file(&final_bitmap, &output_filepath);
+    Ok(add_footer(&final_bitmap, footer))
 }
 
+/// Renders the chart of the given stitches in all cross stitch pattern types. Without `segment`
+/// this is the chart of the complete pattern.
 fn create_cross_stitch_pattern_set(
+    setup: &ChartSetup,
     image: &Bitmap,
-    font_grid_label: &BitmapFont,
-    font_segment_index_indicator: &BitmapFont,
-    image_filepath: &str,
-    output_filename_suffix: &str,
-    color_mappings: &IndexMap<PixelRGBA, ColorInfo>,
-    segment_index: Option<usize>,
-    logical_first_coordinate_x: i32,
-    logical_first_coordinate_y: i32,
-    create_paint_by_number_set: bool,
-    add_origin_grid_bars: bool,
-) {
-    rayon::scope(|scope| {
-        scope.spawn(|_| {
-            create_cross_stitch_pattern(
-                &image,
-                font_grid_label,
-                font_segment_index_indicator,
-                &image_filepath,
-                &("cross_stitch_colorized_".to_owned() + output_filename_suffix),
-                &color_mappings,
-                segment_index,
-                logical_first_coordinate_x,
-                logical_first_coordinate_y,
-                PatternType::Colorized,
-                true,
-                add_origin_grid_bars,
-                PixelRGBA::white(),
-            );
-        });
-        scope.spawn(|_| {
-            create_cross_stitch_pattern(
-                &image,
-                font_grid_label,
-                font_segment_index_indicator,
-                &image_filepath,
-                &("cross_stitch_".to_owned() + output_filename_suffix),
-                &color_mappings,
-                segment_index,
-                logical_first_coordinate_x,
-                logical_first_coordinate_y,
-                PatternType::BlackAndWhite,
-                true,
-                add_origin_grid_bars,
-                PixelRGBA::white(),
-            );
-        });
-        scope.spawn(|_| {
-            create_cross_stitch_pattern(
-                &image,
-                font_grid_label,
-                font_segment_index_indicator,
-                &image_filepath,
-                &("cross_stitch_colorized_no_symbols_".to_owned() + output_filename_suffix),
-                &color_mappings,
-                segment_index,
-                logical_first_coordinate_x,
-                logical_first_coordinate_y,
-                PatternType::ColorizedNoSymbols,
-                true,
-                add_origin_grid_bars,
-                PixelRGBA::white(),
+    page: Option<&PatternPage>,
+    logical_first_coordinate: Vec2i,
+    pattern_name: &str,
+    centered: bool,
+    segment: Option<usize>,
+) -> Result<Vec<OutputFile>, ConversionError> {
+    let pattern_types = [
+        PatternType::Colorized,
+        PatternType::BlackAndWhite,
+        PatternType::ColorizedNoSymbols,
+        PatternType::ColoredSymbols,
+    ];
+
+    pattern_types
+        .par_iter()
+        .map(|pattern_type| {
+            let chart =
+                render_cross_stitch_pattern(setup, image, page, logical_first_coordinate, *pattern_type)?;
+            let filename = format!(
+                "{}_{}{}{}.png",
+                pattern_name,
+                pattern_type.output_filename_prefix(),
+                if centered { "centered_" } else { "" },
+                match segment {
+                    Some(segment) => format!("segment_{}", segment),
+                    None => "complete".to_owned(),
+                }
             );
-        });
-        if create_paint_by_number_set {
-            scope.spawn(|_| {
-                create_cross_stitch_pattern(
-                    &image,
-                    font_grid_label,
-                    font_segment_index_indicator,
-                    &image_filepath,
-                    &("paint_by_numbers_".to_owned() + output_filename_suffix),
-                    &color_mappings,
-                    segment_index,
-                    logical_first_coordinate_x,
-                    logical_first_coordinate_y,
-                    PatternType::PaintByNumbers,
-                    false,
-                    false,
-                    PixelRGBA::transparent(),
-                );
-            });
-        }
-    });
+            Ok(OutputFile {
+                entry: ManifestEntry::new_chart(filename, *pattern_type, centered, segment),
+                data: chart.encoded_as_png(),
+            })
+        })
+        .collect()
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -821,32 +1586,46 @@ fn create_color_mappings_from_image(
     image: &Bitmap,
     image_filepath: &str,
     symbols: &[Bitmap],
-    symbols_alphanum: &[Bitmap],
     stitch_images_premultiplied_alpha: &[Bitmap],
-    stitch_images_luminance_premultiplied_alpha: &[Bitmap]
-) -> IndexMap<PixelRGBA, ColorInfo> {
-    let mut color_mappings = image_extract_colors_and_counts(&image);
+    stitch_images_luminance_premultiplied_alpha: &[Bitmap],
+    stitch_colors_mapping: &HashMap<PixelRGBA, String>,
+    pinned_symbols: &HashMap<String, usize>,
+) -> Result<IndexMap<PixelRGBA, ColorInfo>, ConversionError> {
+    let mut color_mappings = image_extract_colors_and_counts(image);
 
     // Stitch symbols
-    assert!(
-        symbols.len() >= color_mappings.len(),
-        "Not enough symbols to map {} colors found in given image '{}' for cross stitch",
-        color_mappings.len(),
-        &image_filepath,
-    );
-    for (entry, symbol) in color_mappings.values_mut().zip(symbols.iter()) {
-        entry.symbol = symbol.clone();
+    let colors: Vec<PixelRGBA> = color_mappings.keys().cloned().collect();
+    let mut pinned_symbol_list: Vec<PinnedSymbol> = Vec::new();
+    for (color_index, color) in colors.iter().enumerate() {
+        let Some(thread) = stitch_colors_mapping.get(color) else {
+            continue;
+        };
+        let Some(&symbol_number) = pinned_symbols.get(thread) else {
+            continue;
+        };
+        if symbol_number == 0 {
+            return Err(ConversionError::InvalidPinnedSymbol {
+                thread: thread.clone(),
+                symbol_number,
+                reason: "symbol numbers start at 1".to_owned(),
+            });
+        }
+        pinned_symbol_list.push(PinnedSymbol {
+            color_index,
+            symbol_index: symbol_number - 1,
+            thread: thread.clone(),
+        });
     }
-
-    // Alphanum symbols
-    assert!(
-        symbols_alphanum.len() >= color_mappings.len(),
-        "Not enough symbols to map {} colors found in given image '{}' for paint by numbers",
-        color_mappings.len(),
-        &image_filepath,
-    );
-    for (entry, symbol_alphanum) in color_mappings.values_mut().zip(symbols_alphanum.iter()) {
-        entry.symbol_alphanum = symbol_alphanum.clone();
+    let symbol_assignment = symbol_assignment::assign_symbols(
+        image,
+        image_filepath,
+        &colors,
+        symbols,
+        PixelRGBA::white(),
+        &pinned_symbol_list,
+    )?;
+    for (entry, symbol_index) in color_mappings.values_mut().zip(symbol_assignment) {
+        entry.symbol = symbols[symbol_index].clone();
     }
 
     // Colorized stitch tiles
@@ -903,31 +1682,19 @@ fn create_color_mappings_from_image(
 
                 entry
                     .stitches_premultiplied
-                    .push(stitch.masked_by_premultiplied_alpha(&stitch_image_premultipllied));
+                    .push(stitch.masked_by_premultiplied_alpha(stitch_image_premultipllied));
             }
         }
     }
 
-    color_mappings
-}
-
-fn colour_distance(p1: &PixelRGBA, p2: &PixelRGBA) -> i64 {
-    let c1 = ArtColor::from_rgb(p1.r, p1.g, p1.b).unwrap_or_default();
-    let c2 = ArtColor::from_rgb(p2.r, p2.g, p2.b).unwrap_or_default();
-
-    (distance(&c1, &c2) * 1000f64) as i64
+    Ok(color_mappings)
 }
 
-fn find_closest_color(pixel: &PixelRGBA, stitch_colors_mapping: &HashMap<PixelRGBA, String>) -> PixelRGBA {
-    let (closest, _) = stitch_colors_mapping
-        .iter()
-        .map(|(stitch_pixel, _)| (stitch_pixel, colour_distance(pixel, stitch_pixel)))
-        .min_by(|(_, distance1), (_, distance2)| distance1.cmp(distance2))
-        .unwrap_or_else(|| {
-            panic!("Failed to find stitch color");
-        });
-
-    *closest
+/// Returns None if there are no stitch colors
+fn find_closest_color(pixel: &PixelRGBA, stitch_colors_mapping: &HashMap<PixelRGBA, String>, color_metric: ColorMetric) -> Option<PixelRGBA> {
+    stitch_colors_mapping.keys().map(|stitch_pixel| (stitch_pixel, color_metric.distance(*pixel, *stitch_pixel)))
+        .min_by(|(_, distance1), (_, distance2)| distance1.total_cmp(distance2))
+        .map(|(closest, _)| *closest)
 }
 
 fn image_extract_colors_and_counts(image: &Bitmap) -> IndexMap<PixelRGBA, ColorInfo> {
@@ -942,8 +1709,9 @@ fn image_extract_colors_and_counts(image: &Bitmap) -> IndexMap<PixelRGBA, ColorI
             color: *pixel,
             count: 0,
             symbol: Bitmap::new_empty(),
-            symbol_alphanum: Bitmap::new_empty(),
+            key: String::new(),
             stitches_premultiplied: Vec::new(),
+            brick_parts: None,
         });
         entry.count += 1;
     }
@@ -953,161 +1721,258 @@ fn image_extract_colors_and_counts(image: &Bitmap) -> IndexMap<PixelRGBA, ColorI
         PixelRGBA::compare_by_hue_luminosity_saturation(color_a, color_b)
     });
 
+    // NOTE: Written instructions key the colors in legend order, see `Instructions::new`
+    for (index, info) in color_mappings.values_mut().enumerate() {
+        info.key = letter_key(index);
+    }
+
     color_mappings
 }
 
+/// Counts the brick parts of every color of the given image. Does nothing for other crafts.
+fn assign_brick_parts<'a>(
+    resources: &Resources,
+    image: &Bitmap,
+    color_infos: impl Iterator<Item = &'a mut ColorInfo>,
+) {
+    if resources.craft != Craft::Bricks {
+        return;
+    }
+    let mut parts = count_parts(
+        image,
+        resources.bricks.baseplate_size,
+        resources.bricks.optimize_runs,
+    );
+    for info in color_infos {
+        info.brick_parts = parts.remove(&info.color);
+    }
+}
+
+fn brick_part_count(color_mappings: &IndexMap<PixelRGBA, ColorInfo>) -> usize {
+    color_mappings
+        .values()
+        .filter_map(|info| info.brick_parts)
+        .map(|parts| parts.total())
+        .sum()
+}
+
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 // Pattern dir creation
 
-fn create_patterns_dir(
+/// Splits the image into pattern pages that fit on the configured paper. Without a paper size we
+/// use fixed SPLIT_SEGMENT_WIDTH x SPLIT_SEGMENT_HEIGHT parts. Fails if a page is too small for
+/// the configured overlap.
+fn create_page_segments(
     image: &Bitmap,
-    image_filepath: &str,
     resources: &Resources,
     color_mappings: &IndexMap<PixelRGBA, ColorInfo>,
     stitch_colors_mapping: &HashMap<PixelRGBA, String>,
-) {
-    let (segment_images, segment_coordinates) =
-        image.to_segments(SPLIT_SEGMENT_WIDTH, SPLIT_SEGMENT_HEIGHT);
-
-    rayon::scope(|scope| {
-        // Legend
-        scope.spawn(|_| {
-            create_cross_stitch_legend(
-                image.dim(),
-                &color_mappings,
-                &image_filepath,
-                &resources.font,
-                &segment_coordinates,
-                stitch_colors_mapping
-            );
-        });
+    options: &ConversionOptions,
+    footer: Option<&Bitmap>,
+) -> Result<Vec<PageSegment>, ConversionError> {
+    let chart_style = &options.chart_style;
+    let page_setup = &options.page_setup;
+
+    // NOTE: Bead patterns are split into pegboards and brick mosaics into baseplates, regardless
+    //       of the paper size
+    let board_size = match resources.craft {
+        Craft::FuseBeads => Some(resources.fuse_beads.pegboard_size),
+        Craft::Bricks => Some(resources.bricks.baseplate_size),
+        _ => None,
+    };
+    if let Some(board_size) = board_size {
+        return Ok(compute_page_segments(
+            image.dim(),
+            Vec2i::filled(board_size),
+            0,
+            false,
+        ));
+    }
 
-        // Create patterns for complete set
-        scope.spawn(|_| {
-            create_cross_stitch_pattern_set(
-                &image,
-                &resources.font,
-                &resources.font_big,
-                &image_filepath,
-                "complete",
-                &color_mappings,
-                None,
-                0,
-                0,
-                true,
-                false,
-            );
-        });
+    // NOTE: We reserve space for everything that is glued around the stitch cells of a page. The
+    //       sizes of labels and markers are based on the longest possible text, including a minus
+    //       sign for the centered patterns. No page can have more pages than stitches or use more
+    //       colors than the whole image, so we measure their worst case.
+    let reserved_pixels = {
+        let font_grid_label = resources.font_for_label(chart_style.label_font);
+        let label_charcount_max = 1 + i32::max(image.width, image.height).to_string().len() as i32;
+        let label_padding = chart_style.label_font_scale
+            * font_grid_label.horizontal_advance_max
+            * (label_charcount_max + 4);
+        let header_height = 3 * resources.font_big.vertical_advance;
+
+        let page_number_max = (image.width * image.height) as usize;
+        let marker_width =
+            create_neighbour_page_marker(font_grid_label, page_number_max, Vec2i::new(1, 0)).width;
+        let marker_height =
+            create_neighbour_page_marker(font_grid_label, page_number_max, Vec2i::new(0, 1)).height;
+
+        let legend_height = if options.has_page_legends() {
+            TILE_SIZE
+                + create_page_legend(resources, image, color_mappings, stitch_colors_mapping)
+                    .height
+        } else {
+            0
+        };
+        let footer_height = footer.map(|footer| footer.height + TILE_SIZE).unwrap_or(0);
 
-        // Create patterns for individual segments if needed
-        if segment_images.len() > 1 {
-            segment_images
-                .par_iter()
-                .zip(segment_coordinates.par_iter())
-                .enumerate()
-                .for_each(|(segment_index, (segment_image, segment_coordinate))| {
-                    let label_start_x = SPLIT_SEGMENT_WIDTH * segment_coordinate.x;
-                    let label_start_y = SPLIT_SEGMENT_HEIGHT * segment_coordinate.y;
+        Vec2i::new(
+            2 * label_padding + 2 * marker_width,
+            2 * label_padding + 2 * marker_height + header_height + legend_height + footer_height,
+        )
+    };
 
-                    create_cross_stitch_pattern_set(
-                        segment_image,
-                        &resources.font,
-                        &resources.font_big,
-                        &image_filepath,
-                        &format!("segment_{}", segment_index + 1),
-                        &color_mappings,
-                        Some(segment_index + 1),
-                        label_start_x,
-                        label_start_y,
-                        false,
-                        false,
-                    );
-                });
-        }
-    });
+    let page_stitches = page_setup
+        .stitches_per_page(
+            Vec2i::new(chart_style.cell_size, chart_style.cell_height()),
+            reserved_pixels,
+        )
+        .map_err(ConversionError::InvalidOptions)?
+        .unwrap_or_else(|| Vec2i::new(SPLIT_SEGMENT_WIDTH, SPLIT_SEGMENT_HEIGHT));
+    if page_stitches.x <= 2 * page_setup.overlap || page_stitches.y <= 2 * page_setup.overlap {
+        return Err(ConversionError::InvalidOptions(format!(
+            "A page of {}x{} stitches is too small for an overlap of {} stitches",
+            page_stitches.x, page_stitches.y, page_setup.overlap
+        )));
+    }
+    Ok(compute_page_segments(
+        image.dim(),
+        page_stitches,
+        page_setup.overlap,
+        page_setup.balanced,
+    ))
 }
 
-fn create_patterns_dir_centered(
+fn create_pattern_page(
     image: &Bitmap,
-    image_filepath: &str,
+    page_segments: &[PageSegment],
+    segment_index: usize,
     resources: &Resources,
     color_mappings: &IndexMap<PixelRGBA, ColorInfo>,
     stitch_colors_mapping: &HashMap<PixelRGBA, String>,
-) {
-    let image_center_x = make_even_upwards(image.width) / 2;
-    let image_center_y = make_even_upwards(image.height) / 2;
-
-    let (segment_images, segment_coordinates) =
-        image.to_segments(SPLIT_SEGMENT_WIDTH, SPLIT_SEGMENT_HEIGHT);
-
-    rayon::scope(|scope| {
-        // Legend
-        scope.spawn(|_| {
-            create_cross_stitch_legend(
-                image.dim(),
-                &color_mappings,
-                &image_filepath,
-                &resources.font,
-                &segment_coordinates,
-                stitch_colors_mapping
-            );
-        });
+    add_page_legend: bool,
+) -> PatternPage {
+    let segment = &page_segments[segment_index];
+    let page_number_at = |offset: Vec2i| {
+        page_segments
+            .iter()
+            .position(|other| other.layout_index == segment.layout_index + offset)
+            .map(|other_index| other_index + 1)
+    };
 
+    PatternPage {
+        number: segment_index + 1,
+        unshaded_rect: Recti::from_xy_width_height(
+            segment.rect_unshared.left() - segment.rect.left(),
+            segment.rect_unshared.top() - segment.rect.top(),
+            segment.rect_unshared.width(),
+            segment.rect_unshared.height(),
+        ),
+        neighbour_page_numbers: NeighbourPageNumbers {
+            left: page_number_at(Vec2i::new(-1, 0)),
+            top: page_number_at(Vec2i::new(0, -1)),
+            right: page_number_at(Vec2i::new(1, 0)),
+            bottom: page_number_at(Vec2i::new(0, 1)),
+        },
+        legend: if add_page_legend {
+            Some(create_page_legend(
+                resources,
+                &image.cropped_by_rect(segment.rect_owned),
+                color_mappings,
+                stitch_colors_mapping,
+            ))
+        } else {
+            None
+        },
+    }
+}
+
+/// Renders the charts of the complete pattern and of every page. Centered charts label their
+/// stitches relative to the center of the image.
+fn create_charts(pattern: &Pattern, centered: bool) -> Result<Vec<OutputFile>, ConversionError> {
+    let _timer = TimerScoped::new_scoped(
+        &format!(
+            "Rendering {}charts of '{}'",
+            if centered { "centered " } else { "" },
+            pattern.name
+        ),
+        true,
+    );
+    log::info!(
+        "'{}': rendering {}charts",
+        pattern.name,
+        if centered { "centered " } else { "" }
+    );
+
+    let image = &pattern.image;
+    let page_segments = &pattern.page_segments;
+    let setup = pattern.chart_setup(centered);
+
+    let (result_complete, result_segments) = rayon::join(
         // Create patterns for complete set
-        scope.spawn(|_| {
+        || {
             create_cross_stitch_pattern_set(
-                &image,
-                &resources.font,
-                &resources.font_big,
-                &image_filepath,
-                "complete",
-                &color_mappings,
+                &setup,
+                image,
                 None,
-                -image_center_x,
-                -image_center_y,
-                true,
-                true,
-            );
-        });
-
+                pattern.logical_first_coordinate(Vec2i::zero(), centered),
+                &pattern.name,
+                centered,
+                None,
+            )
+        },
         // Create patterns for individual segments if needed
-        if segment_images.len() > 1 {
-            segment_images
+        || {
+            if page_segments.len() <= 1 {
+                return Ok(Vec::new());
+            }
+            page_segments
                 .par_iter()
-                .zip(segment_coordinates.par_iter())
                 .enumerate()
-                .for_each(|(segment_index, (segment_image, segment_coordinate))| {
-                    let logical_first_coordinate_x =
-                        SPLIT_SEGMENT_WIDTH * segment_coordinate.x - image_center_x;
-                    let logical_first_coordinate_y =
-                        SPLIT_SEGMENT_HEIGHT * segment_coordinate.y - image_center_y;
-
-                    create_cross_stitch_pattern_set(
-                        segment_image,
-                        &resources.font,
-                        &resources.font_big,
-                        &image_filepath,
-                        &format!("segment_{}", segment_index + 1),
-                        &color_mappings,
-                        Some(segment_index + 1),
-                        logical_first_coordinate_x,
-                        logical_first_coordinate_y,
-                        false,
+                .map(|(segment_index, segment)| {
+                    let _timer = TimerScoped::new_scoped(
+                        &format!(
+                            "Rendering segment {}/{} of '{}'",
+                            segment_index + 1,
+                            page_segments.len(),
+                            pattern.name
+                        ),
                         true,
                     );
-                });
-        }
-    });
+                    let page = pattern.create_page(segment_index);
+                    create_cross_stitch_pattern_set(
+                        &setup,
+                        &image.cropped_by_rect(segment.rect),
+                        Some(&page),
+                        pattern.logical_first_coordinate(segment.rect.pos, centered),
+                        &pattern.name,
+                        centered,
+                        Some(segment_index + 1),
+                    )
+                })
+                .collect::<Result<Vec<_>, ConversionError>>()
+                .map(|segment_files| segment_files.concat())
+        },
+    );
+
+    let mut result = result_complete?;
+    result.extend(result_segments?);
+    Ok(result)
 }
 
-fn create_cross_stitch_pattern_preview(
+/// Renders the pattern as it would look like stitched on fabric
+fn render_cross_stitch_preview(
     bitmap: &Bitmap,
-    image_filepath: &str,
-    output_filename_suffix: &str,
     resources: &Resources,
     color_mappings: &IndexMap<PixelRGBA, ColorInfo>,
-) {
-    let bitmap = bitmap.extended(10, 10, 10, 10, PixelRGBA::transparent());
+) -> Result<Bitmap, ConversionError> {
+    let bitmap = bitmap.extended(
+        PREVIEW_MARGIN_STITCHES,
+        PREVIEW_MARGIN_STITCHES,
+        PREVIEW_MARGIN_STITCHES,
+        PREVIEW_MARGIN_STITCHES,
+        PixelRGBA::transparent(),
+    );
     let tile_width = resources
         .stitch_background_image_8x8_premultiplied_alpha
         .width
@@ -1139,6 +2004,9 @@ fn create_cross_stitch_pattern_preview(
                 .blit_to(&mut background_layer, pos, true);
         }
     }
+    if resources.craft == Craft::CrossStitch && resources.fabric.hand_dyed {
+        apply_hand_dyed_variation(&mut background_layer, resources.preview.seed);
+    }
 
 
     // Stitches only
@@ -1147,7 +2015,8 @@ fn create_cross_stitch_pattern_preview(
         (tile_height * bitmap.height) as u32,
     );
 
-    let mut random = Random::new_from_seed(1234);
+    let mut random = Random::new_from_seed(resources.preview.seed);
+    let color_jitter = resources.preview.color_jitter;
     for y in 0..bitmap.height {
         for x in 0..bitmap.width {
             let color = bitmap.get(x, y);
@@ -1156,11 +2025,37 @@ fn create_cross_stitch_pattern_preview(
             if color.a != 0 {
                 let tile_pos_center =
                     Vec2i::new(tile_width * x, tile_height * y) + (tile_width / 2);
-                let stitches = &color_mappings.get(&color).unwrap().stitches_premultiplied;
+                let stitches = &color_mappings
+                    .get(&color)
+                    .ok_or(ConversionError::UnmappedColor(color))?
+                    .stitches_premultiplied;
                 let stitches_count = stitches.len();
                 let stitch =
                     &stitches[random.u32_bounded_exclusive(stitches_count as u32) as usize];
                 let stitch_center = Vec2i::new(stitch.width / 2, stitch.height / 2);
+
+                // NOTE: Real stitches vary slightly in brightness depending on thread tension
+                let jittered_stitch;
+                let stitch = if color_jitter > 0.0 {
+                    let brightness = random.f32_in_range(1.0 - color_jitter, 1.0 + color_jitter);
+                    let mut stitch = stitch.clone();
+                    for pixel in stitch.data.iter_mut() {
+                        // NOTE: Our stitches are premultiplied, so no channel may exceed alpha
+                        let jitter_channel = |channel: u8| {
+                            u8::min((channel as f32 * brightness).round() as u8, pixel.a)
+                        };
+                        *pixel = PixelRGBA::new(
+                            jitter_channel(pixel.r),
+                            jitter_channel(pixel.g),
+                            jitter_channel(pixel.b),
+                            pixel.a,
+                        );
+                    }
+                    jittered_stitch = stitch;
+                    &jittered_stitch
+                } else {
+                    stitch
+                };
                 stitch.blit_to_alpha_blended_premultiplied(
                     &mut colored_stitches_layer,
                     tile_pos_center - stitch_center,
@@ -1179,71 +2074,674 @@ fn create_cross_stitch_pattern_preview(
         false,
         ColorBlendMode::Normal,
     );
-    // Write out png image
-    let output_filepath = get_image_output_filepath(&image_filepath)
-        + "_"
-        + output_filename_suffix
-        + ".png";
-    Bitmap::write_to_png_file(&combined, &output_filepath);
+
+    // NOTE: Our tiles are square, but knitted and crocheted stitches are wider than tall
+    if resources.craft.uses_gauge() {
+        let squashed_height = (combined.height as f32 * resources.stitch_aspect()).round();
+        combined = combined.scaled_area_average(combined.width as u32, (squashed_height as u32).max(1));
+    }
+    Ok(combined)
 }
 
-fn create_preview_dir(
+/// Renders the stitched preview of the given image such that one stitch covers
+/// `dpi / fabric_count` pixels. The image is padded with unstitched fabric by the given amount
+/// of stitches.
+fn render_cross_stitch_preview_at_scale(
     image: &Bitmap,
-    image_filepath: &str,
     resources: &Resources,
     color_mappings: &IndexMap<PixelRGBA, ColorInfo>,
-) {
-    rayon::scope(|scope| {
-        // Create stitched preview
-        scope.spawn(|_| {
-            create_cross_stitch_pattern_preview(
-                &image,
-                &image_filepath,
-                "complete",
-                resources,
-                &color_mappings,
-            );
-        });
-    });
+    fabric_count: f32,
+    dpi: f32,
+    padding: Vec2i,
+) -> Result<Bitmap, ConversionError> {
+    let padded_image = image.extended(
+        padding.x,
+        padding.y,
+        padding.x,
+        padding.y,
+        PixelRGBA::transparent(),
+    );
+    let preview = render_cross_stitch_preview(&padded_image, resources, color_mappings)?;
+
+    let tile_width = resources
+        .stitch_background_image_8x8_premultiplied_alpha
+        .width as f32
+        / 8.0;
+    let scale = dpi / fabric_count / tile_width;
+    let scaled_width = ((preview.width as f32 * scale).round() as u32).max(1);
+    let scaled_height = ((preview.height as f32 * scale).round() as u32).max(1);
+    Ok(preview.scaled_area_average(scaled_width, scaled_height))
 }
 
-////////////////////////////////////////////////////////////////////////////////////////////////////
-// Legend creation
+fn mockup_fabric_count(preview_setup: &PreviewSetup) -> f32 {
+    preview_setup
+        .fabric_count
+        .unwrap_or(MOCKUP_FABRIC_COUNT_DEFAULT)
+}
 
-fn create_pattern_page_layout(font: &BitmapFont, layout_indices: &[Vec2i]) -> Bitmap {
-    let caption_image =
-        Bitmap::create_from_text(font, "\n\nPattern parts overview:\n", 1, PixelRGBA::white());
+/// Returns the diameter of the smallest hoop that holds the stitched image
+fn mockup_hoop_diameter_inches(image_dim: Vec2i, resources: &Resources, fabric_count: f32) -> f32 {
+    // NOTE: The fabric count is given in stitches per inch, while knitted and crocheted rows are
+    //       shorter than their stitches are wide
+    hoop_diameter_inches(
+        image_dim.x as f32 / fabric_count,
+        image_dim.y as f32 * resources.stitch_aspect() / fabric_count,
+    )
+}
 
-    let page_count = layout_indices.len();
-    // NOTE: Indexes begin at 0 therefore we add 1
-    let num_rows = 1 + layout_indices.iter().map(|v| v.y).max().unwrap();
-    let num_columns = 1 + layout_indices.iter().map(|v| v.x).max().unwrap();
+/// Returns by how many stitches and rows the image must be padded with unstitched fabric to fill
+/// the given mockup
+fn mockup_fabric_padding(
+    image_dim: Vec2i,
+    resources: &Resources,
+    mockup: MockupType,
+    fabric_count: f32,
+) -> Vec2i {
+    // NOTE: The preview already adds a margin of unstitched stitches around the image
+    let preview_margin = PREVIEW_MARGIN_STITCHES;
+    let stitch_aspect = resources.stitch_aspect();
+    let length_in_stitches_and_rows = |inches: f32| {
+        Vec2::new(inches * fabric_count, inches * fabric_count / stitch_aspect)
+    };
+    match mockup {
+        MockupType::Hoop => {
+            let diameter = length_in_stitches_and_rows(mockup_hoop_diameter_inches(
+                image_dim,
+                resources,
+                fabric_count,
+            ));
+            let padding_for_length = |diameter: f32, length: i32| {
+                i32::max(0, (diameter.ceil() as i32 - length + 1) / 2 + 1 - preview_margin)
+            };
+            Vec2i::new(
+                padding_for_length(diameter.x, image_dim.x),
+                padding_for_length(diameter.y, image_dim.y),
+            )
+        }
+        MockupType::Frame => {
+            let margin = length_in_stitches_and_rows(FRAME_FABRIC_MARGIN_INCHES);
+            Vec2i::new(
+                i32::max(0, margin.x.round() as i32 - preview_margin),
+                i32::max(0, margin.y.round() as i32 - preview_margin),
+            )
+        }
+    }
+}
 
-    let page_tile_dim = {
-        // NOTE: We want to have a 1px visual gap between page tiles therefore we add 1
-        let page_tile_width = 1 + font
-            .get_text_bounding_rect(&format!(" {} ", page_count), 1, false)
-            .dim
-            .x;
-        let page_tile_height = 1 + (page_tile_width as f32 * (9.0 / 6.0)) as i32;
-        Vec2i::new(page_tile_width, page_tile_height)
+/// Returns the biggest pixel count of the bitmaps that are rendered for the real-scale preview
+/// and the mockups of an image of the given size. This allows rejecting a preview setup before
+/// rendering it.
+fn real_scale_previews_pixel_count_max(
+    image_dim: Vec2i,
+    resources: &Resources,
+    preview_setup: &PreviewSetup,
+) -> i64 {
+    let tile_dim = resources
+        .stitch_background_image_8x8_premultiplied_alpha
+        .dim()
+        / 8;
+    let pixel_count_at_scale = |fabric_count: f32, padding: Vec2i| {
+        let stitch_count = image_dim + 2 * (padding + PREVIEW_MARGIN_STITCHES);
+        let unscaled_width = stitch_count.x as f32 * tile_dim.x as f32;
+        let unscaled_height = stitch_count.y as f32 * tile_dim.y as f32;
+        let scale = preview_setup.dpi / fabric_count / tile_dim.x as f32;
+        let scaled_width = unscaled_width * scale;
+        let scaled_height = unscaled_height * resources.stitch_aspect() * scale;
+        f32::max(unscaled_width * unscaled_height, scaled_width * scaled_height) as i64
     };
 
-    let image_width = num_columns * page_tile_dim.x;
-    let image_height = num_rows * page_tile_dim.y;
-    let mut image = Bitmap::new_filled(image_width as u32, image_height as u32, PixelRGBA::white());
-    for (page_index, pos_index) in layout_indices.iter().enumerate() {
-        let pos = *pos_index * page_tile_dim;
-        image.draw_rect(
-            pos.x,
-            pos.y,
-            page_tile_dim.x - 1,
-            page_tile_dim.y - 1,
-            PixelRGBA::black(),
-        );
-        image.draw_text_aligned_in_point(
-            font,
-            &(page_index + 1).to_string(),
+    let mut result = 0;
+    if let Some(fabric_count) = preview_setup.fabric_count {
+        result = pixel_count_at_scale(fabric_count, Vec2i::zero());
+    }
+    let fabric_count = mockup_fabric_count(preview_setup);
+    for (mockup, _) in &resources.mockup_images {
+        let padding = mockup_fabric_padding(image_dim, resources, *mockup, fabric_count);
+        result = i64::max(result, pixel_count_at_scale(fabric_count, padding));
+    }
+    result
+}
+
+/// Renders the stitched preview at its finished size and the requested mockups. The output
+/// files carry their DPI so that they print at the true size.
+fn create_real_scale_previews(
+    image: &Bitmap,
+    pattern_name: &str,
+    resources: &Resources,
+    color_mappings: &IndexMap<PixelRGBA, ColorInfo>,
+    preview_setup: &PreviewSetup,
+) -> Result<Vec<OutputFile>, ConversionError> {
+    let dpi = preview_setup.dpi;
+    let mut result = Vec::new();
+
+    if let Some(fabric_count) = preview_setup.fabric_count {
+        let preview = render_cross_stitch_preview_at_scale(
+            image,
+            resources,
+            color_mappings,
+            fabric_count,
+            dpi,
+            Vec2i::zero(),
+        )?;
+        result.push(OutputFile::new(
+            pattern_name.to_owned() + "_real_scale.png",
+            "real_scale_preview",
+            preview.encoded_as_png_with_dpi(dpi),
+        ));
+    }
+
+    let fabric_count = mockup_fabric_count(preview_setup);
+    for (mockup, mockup_resource_image) in &resources.mockup_images {
+        let padding = mockup_fabric_padding(image.dim(), resources, *mockup, fabric_count);
+        let fabric = render_cross_stitch_preview_at_scale(
+            image,
+            resources,
+            color_mappings,
+            fabric_count,
+            dpi,
+            padding,
+        )?;
+        let (mockup_image, suffix) = match mockup {
+            MockupType::Hoop => {
+                let diameter_inches = mockup_hoop_diameter_inches(image.dim(), resources, fabric_count);
+                (
+                    create_hoop_mockup(mockup_resource_image, &fabric, diameter_inches, dpi)?,
+                    "hoop",
+                )
+            }
+            MockupType::Frame => (
+                create_frame_mockup(mockup_resource_image, &fabric, dpi),
+                "frame",
+            ),
+        };
+        result.push(OutputFile::new(
+            pattern_name.to_owned() + "_mockup_" + suffix + ".png",
+            "mockup",
+            mockup_image.encoded_as_png_with_dpi(dpi),
+        ));
+    }
+    Ok(result)
+}
+
+fn create_previews(
+    image: &Bitmap,
+    pattern_name: &str,
+    resources: &Resources,
+    color_mappings: &IndexMap<PixelRGBA, ColorInfo>,
+    preview_setup: &PreviewSetup,
+) -> Result<Vec<OutputFile>, ConversionError> {
+    let _timer = TimerScoped::new_scoped(&format!("Rendering previews of '{}'", pattern_name), true);
+    log::info!("'{}': rendering previews", pattern_name);
+
+    let (result_preview, result_real_scale) = rayon::join(
+        // Create stitched preview
+        || render_cross_stitch_preview(image, resources, color_mappings),
+        // Create real-scale preview and mockups
+        || {
+            create_real_scale_previews(
+                image,
+                pattern_name,
+                resources,
+                color_mappings,
+                preview_setup,
+            )
+        },
+    );
+    let mut result = vec![OutputFile::new(
+        pattern_name.to_owned() + "_complete.png",
+        "preview",
+        result_preview?.encoded_as_png(),
+    )];
+    result.extend(result_real_scale?);
+    Ok(result)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Comparison
+
+/// Summarizes how far the thread colors are from the colors of the original image
+#[derive(Debug, Clone, Serialize)]
+pub struct ColorErrorStats {
+    pub color_metric: String,
+    pub mean_error: f64,
+    pub max_error: f64,
+    /// ΔE 2000 regardless of the selected metric, so that conversions with different metrics
+    /// can be compared
+    pub mean_delta_e: f64,
+    pub max_delta_e: f64,
+    pub stitch_count: usize,
+}
+
+/// Maps 0 to dark blue over purple and orange to light yellow at 1
+fn heatmap_color(percent: f32) -> PixelRGBA {
+    let stops = [
+        Color::from_rgb_bytes(16, 16, 64),
+        Color::from_rgb_bytes(128, 32, 128),
+        Color::from_rgb_bytes(240, 112, 32),
+        Color::from_rgb_bytes(255, 255, 160),
+    ];
+    let position = clampf(percent, 0.0, 1.0) * (stops.len() - 1) as f32;
+    let index = usize::min(position as usize, stops.len() - 2);
+    Color::lerp(stops[index], stops[index + 1], position - index as f32).to_pixelrgba()
+}
+
+/// Renders the original image, the image in thread colors and a heatmap of the color error of
+/// every stitch side by side. The error statistics are added as json.
+fn create_comparison(
+    original: &Bitmap,
+    converted: &Bitmap,
+    pattern_name: &str,
+    resources: &Resources,
+    color_metric: ColorMetric,
+) -> Result<Vec<OutputFile>, ConversionError> {
+    let _timer = TimerScoped::new_scoped(&format!("Comparing colors of '{}'", pattern_name), true);
+    let (comparison, stats) = render_comparison(original, converted, resources, color_metric);
+    log::info!(
+        "Color error of '{}' ({}): mean {:.2}, max {:.2} - ΔE 2000: mean {:.2}, max {:.2}",
+        pattern_name,
+        stats.color_metric,
+        stats.mean_error,
+        stats.max_error,
+        stats.mean_delta_e,
+        stats.max_delta_e
+    );
+
+    let stats_filename = pattern_name.to_owned() + "_comparison.json";
+    let stats_json = encode_json(&stats, &stats_filename)?;
+    Ok(vec![
+        OutputFile::new(
+            pattern_name.to_owned() + "_comparison.png",
+            "comparison",
+            comparison.encoded_as_png(),
+        ),
+        OutputFile::new(stats_filename, "color_error_stats", stats_json),
+    ])
+}
+
+fn render_comparison(
+    original: &Bitmap,
+    converted: &Bitmap,
+    resources: &Resources,
+    color_metric: ColorMetric,
+) -> (Bitmap, ColorErrorStats) {
+    let mut heatmap = Bitmap::new(original.width as u32, original.height as u32);
+    let mut error_sum = 0.0;
+    let mut error_max: f64 = 0.0;
+    let mut delta_e_sum = 0.0;
+    let mut delta_e_max: f64 = 0.0;
+    let mut stitch_count = 0;
+    for y in 0..original.height {
+        for x in 0..original.width {
+            let original_color = original.get(x, y);
+            let converted_color = converted.get(x, y);
+            if original_color.a == 0 {
+                continue;
+            }
+
+            let error = color_metric.distance(original_color, converted_color);
+            let delta_e = if color_metric == ColorMetric::Ciede2000 {
+                error
+            } else {
+                ColorMetric::Ciede2000.distance(original_color, converted_color)
+            };
+            error_sum += error;
+            error_max = error_max.max(error);
+            delta_e_sum += delta_e;
+            delta_e_max = delta_e_max.max(delta_e);
+            stitch_count += 1;
+
+            heatmap.set(
+                x,
+                y,
+                heatmap_color((error / color_metric.clearly_visible_error()) as f32),
+            );
+        }
+    }
+
+    let stats = ColorErrorStats {
+        color_metric: color_metric.name().to_owned(),
+        mean_error: error_sum / stitch_count.max(1) as f64,
+        max_error: error_max,
+        mean_delta_e: delta_e_sum / stitch_count.max(1) as f64,
+        max_delta_e: delta_e_max,
+        stitch_count,
+    };
+
+    // Panels
+    let scale = i32::max(
+        1,
+        COMPARISON_PANEL_SIZE_MIN / i32::max(original.width, original.height),
+    );
+    let create_panel = |image: &Bitmap, label: &str| {
+        Bitmap::create_from_text(&resources.font, label, 1, PixelRGBA::white()).glued_to(
+            &image.scaled(scale),
+            GluePosition::TopLeft,
+            TILE_SIZE / 4,
+            PixelRGBA::white(),
+        )
+    };
+
+    let mut heatmap_bar = Bitmap::new(
+        (original.width * scale) as u32,
+        COMPARISON_HEATMAP_BAR_HEIGHT as u32,
+    );
+    for x in 0..heatmap_bar.width {
+        let color = heatmap_color(x as f32 / (heatmap_bar.width - 1).max(1) as f32);
+        heatmap_bar.draw_rect_filled(x, 0, 1, heatmap_bar.height, color);
+    }
+    let heatmap_bar_label = Bitmap::create_from_text(
+        &resources.font,
+        &format!("0 - {}+", color_metric.clearly_visible_error()),
+        1,
+        PixelRGBA::white(),
+    );
+    let heatmap_panel = create_panel(
+        &heatmap,
+        &format!("Color error ({})", color_metric.name()),
+    )
+    .glued_to(
+        &heatmap_bar,
+        GluePosition::TopLeft,
+        TILE_SIZE / 4,
+        PixelRGBA::white(),
+    )
+    .glued_to(
+        &heatmap_bar_label,
+        GluePosition::TopLeft,
+        TILE_SIZE / 4,
+        PixelRGBA::white(),
+    );
+
+    let panels = Bitmap::glue_together_multiple(
+        &[
+            create_panel(original, "Original"),
+            create_panel(converted, "Thread colors"),
+            heatmap_panel,
+        ],
+        GluePosition::RightTop,
+        TILE_SIZE,
+        PixelRGBA::white(),
+    );
+    let stats_text = format!(
+        "{}: mean {:.2}, max {:.2}\ndE2000: mean {:.2}, max {:.2}",
+        stats.color_metric, stats.mean_error, stats.max_error, stats.mean_delta_e, stats.max_delta_e
+    );
+    let padding = TILE_SIZE;
+    let comparison = panels
+        .glued_to(
+            &Bitmap::create_from_text(&resources.font, &stats_text, 1, PixelRGBA::white()),
+            GluePosition::TopLeft,
+            TILE_SIZE,
+            PixelRGBA::white(),
+        )
+        .extended(padding, padding, padding, padding, PixelRGBA::white());
+
+    (comparison, stats)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Cover page and footer
+
+/// Returns a footer line with the pattern metadata if any was given
+fn create_footer(font: &BitmapFont, metadata: &PatternMetadata) -> Option<Bitmap> {
+    let entries = metadata.footer_entries();
+    if entries.is_empty() {
+        return None;
+    }
+
+    Some(Bitmap::create_from_text(
+        font,
+        &format!(" {} ", entries.join("  |  ")),
+        1,
+        PixelRGBA::white(),
+    ))
+}
+
+/// Returns the given text with an uppercase first letter
+fn capitalized(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn add_footer(bitmap: &Bitmap, footer: Option<&Bitmap>) -> Bitmap {
+    if let Some(footer) = footer {
+        footer
+            .glued_to(bitmap, GluePosition::BottomCenter, TILE_SIZE / 2, PixelRGBA::white())
+            .extended(0, 0, 0, TILE_SIZE / 2, PixelRGBA::white())
+    } else {
+        bitmap.clone()
+    }
+}
+
+fn create_cover_page(
+    image: &Bitmap,
+    pattern_name: &str,
+    resources: &Resources,
+    color_mappings: &IndexMap<PixelRGBA, ColorInfo>,
+    metadata: &PatternMetadata,
+) -> Result<OutputFile, ConversionError> {
+    let _timer = TimerScoped::new_scoped(&format!("Rendering cover page of '{}'", pattern_name), true);
+    log::info!("'{}': rendering cover page", pattern_name);
+    let cover = render_cover_page(image, pattern_name, resources, color_mappings, metadata)?;
+    Ok(OutputFile::new(
+        pattern_name.to_owned() + "_cover.png",
+        "cover_page",
+        cover.encoded_as_png(),
+    ))
+}
+
+/// Renders a cover page with the title, a stitched preview and stats. `name` is used as title if
+/// the metadata has none.
+fn render_cover_page(
+    image: &Bitmap,
+    name: &str,
+    resources: &Resources,
+    color_mappings: &IndexMap<PixelRGBA, ColorInfo>,
+    metadata: &PatternMetadata,
+) -> Result<Bitmap, ConversionError> {
+    let mut parts = Vec::new();
+
+    let title = metadata.title.clone().unwrap_or_else(|| name.to_owned());
+    parts.push(Bitmap::create_from_text(
+        &resources.font_big,
+        &title,
+        2,
+        PixelRGBA::white(),
+    ));
+    if let Some(designer) = &metadata.designer {
+        parts.push(Bitmap::create_from_text(
+            &resources.font_big,
+            &format!("by {}", designer),
+            1,
+            PixelRGBA::white(),
+        ));
+    }
+
+    let preview = render_cross_stitch_preview(image, resources, color_mappings)?;
+    let text_width = i32::clamp(preview.width, COVER_PAGE_TEXT_WIDTH_MIN, COVER_PAGE_TEXT_WIDTH_MAX);
+    parts.push(preview);
+
+    // Size and stats
+    let stitch_count: usize = color_mappings.values().map(|info| info.count).sum();
+    let cell_name_plural = resources.craft.cell_name_plural();
+    let mut stats = format!(
+        "Size:     {}x{} {}\nColors:   {}\n{:<10}{}\n\nFinished size:\n",
+        image.width,
+        image.height,
+        cell_name_plural,
+        color_mappings.len(),
+        capitalized(cell_name_plural) + ":",
+        stitch_count
+    );
+    match resources.craft {
+        Craft::CrossStitch => {
+            for fabric_count in &FABRIC_COUNTS {
+                let width_inches = image.width as f32 / *fabric_count as f32;
+                let height_inches = image.height as f32 / *fabric_count as f32;
+                stats += &format!(
+                    "  {} count: {:.1}x{:.1} in ({:.1}x{:.1} cm)\n",
+                    fabric_count,
+                    width_inches,
+                    height_inches,
+                    width_inches * 2.54,
+                    height_inches * 2.54,
+                );
+            }
+        }
+        Craft::DiamondPainting => {
+            let (width_cm, height_cm) = canvas_size_cm(image.width, image.height);
+            stats += &format!(
+                "  {:.1}x{:.1} cm ({:.1}x{:.1} in) at {} mm drills\n",
+                width_cm,
+                height_cm,
+                width_cm / 2.54,
+                height_cm / 2.54,
+                DRILL_PITCH_MM
+            );
+        }
+        Craft::FuseBeads => {
+            let pegboard_size = resources.fuse_beads.pegboard_size;
+            let width_cm = image.width as f32 * BEAD_PITCH_MM / 10.0;
+            let height_cm = image.height as f32 * BEAD_PITCH_MM / 10.0;
+            stats += &format!(
+                "  {:.1}x{:.1} cm ({:.1}x{:.1} in) at {} mm beads\n  {}x{} pegboards of {}x{} pegs\n",
+                width_cm,
+                height_cm,
+                width_cm / 2.54,
+                height_cm / 2.54,
+                BEAD_PITCH_MM,
+                (image.width + pegboard_size - 1) / pegboard_size,
+                (image.height + pegboard_size - 1) / pegboard_size,
+                pegboard_size,
+                pegboard_size
+            );
+        }
+        Craft::Knitting | Craft::Crochet => {
+            let knitting = &resources.knitting;
+            let (width_cm, height_cm) = knitting.finished_size_cm(image.width, image.height);
+            let (yarn_meters, yarn_yards) = knitting.yarn_length(resources.craft, stitch_count);
+            stats += &format!(
+                "  {:.1}x{:.1} cm ({:.1}x{:.1} in) at {} stitches and {} rows per 10 cm\n\nYarn:\n  about {:.0} m ({:.0} yd) including {}% spare\n",
+                width_cm,
+                height_cm,
+                width_cm / 2.54,
+                height_cm / 2.54,
+                knitting.stitches_per_10cm,
+                knitting.rows_per_10cm,
+                yarn_meters,
+                yarn_yards,
+                knitting.spare_percent
+            );
+        }
+        Craft::Bricks => {
+            let baseplate_size = resources.bricks.baseplate_size;
+            let width_cm = image.width as f32 * STUD_PITCH_MM / 10.0;
+            let height_cm = image.height as f32 * STUD_PITCH_MM / 10.0;
+            stats += &format!(
+                "  {:.1}x{:.1} cm ({:.1}x{:.1} in) at {} mm studs\n  {}x{} baseplates of {}x{} studs\n\nParts:\n  {} {}\n",
+                width_cm,
+                height_cm,
+                width_cm / 2.54,
+                height_cm / 2.54,
+                STUD_PITCH_MM,
+                (image.width + baseplate_size - 1) / baseplate_size,
+                (image.height + baseplate_size - 1) / baseplate_size,
+                baseplate_size,
+                baseplate_size,
+                brick_part_count(color_mappings),
+                resources.bricks.part.name_plural()
+            );
+        }
+    }
+    parts.push(Bitmap::create_from_text(
+        &resources.font_big,
+        &stats,
+        1,
+        PixelRGBA::white(),
+    ));
+
+    if let Some(description) = &metadata.description {
+        let description = resources
+            .font_big
+            .wrap_text_for_pixelwidth(description, text_width as u32);
+        parts.push(Bitmap::create_from_text(
+            &resources.font_big,
+            &description,
+            1,
+            PixelRGBA::white(),
+        ));
+    }
+
+    let details: Vec<String> = [
+        metadata.website.clone(),
+        metadata.version.as_ref().map(|version| format!("Version {}", version)),
+        metadata.date.clone(),
+        metadata.copyright.clone(),
+    ]
+    .iter()
+    .flatten()
+    .cloned()
+    .collect();
+    if !details.is_empty() {
+        parts.push(Bitmap::create_from_text(
+            &resources.font_big,
+            &details.join("\n"),
+            1,
+            PixelRGBA::white(),
+        ));
+    }
+
+    let padding = 2 * TILE_SIZE;
+    let cover = Bitmap::glue_together_multiple(
+        &parts,
+        GluePosition::BottomCenter,
+        TILE_SIZE,
+        PixelRGBA::white(),
+    )
+    .extended(padding, padding, padding, padding, PixelRGBA::white());
+    Ok(cover)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Legend creation
+
+fn create_pattern_page_layout(font: &BitmapFont, layout_indices: &[Vec2i]) -> Bitmap {
+    let caption_image =
+        Bitmap::create_from_text(font, "\n\nPattern parts overview:\n", 1, PixelRGBA::white());
+
+    let page_count = layout_indices.len();
+    // NOTE: Indexes begin at 0 therefore we add 1
+    let num_rows = 1 + layout_indices.iter().map(|v| v.y).max().unwrap();
+    let num_columns = 1 + layout_indices.iter().map(|v| v.x).max().unwrap();
+
+    let page_tile_dim = {
+        // NOTE: We want to have a 1px visual gap between page tiles therefore we add 1
+        let page_tile_width = 1 + font
+            .get_text_bounding_rect(&format!(" {} ", page_count), 1, false)
+            .dim
+            .x;
+        let page_tile_height = 1 + (page_tile_width as f32 * (9.0 / 6.0)) as i32;
+        Vec2i::new(page_tile_width, page_tile_height)
+    };
+
+    let image_width = num_columns * page_tile_dim.x;
+    let image_height = num_rows * page_tile_dim.y;
+    let mut image = Bitmap::new_filled(image_width as u32, image_height as u32, PixelRGBA::white());
+    for (page_index, pos_index) in layout_indices.iter().enumerate() {
+        let pos = *pos_index * page_tile_dim;
+        image.draw_rect(
+            pos.x,
+            pos.y,
+            page_tile_dim.x - 1,
+            page_tile_dim.y - 1,
+            PixelRGBA::black(),
+        );
+        image.draw_text_aligned_in_point(
+            font,
+            &(page_index + 1).to_string(),
             1,
             pos + page_tile_dim / 2,
             Vec2i::zero(),
@@ -1259,7 +2757,7 @@ fn create_pattern_page_layout(font: &BitmapFont, layout_indices: &[Vec2i]) -> Bi
     caption_image.glued_to(&image, GluePosition::TopLeft, 0, PixelRGBA::white())
 }
 
-fn create_legend_entry(font: &BitmapFont, info: &ColorInfo, stitch_colors_mapping: &HashMap<PixelRGBA, String>) -> Bitmap {
+fn create_legend_entry(resources: &Resources, info: &ColorInfo, stitch_colors_mapping: &HashMap<PixelRGBA, String>) -> Bitmap {
     // Draw color and symbol mapping
     let mut color_symbol_map =
         Bitmap::new_filled(2 * TILE_SIZE as u32, TILE_SIZE as u32, PixelRGBA::white());
@@ -1278,7 +2776,7 @@ fn create_legend_entry(font: &BitmapFont, info: &ColorInfo, stitch_colors_mappin
         PixelRGBA::white(),
     );
     color_symbol_map.draw_rect(
-        0 + TILE_SIZE,
+        TILE_SIZE,
         0,
         TILE_SIZE,
         TILE_SIZE,
@@ -1289,23 +2787,23 @@ fn create_legend_entry(font: &BitmapFont, info: &ColorInfo, stitch_colors_mappin
 
     // Add stitches info
     let stitches_info = Bitmap::create_from_text(
-        font,
-        &format!(" {} stitches DMC {}", info.count, stitch_colors_mapping.get(&info.color).unwrap_or(&not_found)),
+        &resources.font,
+        &format!(" {}: {} {}", info.key, resources.material_count_text(info), resources.color_label(stitch_colors_mapping.get(&info.color).unwrap_or(&not_found))),
         1,
         PixelRGBA::white(),
     );
     stitches_info.glued_to(
-        &mut color_symbol_map,
+        &color_symbol_map,
         GluePosition::RightCenter,
         0,
         PixelRGBA::white(),
     )
 }
 
-fn create_legend_block(font: &BitmapFont, infos: &[ColorInfo], stitch_colors_mapping: &HashMap<PixelRGBA, String>) -> Bitmap {
+fn create_legend_block(resources: &Resources, infos: &[ColorInfo], stitch_colors_mapping: &HashMap<PixelRGBA, String>) -> Bitmap {
     let entries: Vec<Bitmap> = infos
         .iter()
-        .map(|entry| create_legend_entry(font, entry, stitch_colors_mapping))
+        .map(|entry| create_legend_entry(resources, entry, stitch_colors_mapping))
         .collect();
     Bitmap::glue_together_multiple(
         &entries,
@@ -1315,14 +2813,84 @@ fn create_legend_block(font: &BitmapFont, infos: &[ColorInfo], stitch_colors_map
     )
 }
 
-fn create_cross_stitch_legend(
+/// Lays out legend blocks of LEGEND_BLOCK_ENTRY_COUNT entries each in rows of `num_columns` blocks
+fn create_legend_blocks(
+    resources: &Resources,
+    infos: &[ColorInfo],
+    stitch_colors_mapping: &HashMap<PixelRGBA, String>,
+    num_columns: usize,
+) -> Bitmap {
+    let block_bitmaps: Vec<Bitmap> = infos
+        .chunks(LEGEND_BLOCK_ENTRY_COUNT)
+        .map(|chunk| create_legend_block(resources, chunk, stitch_colors_mapping))
+        .collect();
+    let block_rows: Vec<Bitmap> = block_bitmaps
+        .chunks(num_columns)
+        .map(|chunk| {
+            Bitmap::glue_together_multiple(
+                chunk,
+                GluePosition::RightTop,
+                TILE_SIZE,
+                PixelRGBA::white(),
+            )
+        })
+        .collect();
+    Bitmap::glue_together_multiple(
+        &block_rows,
+        GluePosition::BottomLeft,
+        TILE_SIZE,
+        PixelRGBA::white(),
+    )
+}
+
+/// Creates a compact legend that lists only the colors used in the given pattern page together
+/// with their stitch counts on that page
+fn create_page_legend(
+    resources: &Resources,
+    page_image: &Bitmap,
+    color_mappings: &IndexMap<PixelRGBA, ColorInfo>,
+    stitch_colors_mapping: &HashMap<PixelRGBA, String>,
+) -> Bitmap {
+    let page_colors_and_counts = image_extract_colors_and_counts(page_image);
+    let mut page_infos: Vec<ColorInfo> = color_mappings
+        .values()
+        .filter_map(|info| {
+            let page_count = page_colors_and_counts.get(&info.color)?.count;
+            Some(ColorInfo {
+                count: page_count,
+                ..info.clone()
+            })
+        })
+        .collect();
+    assign_brick_parts(resources, page_image, page_infos.iter_mut());
+
+    let caption = Bitmap::create_from_text(
+        &resources.font,
+        &format!("Colors on this page: {}\n\n", page_infos.len()),
+        1,
+        PixelRGBA::white(),
+    );
+    let blocks = create_legend_blocks(
+        resources,
+        &page_infos,
+        stitch_colors_mapping,
+        LEGEND_PAGE_BLOCK_COLUMN_COUNT,
+    );
+    let padding = TILE_SIZE;
+    caption
+        .glued_to(&blocks, GluePosition::TopLeft, 0, PixelRGBA::white())
+        .extended(padding, 0, padding, padding, PixelRGBA::white())
+}
+
+fn render_cross_stitch_legend(
     image_dimensions: Vec2i,
     color_mappings: &IndexMap<PixelRGBA, ColorInfo>,
-    image_filepath: &str,
-    font: &BitmapFont,
+    resources: &Resources,
     segment_layout_indices: &[Vec2i],
-    stitch_colors_mapping: &HashMap<PixelRGBA, String>
-) {
+    stitch_colors_mapping: &HashMap<PixelRGBA, String>,
+    footer: Option<&Bitmap>,
+) -> Bitmap {
+    let font = &resources.font;
     let mut legend = {
         // Create color and stitch stats
         let stats_bitmap = {
@@ -1331,43 +2899,42 @@ fn create_cross_stitch_legend(
                 .values()
                 .fold(0, |acc, entry| acc + entry.count);
 
-            Bitmap::create_from_text(
-                &font,
-                &format!(
-                    "Size:     {}x{}\n\nColors:   {}\n\nStitches: {}\n\n\n",
-                    image_dimensions.x, image_dimensions.y, color_count, stitch_count
-                ),
-                1,
-                PixelRGBA::white(),
-            )
+            let mut stats = format!(
+                "Size:     {}x{}\n\nColors:   {}\n\n{:<10}{}\n\n",
+                image_dimensions.x,
+                image_dimensions.y,
+                color_count,
+                capitalized(resources.craft.cell_name_plural()) + ":",
+                stitch_count
+            );
+            if resources.craft == Craft::DiamondPainting {
+                let (width_cm, height_cm) = canvas_size_cm(image_dimensions.x, image_dimensions.y);
+                stats += &format!("Canvas:   {:.1}x{:.1} cm\n\n", width_cm, height_cm);
+            }
+            if resources.craft.uses_gauge() {
+                stats += &format!(
+                    "Gauge:    {} stitches and {} rows per 10 cm\n\n",
+                    resources.knitting.stitches_per_10cm,
+                    resources.knitting.rows_per_10cm
+                );
+            }
+            if resources.craft == Craft::Bricks {
+                stats += &format!(
+                    "Parts:    {} {}\n\n",
+                    brick_part_count(color_mappings),
+                    resources.bricks.part.name_plural()
+                );
+            }
+            stats += "\n";
+            Bitmap::create_from_text(font, &stats, 1, PixelRGBA::white())
         };
 
         // Create color mapping blocks
         let blocks = {
             let color_infos: Vec<ColorInfo> = color_mappings.values().cloned().collect();
-            let block_bitmaps: Vec<Bitmap> = color_infos
-                .chunks(LEGEND_BLOCK_ENTRY_COUNT)
-                .map(|chunk| create_legend_block(&font, chunk, stitch_colors_mapping))
-                .collect();
-            let num_columns = block_bitmaps.len().max(4);
-            let block_rows: Vec<Bitmap> = block_bitmaps
-                .chunks(num_columns)
-                .map(|chunk| {
-                    Bitmap::glue_together_multiple(
-                        chunk,
-                        GluePosition::RightTop,
-                        TILE_SIZE,
-                        PixelRGBA::white(),
-                    )
-                })
-                .collect();
-            Bitmap::glue_together_multiple(
-                &block_rows,
-                GluePosition::BottomLeft,
-                TILE_SIZE,
-                PixelRGBA::white(),
-            )
-            .extended(0, 0, 0, (1.5 * TILE_SIZE as f32) as i32, PixelRGBA::white())
+            let block_count = color_infos.len().div_ceil(LEGEND_BLOCK_ENTRY_COUNT);
+            create_legend_blocks(resources, &color_infos, stitch_colors_mapping, block_count.max(4))
+                .extended(0, 0, 0, (1.5 * TILE_SIZE as f32) as i32, PixelRGBA::white())
         };
 
         Bitmap::glue_a_to_b(
@@ -1381,7 +2948,7 @@ fn create_cross_stitch_legend(
 
     // Add page layout order if necessary
     if segment_layout_indices.len() > 1 {
-        let page_layout_image = create_pattern_page_layout(&font, segment_layout_indices);
+        let page_layout_image = create_pattern_page_layout(font, segment_layout_indices);
 
         legend = legend.glued_to(
             &page_layout_image,
@@ -1402,61 +2969,1114 @@ fn create_cross_stitch_legend(
 
     let padding = TILE_SIZE;
     let final_image = legend.extended(padding, padding, padding, padding, PixelRGBA::white());
+    add_footer(&final_image, footer)
+}
 
-    // Write out png image
-    let output_filepath =
-        get_image_output_filepath(&image_filepath) + "_legend.png";
-    Bitmap::write_to_png_file(&final_image, &output_filepath);
-}
-
-
-pub fn make() {
-    let now = Instant::now();
-    let stitch_colors_mapping = color_mapping::make();
-    let (font, font_big) = load_fonts();
-    let symbols = collect_symbols();
-    let symbols_alphanum = create_alphanumeric_symbols(&font);
-    let (
-        stitch_images_premultiplied_alpha,
-        stitch_images_luminance_premultiplied_alpha,
-        stitch_background_image_8x8_premultiplied_alpha,
-    ) = load_stitch_preview_images_premultiplied_alpha();
-    let resources = Resources {
-        font,
-        font_big,
-        stitch_background_image_8x8_premultiplied_alpha,
-    };
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Public API
+
+/// Holds everything needed to convert images into patterns, like fonts, symbols and stitch tiles.
+/// Loading these is expensive, so a converter should be created once and reused for many images.
+pub struct Converter {
+    options: ConversionOptions,
+    resources: Resources,
+    symbols: Vec<Bitmap>,
+    stitch_images_premultiplied_alpha: Vec<Bitmap>,
+    stitch_images_luminance_premultiplied_alpha: Vec<Bitmap>,
+    stitch_colors_mapping: HashMap<PixelRGBA, String>,
+    footer: Option<Bitmap>,
+}
+
+impl Converter {
+    /// Loads all resources needed for conversion. Fails if the options are invalid or if user
+    /// supplied resources like symbol sheets, fonts or stitch tiles are missing or invalid.
+    pub fn new(options: ConversionOptions) -> Result<Converter, ConversionError> {
+        let _timer = TimerScoped::new_scoped("Loading resources", true);
+        Converter::create(options, None)
+    }
+
+    /// Returns a converter for the given options which shares all resources with this converter
+    /// that do not depend on the differing options. This is much cheaper than `Converter::new`
+    /// if only a few options differ.
+    pub fn with_options(&self, options: ConversionOptions) -> Result<Converter, ConversionError> {
+        Converter::create(options, Some(self))
+    }
 
+    fn create(
+        mut options: ConversionOptions,
+        base: Option<&Converter>,
+    ) -> Result<Converter, ConversionError> {
+        options.validate().map_err(ConversionError::InvalidOptions)?;
+        if options.craft.uses_gauge() {
+            options.chart_style.cell_aspect = options.knitting.stitch_aspect();
+            options.chart_style.grid_labels = GridLabels::KnittingRows;
+        }
 
+        let same_palette = |base: &&Converter| {
+            base.options.craft == options.craft
+                && palette_filepath(&base.options) == palette_filepath(&options)
+                && base.options.fuse_beads.brand == options.fuse_beads.brand
+        };
+        let stitch_colors_mapping = match base.filter(same_palette) {
+            Some(base) => base.stitch_colors_mapping.clone(),
+            None => load_palette(&options)?,
+        };
 
-    for image_filepath in get_image_filepaths_from_commandline() {
-        create_image_output_dir(&image_filepath);
-        create_image_output_dir(&image_filepath);
-        create_image_output_dir(&image_filepath);
+        let (font, font_big) = match base {
+            Some(base) => (base.resources.font.clone(), base.resources.font_big.clone()),
+            None => load_fonts(),
+        };
+
+        let same_symbols = |base: &&Converter| {
+            base.options.symbol_font_filepath == options.symbol_font_filepath
+                && base.options.symbol_sheet_filepath == options.symbol_sheet_filepath
+        };
+        let symbols = match base.filter(same_symbols) {
+            Some(base) => base.symbols.clone(),
+            None => collect_symbols(&options)?,
+        };
+
+        let same_stitch_tiles = |base: &&Converter| {
+            base.options.craft == options.craft
+                && base.options.fabric == options.fabric
+                && base.options.preview.stitch_tiles_dir == options.preview.stitch_tiles_dir
+                && base.options.preview.rotation_jitter_degrees
+                    == options.preview.rotation_jitter_degrees
+                && base.options.diamond_painting.drill_shape
+                    == options.diamond_painting.drill_shape
+                && base.options.bricks.part == options.bricks.part
+        };
+        let (
+            stitch_images_premultiplied_alpha,
+            stitch_images_luminance_premultiplied_alpha,
+            stitch_background_image_8x8_premultiplied_alpha,
+        ) = match base.filter(same_stitch_tiles) {
+            Some(base) => (
+                base.stitch_images_premultiplied_alpha.clone(),
+                base.stitch_images_luminance_premultiplied_alpha.clone(),
+                base.resources
+                    .stitch_background_image_8x8_premultiplied_alpha
+                    .clone(),
+            ),
+            None => load_stitch_preview_images_premultiplied_alpha(
+                &options.fabric,
+                &options.preview,
+                options.craft,
+                &options.diamond_painting,
+                &options.bricks,
+            )?,
+        };
+
+        let same_mockups = |base: &&Converter| base.options.preview.mockups == options.preview.mockups;
+        let mockup_images = match base.filter(same_mockups) {
+            Some(base) => base.resources.mockup_images.clone(),
+            None => load_mockup_images(&options.preview)?,
+        };
+
+        let resources = Resources {
+            font,
+            font_big,
+            stitch_background_image_8x8_premultiplied_alpha,
+            fabric: options.fabric.clone(),
+            preview: options.preview.clone(),
+            craft: options.craft,
+            diamond_painting: options.diamond_painting.clone(),
+            fuse_beads: options.fuse_beads.clone(),
+            knitting: options.knitting.clone(),
+            bricks: options.bricks.clone(),
+            mockup_images,
+        };
+        let footer = create_footer(&resources.font, &options.metadata);
+
+        Ok(Converter {
+            options,
+            resources,
+            symbols,
+            stitch_images_premultiplied_alpha,
+            stitch_images_luminance_premultiplied_alpha,
+            stitch_colors_mapping,
+            footer,
+        })
+    }
+
+    pub fn options(&self) -> &ConversionOptions {
+        &self.options
+    }
+
+    /// Maps every pixel of the given image to its closest thread color and assigns symbols. The
+    /// name is used in messages and as the default title of the cover page. Fails if the image has
+    /// more colors than there are symbols. Knitting and crochet images are resized to the gauge
+    /// first.
+    pub fn convert(&self, image: &Bitmap, name: &str) -> Result<Pattern<'_>, ConversionError> {
+        // NOTE: Every pixel becomes a stitch, so for stitches that are wider than tall we need
+        //       more rows than pixels to keep the proportions of the image
+        let resized;
+        let image = if self.options.craft.uses_gauge() {
+            resized = self.options.knitting.resized_to_gauge(image);
+            &resized
+        } else {
+            image
+        };
+        let converted = {
+            let _timer = TimerScoped::new_scoped(&format!("Converting '{}'", name), true);
+            log::info!(
+                "'{}': converting {}x{} image to thread colors",
+                name,
+                image.width,
+                image.height
+            );
+            convert_image(image, &self.stitch_colors_mapping, self.options.color_metric)?
+        };
 
-        let mut image = open_image(&image_filepath);
-        image = convert_image(&image, &stitch_colors_mapping);
-        let color_mappings = create_color_mappings_from_image(
-            &image,
-            &image_filepath,
-            &symbols,
-            &symbols_alphanum,
-            &stitch_images_premultiplied_alpha,
-            &stitch_images_luminance_premultiplied_alpha
+        let _timer = TimerScoped::new_scoped(&format!("Mapping colors of '{}'", name), true);
+        let mut color_mappings = create_color_mappings_from_image(
+            &converted,
+            name,
+            &self.symbols,
+            &self.stitch_images_premultiplied_alpha,
+            &self.stitch_images_luminance_premultiplied_alpha,
+            &self.stitch_colors_mapping,
+            &self.options.pinned_symbols,
+        )?;
+        assign_brick_parts(
+            &self.resources,
+            &converted,
+            color_mappings.values_mut(),
+        );
+        log::info!(
+            "'{}': mapped {} colors to threads and symbols",
+            name,
+            color_mappings.len()
         );
+        let page_segments = create_page_segments(
+            &converted,
+            &self.resources,
+            &color_mappings,
+            &self.stitch_colors_mapping,
+            &self.options,
+            self.footer.as_ref(),
+        )?;
+
+        Ok(Pattern {
+            converter: self,
+            name: name.to_owned(),
+            original: image.clone(),
+            image: converted,
+            color_mappings,
+            page_segments,
+            paint_by_numbers: OnceLock::new(),
+        })
+    }
+}
+
+/// A thread used in a pattern
+#[derive(Debug, Clone, Serialize)]
+pub struct ThreadUsage {
+    /// DMC thread number
+    pub thread: String,
+    pub color: PixelRGBA,
+    pub stitch_count: usize,
+}
 
+/// An image converted into thread colors. All render methods return in-memory bitmaps which can
+/// be encoded via `Bitmap::encoded_as_png`.
+pub struct Pattern<'converter> {
+    converter: &'converter Converter,
+    name: String,
+    original: Bitmap,
+    image: Bitmap,
+    color_mappings: IndexMap<PixelRGBA, ColorInfo>,
+    page_segments: Vec<PageSegment>,
+    /// Paint regions and their palette, merged once on first use
+    paint_by_numbers: OnceLock<(PaintRegions, Vec<PaletteEntry>)>,
+}
+
+impl<'converter> Pattern<'converter> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The image as given to the converter. Knitting and crochet images are resized to the gauge.
+    pub fn original(&self) -> &Bitmap {
+        &self.original
+    }
+
+    /// The image with every pixel replaced by its thread color. Transparent pixels are left
+    /// unstitched.
+    pub fn image(&self) -> &Bitmap {
+        &self.image
+    }
+
+    pub fn threads(&self) -> Vec<ThreadUsage> {
+        self.color_mappings
+            .values()
+            .filter(|info| info.color.a != 0)
+            .map(|info| ThreadUsage {
+                thread: self.converter.stitch_colors_mapping[&info.color].clone(),
+                color: info.color,
+                stitch_count: info.count,
+            })
+            .collect()
+    }
+
+    /// Biggest pixel count of the bitmaps rendered for the real-scale preview and the mockups.
+    /// These grow with the dpi and shrink with the fabric count of the preview setup.
+    pub fn real_scale_previews_pixel_count_max(&self) -> i64 {
+        real_scale_previews_pixel_count_max(
+            self.image.dim(),
+            &self.converter.resources,
+            &self.converter.options.preview,
+        )
+    }
+
+    /// Number of pages the chart is split into, see `render_chart_page`
+    pub fn page_count(&self) -> usize {
+        self.page_segments.len()
+    }
+
+    /// Renders the complete chart on a single image. Centered charts label their stitches
+    /// relative to the center of the image, while knitting charts are always counted from their
+    /// bottom right. Paint by numbers charts have no stitch labels.
+    pub fn render_chart(
+        &self,
+        pattern_type: PatternType,
+        centered: bool,
+    ) -> Result<Bitmap, ConversionError> {
+        if pattern_type == PatternType::PaintByNumbers {
+            let (regions, palette) = self.paint_by_numbers()?;
+            return Ok(self.render_paint_by_numbers_chart(regions, palette, None));
+        }
+        render_cross_stitch_pattern(
+            &self.chart_setup(centered),
+            &self.image,
+            None,
+            self.logical_first_coordinate(Vec2i::zero(), centered),
+            pattern_type,
+        )
+    }
+
+    /// Renders a single page of the chart where `page_index` starts at 0
+    pub fn render_chart_page(
+        &self,
+        pattern_type: PatternType,
+        centered: bool,
+        page_index: usize,
+    ) -> Result<Bitmap, ConversionError> {
+        let segment = self.page_segments.get(page_index).ok_or_else(|| {
+            ConversionError::InvalidOptions(format!(
+                "Page index {} is out of range for a pattern with {} pages",
+                page_index,
+                self.page_segments.len()
+            ))
+        })?;
+        if pattern_type == PatternType::PaintByNumbers {
+            let (regions, palette) = self.paint_by_numbers()?;
+            return Ok(self.render_paint_by_numbers_chart(regions, palette, Some(segment.rect)));
+        }
+        render_cross_stitch_pattern(
+            &self.chart_setup(centered),
+            &self.image.cropped_by_rect(segment.rect),
+            Some(&self.create_page(page_index)),
+            self.logical_first_coordinate(segment.rect.pos, centered),
+            pattern_type,
+        )
+    }
+
+    pub fn render_legend(&self) -> Bitmap {
+        let segment_layout_indices: Vec<Vec2i> = self
+            .page_segments
+            .iter()
+            .map(|segment| segment.layout_index)
+            .collect();
+        render_cross_stitch_legend(
+            self.image.dim(),
+            &self.color_mappings,
+            &self.converter.resources,
+            &segment_layout_indices,
+            &self.converter.stitch_colors_mapping,
+            self.converter.footer.as_ref(),
+        )
+    }
+
+    /// Renders the pattern as it would look like stitched on the configured fabric
+    pub fn render_preview(&self) -> Result<Bitmap, ConversionError> {
+        render_cross_stitch_preview(&self.image, &self.converter.resources, &self.color_mappings)
+    }
+
+    pub fn render_cover_page(&self) -> Result<Bitmap, ConversionError> {
+        render_cover_page(
+            &self.image,
+            &self.name,
+            &self.converter.resources,
+            &self.color_mappings,
+            &self.converter.options.metadata,
+        )
+    }
+
+    /// Renders the numbered paint colors that match the paint by numbers chart
+    pub fn render_paint_by_numbers_palette(&self) -> Result<Bitmap, ConversionError> {
+        let (_regions, palette) = self.paint_by_numbers()?;
+        Ok(self.render_paint_palette(palette))
+    }
+
+    fn render_paint_palette(&self, palette: &[PaletteEntry]) -> Bitmap {
+        let palette = render_palette(palette, &self.converter.resources.font);
+        let padding = TILE_SIZE;
+        let palette = palette.extended(padding, padding, padding, padding, PixelRGBA::white());
+        add_footer(&palette, self.converter.footer.as_ref())
+    }
+
+    /// Writes the row by row color changes of the pattern, see `InstructionsSetup`
+    pub fn render_instructions(&self, format: InstructionFormat) -> Vec<u8> {
+        let resources = &self.converter.resources;
+        let options = &self.converter.options;
+        let colors: Vec<(PixelRGBA, String, usize)> = self
+            .color_mappings
+            .values()
+            .map(|info| {
+                let label = self
+                    .converter
+                    .stitch_colors_mapping
+                    .get(&info.color)
+                    .map(|name| resources.color_label(name))
+                    .unwrap_or_default();
+                (info.color, label, info.count)
+            })
+            .collect();
+        let title = options.metadata.title.as_ref().unwrap_or(&self.name);
+        let instructions = Instructions::new(title, &self.image, &colors, &options.instructions);
+        match format {
+            InstructionFormat::Text => instructions.to_text().into_bytes(),
+            InstructionFormat::Markdown => instructions.to_markdown().into_bytes(),
+            InstructionFormat::Pdf => instructions.to_pdf(
+                options.page_setup.paper_size_mm,
+                options.page_setup.margin_mm,
+            ),
+        }
+    }
+
+    /// Renders the original image, the image in thread colors and a heatmap of their difference
+    pub fn render_comparison(&self) -> (Bitmap, ColorErrorStats) {
+        render_comparison(
+            &self.original,
+            &self.image,
+            &self.converter.resources,
+            self.converter.options.color_metric,
+        )
+    }
+
+    /// Renders all charts, legends and previews of the pattern in memory. Filenames start with
+    /// the name of the pattern.
+    pub fn render_output_files(&self) -> Result<Vec<OutputFile>, ConversionError> {
+        let converter = self.converter;
+        let options = &converter.options;
+        let resources = &converter.resources;
+        let image = &self.image;
+        let color_mappings = &self.color_mappings;
+
+        let mut result_charts = Ok(Vec::new());
+        let mut result_charts_centered = Ok(Vec::new());
+        let mut result_preview = Ok(Vec::new());
+        let mut result_cover_page = Ok(Vec::new());
+        let mut result_comparison = Ok(Vec::new());
+        let mut files_legend = Vec::new();
+        let mut result_paint_by_numbers = Ok(Vec::new());
+        let mut files_instructions = Vec::new();
         rayon::scope(|scope| {
             scope.spawn(|_| {
-                create_patterns_dir(&image, &image_filepath, &resources, &color_mappings, &stitch_colors_mapping);
+                files_legend = vec![OutputFile::new(
+                    self.name.clone() + "_legend.png",
+                    "legend",
+                    self.render_legend().encoded_as_png(),
+                )];
             });
             scope.spawn(|_| {
-                create_patterns_dir_centered(&image, &image_filepath, &resources, &color_mappings, &stitch_colors_mapping);
+                result_charts = create_charts(self, false);
+            });
+            // NOTE: Knitting charts are counted from their bottom right and have no centered variant
+            if options.chart_style.grid_labels == GridLabels::Coordinates {
+                scope.spawn(|_| {
+                    result_charts_centered = create_charts(self, true);
+                });
+            }
+            scope.spawn(|_| {
+                result_preview = create_previews(image, &self.name, resources, color_mappings, &options.preview);
             });
             scope.spawn(|_| {
-                create_preview_dir(&image, &image_filepath, &resources, &color_mappings);
+                result_cover_page = create_cover_page(image, &self.name, resources, color_mappings, &options.metadata)
+                    .map(|cover_page| vec![cover_page]);
+            });
+            scope.spawn(|_| {
+                result_comparison = create_comparison(&self.original, image, &self.name, resources, options.color_metric);
+            });
+            scope.spawn(|_| {
+                result_paint_by_numbers = create_paint_by_numbers(self);
+            });
+            scope.spawn(|_| {
+                files_instructions = create_instructions(self);
             });
         });
+
+        let mut result = files_legend;
+        result.extend(result_charts?);
+        result.extend(result_charts_centered?);
+        result.extend(result_paint_by_numbers?);
+        result.extend(result_preview?);
+        result.extend(result_cover_page?);
+        result.extend(result_comparison?);
+        result.extend(files_instructions);
+        Ok(result)
+    }
+
+    /// Packs all output files together with a project file and a manifest into a zip archive.
+    /// The project file contains the options used for conversion and can be given via `--config`
+    /// to convert the image again. `image_filename` is the name of the original image.
+    pub fn render_bundle(&self, image_filename: &str) -> Result<Vec<u8>, ConversionError> {
+        let mut output_files = self.render_output_files()?;
+        let project_filename = self.name.clone() + "_project.json";
+        let project_json = encode_json(self.converter.options(), &project_filename)?;
+        output_files.push(OutputFile::new(project_filename, "project", project_json));
+
+        log::info!("'{}': bundling outputs", self.name);
+        let manifest = Manifest {
+            image: image_filename.to_owned(),
+            width: self.image.width,
+            height: self.image.height,
+            threads: self.threads(),
+            files: Vec::new(),
+        };
+        create_pattern_bundle(manifest, &output_files).map_err(|reason| ConversionError::FileWrite {
+            filepath: self.name.clone() + ".zip",
+            reason,
+        })
+    }
+
+    /// Knitting charts are never centered because they are counted from their bottom right
+    fn is_centered_chart(&self, centered: bool) -> bool {
+        centered && self.converter.options.chart_style.grid_labels == GridLabels::Coordinates
+    }
+
+    fn logical_first_coordinate(&self, pos: Vec2i, centered: bool) -> Vec2i {
+        if self.is_centered_chart(centered) {
+            Vec2i::new(
+                pos.x - make_even_upwards(self.image.width) / 2,
+                pos.y - make_even_upwards(self.image.height) / 2,
+            )
+        } else {
+            pos - self.converter.options.chart_style.label_origin(self.image.dim())
+        }
+    }
+
+    fn chart_setup(&self, centered: bool) -> ChartSetup<'_> {
+        let chart_style = &self.converter.options.chart_style;
+        let resources = &self.converter.resources;
+        ChartSetup {
+            font_grid_label: resources.font_for_label(chart_style.label_font),
+            font_segment_index_indicator: &resources.font_big,
+            color_mappings: &self.color_mappings,
+            chart_style,
+            footer: self.converter.footer.as_ref(),
+            add_major_grid: true,
+            add_origin_grid_bars: self.is_centered_chart(centered),
+            symbol_mask_color: PixelRGBA::white(),
+        }
+    }
+
+    fn create_page(&self, page_index: usize) -> PatternPage {
+        create_pattern_page(
+            &self.image,
+            &self.page_segments,
+            page_index,
+            &self.converter.resources,
+            &self.color_mappings,
+            &self.converter.stitch_colors_mapping,
+            self.converter.options.has_page_legends(),
+        )
+    }
+
+    /// Splits the image into paint regions and numbers their colors, see
+    /// `Craft::has_paint_by_numbers`
+    /// Returns the paint regions of the whole image and their palette. Both are merged on first
+    /// use and shared by every page and output afterwards.
+    fn paint_by_numbers(&self) -> Result<&(PaintRegions, Vec<PaletteEntry>), ConversionError> {
+        let craft = self.converter.options.craft;
+        if !craft.has_paint_by_numbers() {
+            return Err(ConversionError::InvalidOptions(format!(
+                "Paint by numbers is not available for {} patterns",
+                craft.name()
+            )));
+        }
+
+        Ok(self.paint_by_numbers.get_or_init(|| {
+            let regions = PaintRegions::from_image(
+                &self.image,
+                self.converter.options.paint_by_numbers.min_region_size,
+            );
+            let colors: Vec<PixelRGBA> = self.color_mappings.keys().cloned().collect();
+            let palette = regions.palette(&colors, |color| {
+                self.converter.stitch_colors_mapping[&color].clone()
+            });
+            (regions, palette)
+        }))
+    }
+
+    /// Renders the region outlines of the whole image or of the given page. Regions are always
+    /// merged on the whole image so that pages match the complete chart.
+    fn render_paint_by_numbers_chart(
+        &self,
+        regions: &PaintRegions,
+        palette: &[PaletteEntry],
+        page_rect: Option<Recti>,
+    ) -> Bitmap {
+        let _timer = TimerScoped::new_scoped(
+            &format!("Rendering paint by numbers chart of '{}'", self.name),
+            true,
+        );
+        let page_regions;
+        let regions = match page_rect {
+            // NOTE: Regions that are cut by the page border need a number on every page, so we
+            //       split the merged image of the page into regions again without merging
+            Some(rect) => {
+                page_regions =
+                    PaintRegions::from_image(&regions.to_bitmap().cropped_by_rect(rect), 1);
+                &page_regions
+            }
+            None => regions,
+        };
+
+        let resources = &self.converter.resources;
+        let chart_style = &self.converter.options.chart_style;
+        let chart = render_regions_chart(
+            regions,
+            palette,
+            resources.font_for_label(chart_style.label_font),
+            chart_style.cell_size,
+        );
+        let padding = TILE_SIZE;
+        let chart = chart.extended(padding, padding, padding, padding, PixelRGBA::white());
+        add_footer(&chart, self.converter.footer.as_ref())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Commandline
+
+/// Renders the written instructions in all requested formats
+fn create_instructions(pattern: &Pattern) -> Vec<OutputFile> {
+    pattern
+        .converter
+        .options
+        .instructions
+        .formats
+        .iter()
+        .map(|format| {
+            log::info!(
+                "'{}': writing {} instructions",
+                pattern.name,
+                format.file_extension()
+            );
+            OutputFile::new(
+                pattern.name.clone() + "_instructions." + format.file_extension(),
+                "instructions",
+                pattern.render_instructions(*format),
+            )
+        })
+        .collect()
+}
+
+/// Renders the paint by numbers chart and its palette if the craft has them. Paint by numbers
+/// has no coordinates, so there is no centered variant.
+fn create_paint_by_numbers(pattern: &Pattern) -> Result<Vec<OutputFile>, ConversionError> {
+    if !pattern.converter.options.craft.has_paint_by_numbers() {
+        return Ok(Vec::new());
+    }
+
+    log::info!("'{}': rendering paint by numbers chart", pattern.name);
+    let (regions, palette) = pattern.paint_by_numbers()?;
+    let filename_prefix =
+        pattern.name.clone() + "_" + PatternType::PaintByNumbers.output_filename_prefix();
+    let mut palette_file = OutputFile::new(
+        filename_prefix.clone() + "palette.png",
+        "palette",
+        pattern.render_paint_palette(palette).encoded_as_png(),
+    );
+    palette_file.entry.pattern_type = Some(PatternType::PaintByNumbers.name().to_owned());
+    Ok(vec![
+        OutputFile {
+            entry: ManifestEntry::new_chart(
+                filename_prefix + "complete.png",
+                PatternType::PaintByNumbers,
+                false,
+                None,
+            ),
+            data: pattern
+                .render_paint_by_numbers_chart(regions, palette, None)
+                .encoded_as_png(),
+        },
+        palette_file,
+    ])
+}
+
+/// Size and thread usage of a successfully converted image
+pub struct ConversionSummary {
+    width: i32,
+    height: i32,
+    color_count: usize,
+    stitch_count: usize,
+}
+
+fn convert_image_file(
+    converter: &Converter,
+    image_filepath: &str,
+    zip_bundle: bool,
+) -> Result<ConversionSummary, ConversionError> {
+    let _timer = TimerScoped::new_scoped(&format!("Total for '{}'", image_filepath), true);
+
+    let image = {
+        let _timer = TimerScoped::new_scoped(&format!("Loading '{}'", image_filepath), true);
+        log::info!("'{}': loading", image_filepath);
+        open_image(image_filepath)?
+    };
+    let pattern = converter.convert(&image, &path_to_filename_without_extension(image_filepath))?;
+
+    if zip_bundle {
+        let _timer = TimerScoped::new_scoped(&format!("Bundling '{}'", image_filepath), true);
+        let bundle = pattern.render_bundle(&path_to_filename(image_filepath))?;
+        log::info!("'{}': writing bundle", image_filepath);
+        write_output_file(&(get_image_output_dir(image_filepath) + ".zip"), &bundle)?;
+    } else {
+        let output_files = pattern.render_output_files()?;
+        create_image_output_dir(image_filepath)?;
+        let output_dir = get_image_output_dir(image_filepath);
+        for output_file in &output_files {
+            write_output_file(&path_join(&output_dir, output_file.filename()), &output_file.data)?;
+        }
     }
 
-    println!("Conversion done in {} secs", now.elapsed().as_secs());
+    let threads = pattern.threads();
+    Ok(ConversionSummary {
+        width: pattern.image().width,
+        height: pattern.image().height,
+        color_count: threads.len(),
+        stitch_count: threads.iter().map(|thread| thread.stitch_count).sum(),
+    })
+}
+
+fn is_supported_image_filepath(filepath: &str) -> bool {
+    // NOTE: Directories may contain files without extension
+    std::path::Path::new(filepath)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| {
+            extension.eq_ignore_ascii_case("png") || extension.eq_ignore_ascii_case("gif")
+        })
+        .unwrap_or(false)
+}
+
+/// Returns true if the given file is one of our outputs. Outputs of an image are written to
+/// `<output_dir_root>/<name>/<name>_*`, see `get_image_output_dir`. Other images next to the
+/// executable are regular inputs.
+fn is_output_filepath(filepath: &str, output_dir_root: &std::path::Path) -> bool {
+    std::fs::canonicalize(filepath)
+        .ok()
+        .and_then(|filepath| {
+            let output_dir = filepath.parent()?;
+            if output_dir.parent()? != output_dir_root {
+                return Some(false);
+            }
+            let image_name = output_dir.file_name()?.to_str()?;
+            let filename = filepath.file_name()?.to_str()?;
+            Some(filename.starts_with(&format!("{}_", image_name)))
+        })
+        .unwrap_or(false)
+}
+
+/// Replaces directories in the given inputs by the images they contain. If a glob pattern is
+/// given, only images matching it are picked (relative to each directory). Images in our own
+/// output directories are skipped so that we never convert our outputs again.
+pub fn collect_image_filepaths(
+    inputs: &[String],
+    glob_pattern: Option<&str>,
+    recursive: bool,
+) -> Vec<String> {
+    collect_image_filepaths_and_log_problems(inputs, glob_pattern, recursive, true)
+}
+
+/// Same as `collect_image_filepaths`. Problems like directories without images are only logged
+/// if `log_problems` is set, so that polling does not repeat them.
+fn collect_image_filepaths_and_log_problems(
+    inputs: &[String],
+    glob_pattern: Option<&str>,
+    recursive: bool,
+    log_problems: bool,
+) -> Vec<String> {
+    let output_dir_root = std::fs::canonicalize(get_executable_dir()).ok();
+    let mut result = Vec::new();
+    for input in inputs {
+        if !path_is_directory(input) {
+            result.push(input.clone());
+            continue;
+        }
+
+        let mut filepaths: Vec<String> = if let Some(glob_pattern) = glob_pattern {
+            match collect_files_by_glob_pattern(input, glob_pattern) {
+                Ok(filepaths) => filepaths,
+                Err(error) => {
+                    if log_problems {
+                        log::error!("Cannot search '{}' for images: {}", input, error);
+                    }
+                    continue;
+                }
+            }
+        } else if recursive {
+            collect_files_recursive(input)
+        } else {
+            collect_files(input)
+        };
+        filepaths.retain(|filepath| is_supported_image_filepath(filepath));
+        if let Some(output_dir_root) = &output_dir_root {
+            filepaths.retain(|filepath| !is_output_filepath(filepath, output_dir_root));
+        }
+        filepaths.sort();
+
+        if filepaths.is_empty() && log_problems {
+            log::warn!("No GIF or PNG images found in directory '{}'", input);
+        }
+        result.extend(filepaths);
+    }
+    result
+}
+
+pub fn log_summary_table(
+    image_filepaths: &[String],
+    results: &[Result<ConversionSummary, ConversionError>],
+) {
+    let header = [
+        "Image".to_owned(),
+        "Size".to_owned(),
+        "Colors".to_owned(),
+        "Stitches".to_owned(),
+        "Status".to_owned(),
+    ];
+    let rows: Vec<[String; 5]> = image_filepaths
+        .iter()
+        .zip(results)
+        .map(|(image_filepath, result)| match result {
+            Ok(summary) => [
+                image_filepath.clone(),
+                format!("{}x{}", summary.width, summary.height),
+                summary.color_count.to_string(),
+                summary.stitch_count.to_string(),
+                "ok".to_owned(),
+            ],
+            Err(error) => [
+                image_filepath.clone(),
+                "-".to_owned(),
+                "-".to_owned(),
+                "-".to_owned(),
+                format!("FAILED: {}", error),
+            ],
+        })
+        .collect();
+
+    // NOTE: The status is the last column and can be long, so we don't pad it
+    let mut column_widths = [0; 4];
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        for (column_width, entry) in column_widths.iter_mut().zip(row.iter()) {
+            *column_width = usize::max(*column_width, entry.chars().count());
+        }
+    }
+    let format_row = |row: &[String; 5]| {
+        format!(
+            "{:<w0$}  {:>w1$}  {:>w2$}  {:>w3$}  {}",
+            row[0],
+            row[1],
+            row[2],
+            row[3],
+            row[4],
+            w0 = column_widths[0],
+            w1 = column_widths[1],
+            w2 = column_widths[2],
+            w3 = column_widths[3],
+        )
+    };
+
+    log::info!("{}", format_row(&header));
+    for row in &rows {
+        log::info!("{}", format_row(row));
+    }
+}
+
+/// Converts the given images in parallel on the given thread pool
+pub fn convert_image_files(
+    converter: &Converter,
+    thread_pool: &rayon::ThreadPool,
+    image_filepaths: &[String],
+    zip_bundle: bool,
+) -> Vec<Result<ConversionSummary, ConversionError>> {
+    thread_pool.install(|| {
+        image_filepaths
+            .par_iter()
+            .enumerate()
+            .map(|(image_index, image_filepath)| {
+                log::info!(
+                    "Converting image {}/{}: '{}'",
+                    image_index + 1,
+                    image_filepaths.len(),
+                    image_filepath
+                );
+                let result = convert_image_file(converter, image_filepath, zip_bundle);
+                if let Err(error) = &result {
+                    log::error!("Failed to convert '{}': {}", image_filepath, error);
+                }
+                result
+            })
+            .collect()
+    })
+}
+
+/// Returns the last modification time of every given image that still exists
+pub fn collect_last_modified_times(image_filepaths: &[String]) -> HashMap<String, SystemTime> {
+    // NOTE: Images can be removed at any time, so we must not check for their existence first
+    image_filepaths
+        .iter()
+        .filter_map(|image_filepath| {
+            let modified_time = std::fs::metadata(image_filepath)
+                .and_then(|metadata| metadata.modified())
+                .ok()?;
+            Some((image_filepath.clone(), modified_time))
+        })
+        .collect()
+}
+
+/// Polls the commandline inputs and converts images again that were added or modified since the
+/// given times. This runs until the process is terminated.
+pub fn watch_image_files(
+    converter: &Converter,
+    thread_pool: &rayon::ThreadPool,
+    commandline: &CommandlineOptions,
+    mut last_modified_times: HashMap<String, SystemTime>,
+) -> ! {
+    log::info!(
+        "Watching for changes every {} secs - press Ctrl+C to stop",
+        commandline.watch_interval_seconds
+    );
+    loop {
+        std::thread::sleep(std::time::Duration::from_secs_f32(
+            commandline.watch_interval_seconds,
+        ));
+
+        // NOTE: Our output directories are skipped, otherwise we would convert our own outputs
+        let image_filepaths = collect_image_filepaths_and_log_problems(
+            &commandline.image_filepaths,
+            commandline.glob_pattern.as_deref(),
+            commandline.recursive,
+            false,
+        );
+        let current_modified_times = collect_last_modified_times(&image_filepaths);
+        let changed_image_filepaths: Vec<String> = image_filepaths
+            .into_iter()
+            .filter(|image_filepath| {
+                let current = current_modified_times.get(image_filepath);
+                current.is_some() && current != last_modified_times.get(image_filepath)
+            })
+            .collect();
+        let previous_modified_times =
+            std::mem::replace(&mut last_modified_times, current_modified_times);
+
+        if !changed_image_filepaths.is_empty() {
+            let results = convert_image_files(
+                converter,
+                thread_pool,
+                &changed_image_filepaths,
+                commandline.zip_bundle,
+            );
+            log_summary_table(&changed_image_filepaths, &results);
+
+            // NOTE: Images can fail because they were only partially written while we loaded
+            //       them. Keeping their previous modification time retries them on the next poll.
+            for (image_filepath, result) in changed_image_filepaths.iter().zip(&results) {
+                if result.is_err() {
+                    match previous_modified_times.get(image_filepath) {
+                        Some(previous) => last_modified_times.insert(image_filepath.clone(), *previous),
+                        None => last_modified_times.remove(image_filepath),
+                    };
+                }
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_and_renders_in_memory() {
+        let mut image = Bitmap::new_filled(12, 8, PixelRGBA::new(0, 0, 0, 255));
+        image.draw_rect_filled(0, 0, 6, 8, PixelRGBA::new(255, 255, 255, 255));
+        image.set(11, 7, PixelRGBA::transparent());
+
+        let converter = Converter::new(ConversionOptions::default()).unwrap();
+        let pattern = converter.convert(&image, "test").unwrap();
+        let threads = pattern.threads();
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads.iter().map(|thread| thread.stitch_count).sum::<usize>(), 95);
+        assert_eq!(pattern.page_count(), 1);
+
+        let chart = pattern.render_chart(PatternType::BlackAndWhite, false).unwrap();
+        assert!(chart.width > image.width * TILE_SIZE);
+        assert!(matches!(
+            pattern.render_chart_page(PatternType::BlackAndWhite, false, 1),
+            Err(ConversionError::InvalidOptions(_))
+        ));
+        let png_data = pattern.render_legend().encoded_as_png();
+        assert!(Bitmap::from_png_data(&png_data).is_ok());
+        assert!(pattern.render_chart(PatternType::PaintByNumbers, false).is_ok());
+        assert!(pattern.render_chart_page(PatternType::PaintByNumbers, false, 0).is_ok());
+        assert!(std::ptr::eq(
+            pattern.paint_by_numbers().unwrap(),
+            pattern.paint_by_numbers().unwrap()
+        ));
+
+        let converter = Converter::new(ConversionOptions {
+            craft: Craft::FuseBeads,
+            ..ConversionOptions::default()
+        })
+        .unwrap();
+        let pattern = converter.convert(&image, "test").unwrap();
+        assert!(matches!(
+            pattern.render_paint_by_numbers_palette(),
+            Err(ConversionError::InvalidOptions(_))
+        ));
+    }
+
+    #[test]
+    fn renders_hoop_mockup_of_knitting_pattern() {
+        let image = Bitmap::new_filled(40, 40, PixelRGBA::new(200, 40, 40, 255));
+        let converter = Converter::new(ConversionOptions {
+            craft: Craft::Knitting,
+            preview: PreviewSetup {
+                mockups: vec![MockupType::Hoop],
+                ..PreviewSetup::default()
+            },
+            ..ConversionOptions::default()
+        })
+        .unwrap();
+        let pattern = converter.convert(&image, "test").unwrap();
+
+        let files = create_real_scale_previews(
+            &pattern.image,
+            &pattern.name,
+            &converter.resources,
+            &pattern.color_mappings,
+            &converter.options.preview,
+        )
+        .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].filename(), "test_mockup_hoop.png");
+    }
+
+    #[test]
+    fn estimates_real_scale_preview_size_before_rendering() {
+        let image = Bitmap::new_filled(30, 20, PixelRGBA::new(200, 40, 40, 255));
+        let converter = Converter::new(ConversionOptions {
+            preview: PreviewSetup {
+                dpi: 1200.0,
+                fabric_count: Some(2.0),
+                mockups: vec![MockupType::Frame],
+                ..PreviewSetup::default()
+            },
+            ..ConversionOptions::default()
+        })
+        .unwrap();
+        let pattern = converter.convert(&image, "test").unwrap();
+
+        let padding = mockup_fabric_padding(image.dim(), &converter.resources, MockupType::Frame, 2.0);
+        let fabric = render_cross_stitch_preview_at_scale(
+            &pattern.image,
+            &converter.resources,
+            &pattern.color_mappings,
+            2.0,
+            1200.0,
+            padding,
+        )
+        .unwrap();
+        let pixel_count = pattern.real_scale_previews_pixel_count_max();
+        assert!(pixel_count >= fabric.width as i64 * fabric.height as i64);
+        assert!(pixel_count < 2 * fabric.width as i64 * fabric.height as i64);
+    }
+
+    #[test]
+    fn reports_unsupported_and_missing_images() {
+        assert_eq!(
+            open_image("photo.jpg"),
+            Err(ConversionError::UnsupportedImageFormat {
+                filepath: "photo.jpg".to_owned()
+            })
+        );
+        assert!(matches!(
+            open_image("does_not_exist.png"),
+            Err(ConversionError::FileLoad { .. })
+        ));
+    }
+    #[test]
+    fn collects_images_from_directories() {
+        let root = path_join(
+            &std::env::temp_dir().to_string_lossy(),
+            "launcher_collects_images_from_directories",
+        );
+        let subdir = path_join(&root, "sub");
+        std::fs::create_dir_all(&subdir).unwrap();
+        for filepath in ["a.png", "b.gif", "notes.txt", "sub/c.png", "sub/d_final.png"] {
+            std::fs::write(path_join(&root, filepath), []).unwrap();
+        }
+        let inputs = [root.clone(), "single.png".to_owned()];
+        let relative = |filepaths: Vec<String>| -> Vec<String> {
+            filepaths
+                .iter()
+                .map(|filepath| filepath.replace(&(root.clone() + "/"), ""))
+                .collect()
+        };
+
+        assert_eq!(
+            relative(collect_image_filepaths(&inputs, None, false)),
+            ["a.png", "b.gif", "single.png"]
+        );
+        assert_eq!(
+            relative(collect_image_filepaths(&inputs, None, true)),
+            ["a.png", "b.gif", "sub/c.png", "sub/d_final.png", "single.png"]
+        );
+        assert_eq!(
+            relative(collect_image_filepaths(&inputs, Some("**/*_final.png"), false)),
+            ["sub/d_final.png", "single.png"]
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn recognizes_only_our_output_files() {
+        let root = path_join(
+            &std::env::temp_dir().to_string_lossy(),
+            "launcher_recognizes_only_our_output_files",
+        );
+        for dir in ["art/cats", "cat"] {
+            std::fs::create_dir_all(path_join(&root, dir)).unwrap();
+        }
+        for filepath in ["art/dog.png", "art/cats/cat.png", "cat/cat_legend.png"] {
+            std::fs::write(path_join(&root, filepath), []).unwrap();
+        }
+        let output_dir_root = std::fs::canonicalize(&root).unwrap();
+        let is_output = |filepath: &str| is_output_filepath(&path_join(&root, filepath), &output_dir_root);
+
+        assert!(!is_output("art/dog.png"));
+        assert!(!is_output("art/cats/cat.png"));
+        assert!(is_output("cat/cat_legend.png"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
 }
@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+
+use color_art::{distance, Color as ArtColor};
+
+use crate::error::ConversionError;
+use crate::image::{Bitmap, PixelRGBA};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Constants
+
+/// Colors closer than this (euclidean RGB distance) are considered easy to mix up
+const COLOR_CLOSENESS_DISTANCE: f64 = 100.0;
+/// How much sharing a border in the image counts compared to being close in color
+const NEIGHBOUR_CONFLICT_WEIGHT: f32 = 1.5;
+/// Slightly prefers symbols at the front of the list, as those are the hand-drawn ones
+const SYMBOL_INDEX_PENALTY: f32 = 0.05;
+const SYMBOL_POOL_SIZE_MIN: usize = 20;
+const IMPROVEMENT_PASS_COUNT_MAX: usize = 8;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Symbol assignment
+
+/// A symbol that is always assigned to the given color
+#[derive(Debug, Clone)]
+pub struct PinnedSymbol {
+    pub color_index: usize,
+    pub symbol_index: usize,
+    /// Name of the thread of the color, used for error messages
+    pub thread: String,
+}
+
+/// Assigns a symbol to every color such that colors which are close in value or which often
+/// neighbour each other in the image get symbols that look as different as possible. Pinned
+/// symbols are always kept as given. The image name is only used for error messages.
+/// Returns the symbol index for every color.
+pub fn assign_symbols(
+    image: &Bitmap,
+    image_name: &str,
+    colors: &[PixelRGBA],
+    symbols: &[Bitmap],
+    symbol_mask_color: PixelRGBA,
+    pinned_symbols: &[PinnedSymbol],
+) -> Result<Vec<usize>, ConversionError> {
+    let not_enough_symbols = || ConversionError::NotEnoughSymbols {
+        image_name: image_name.to_owned(),
+        color_count: colors.len(),
+        symbol_count: symbols.len(),
+        usage: "cross stitch",
+    };
+    if symbols.len() < colors.len() {
+        return Err(not_enough_symbols());
+    }
+    for (index, pinned) in pinned_symbols.iter().enumerate() {
+        let invalid_pin = |reason: String| ConversionError::InvalidPinnedSymbol {
+            thread: pinned.thread.clone(),
+            symbol_number: pinned.symbol_index + 1,
+            reason,
+        };
+        if pinned.symbol_index >= symbols.len() {
+            return Err(invalid_pin(format!(
+                "there are only {} symbols",
+                symbols.len()
+            )));
+        }
+        if let Some(other) = pinned_symbols[..index]
+            .iter()
+            .find(|other| other.symbol_index == pinned.symbol_index)
+        {
+            return Err(invalid_pin(format!(
+                "it is already pinned to DMC {}",
+                other.thread
+            )));
+        }
+    }
+
+    let color_count = colors.len();
+    let conflicts = color_conflict_matrix(image, colors);
+
+    // NOTE: We let the assignment choose from a few more symbols than there are colors so that it
+    //       has room to avoid look-alikes, but not from all of them as the later font glyphs tend
+    //       to be less readable
+    let pool_size = usize::min(
+        symbols.len(),
+        usize::max(SYMBOL_POOL_SIZE_MIN, color_count + color_count / 2),
+    );
+    let mut candidates: Vec<usize> = (0..pool_size).collect();
+    for pinned in pinned_symbols {
+        if !candidates.contains(&pinned.symbol_index) {
+            candidates.push(pinned.symbol_index);
+        }
+    }
+    let masks: HashMap<usize, Vec<bool>> = candidates
+        .iter()
+        .map(|&symbol_index| {
+            let mask = symbols[symbol_index]
+                .data
+                .iter()
+                .map(|pixel| *pixel != symbol_mask_color)
+                .collect();
+            (symbol_index, mask)
+        })
+        .collect();
+    let mut similarity_cache: HashMap<(usize, usize), f32> = HashMap::new();
+    let mut similarity = |a: usize, b: usize| -> f32 {
+        let key = (usize::min(a, b), usize::max(a, b));
+        *similarity_cache
+            .entry(key)
+            .or_insert_with(|| symbol_similarity(&masks[&a], &masks[&b]))
+    };
+    let penalty = |symbol_index: usize| SYMBOL_INDEX_PENALTY * symbol_index as f32 / pool_size as f32;
+
+    // Pinned symbols first
+    let mut assignment: Vec<Option<usize>> = vec![None; color_count];
+    let mut is_pinned = vec![false; color_count];
+    for pinned in pinned_symbols {
+        assignment[pinned.color_index] = Some(pinned.symbol_index);
+        is_pinned[pinned.color_index] = true;
+    }
+
+    // Greedily assign the most conflicted colors first
+    let mut color_order: Vec<usize> = (0..color_count).filter(|&c| !is_pinned[c]).collect();
+    color_order.sort_by(|&a, &b| {
+        let conflict_a: f32 = conflicts[a].iter().sum();
+        let conflict_b: f32 = conflicts[b].iter().sum();
+        crate::math::compare_floats(conflict_b, conflict_a).then(a.cmp(&b))
+    });
+    for color_index in color_order {
+        let mut best: Option<(usize, f32)> = None;
+        for &symbol_index in &candidates {
+            if assignment.contains(&Some(symbol_index)) {
+                continue;
+            }
+            let mut cost = penalty(symbol_index);
+            for (other_index, other_symbol) in assignment.iter().enumerate() {
+                if let Some(other_symbol) = other_symbol {
+                    cost += conflicts[color_index][other_index]
+                        * similarity(symbol_index, *other_symbol);
+                }
+            }
+            if best.map(|(_, best_cost)| cost < best_cost).unwrap_or(true) {
+                best = Some((symbol_index, cost));
+            }
+        }
+        assignment[color_index] = best.map(|(symbol_index, _)| symbol_index);
+    }
+    let mut assignment: Vec<usize> = assignment
+        .into_iter()
+        .collect::<Option<Vec<usize>>>()
+        .ok_or_else(not_enough_symbols)?;
+
+    // Improve the greedy result by swapping symbols between colors or with unused symbols
+    for _ in 0..IMPROVEMENT_PASS_COUNT_MAX {
+        let mut improved = false;
+        for color_a in 0..color_count {
+            if is_pinned[color_a] {
+                continue;
+            }
+
+            // Cost of `color` carrying `symbol`, ignoring the pair (color, ignored_color)
+            let mut cost_of = |assignment: &[usize], color: usize, symbol: usize, ignored: usize| {
+                let mut cost = penalty(symbol);
+                for (other, &other_symbol) in assignment.iter().enumerate() {
+                    if other != color && other != ignored {
+                        cost += conflicts[color][other] * similarity(symbol, other_symbol);
+                    }
+                }
+                cost
+            };
+
+            for color_b in (color_a + 1)..color_count {
+                if is_pinned[color_b] {
+                    continue;
+                }
+                let symbol_a = assignment[color_a];
+                let symbol_b = assignment[color_b];
+                let cost_before = cost_of(&assignment, color_a, symbol_a, color_b)
+                    + cost_of(&assignment, color_b, symbol_b, color_a);
+                let cost_after = cost_of(&assignment, color_a, symbol_b, color_b)
+                    + cost_of(&assignment, color_b, symbol_a, color_a);
+                if cost_after + f32::EPSILON < cost_before {
+                    assignment.swap(color_a, color_b);
+                    improved = true;
+                }
+            }
+
+            for &symbol_unused in &candidates {
+                if assignment.contains(&symbol_unused) {
+                    continue;
+                }
+                let symbol_a = assignment[color_a];
+                let cost_before = cost_of(&assignment, color_a, symbol_a, color_a);
+                let cost_after = cost_of(&assignment, color_a, symbol_unused, color_a);
+                if cost_after + f32::EPSILON < cost_before {
+                    assignment[color_a] = symbol_unused;
+                    improved = true;
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+
+    Ok(assignment)
+}
+
+/// Returns for every pair of colors how likely they are mixed up while stitching, in [0, 1 + W]
+/// where W is the neighbour weight
+fn color_conflict_matrix(image: &Bitmap, colors: &[PixelRGBA]) -> Vec<Vec<f32>> {
+    let color_indices: HashMap<PixelRGBA, usize> = colors
+        .iter()
+        .enumerate()
+        .map(|(index, color)| (*color, index))
+        .collect();
+
+    // Count how often two colors share a cell border
+    let mut border_counts = vec![vec![0usize; colors.len()]; colors.len()];
+    let mut border_totals = vec![0usize; colors.len()];
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let index = if let Some(index) = color_indices.get(&image.get(x, y)) {
+                *index
+            } else {
+                continue;
+            };
+            for (neighbour_x, neighbour_y) in [(x + 1, y), (x, y + 1)] {
+                if !image.contains_coordinate(neighbour_x, neighbour_y) {
+                    continue;
+                }
+                if let Some(&neighbour_index) =
+                    color_indices.get(&image.get(neighbour_x, neighbour_y))
+                {
+                    if neighbour_index != index {
+                        border_counts[index][neighbour_index] += 1;
+                        border_counts[neighbour_index][index] += 1;
+                        border_totals[index] += 1;
+                        border_totals[neighbour_index] += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut conflicts = vec![vec![0.0; colors.len()]; colors.len()];
+    for a in 0..colors.len() {
+        for b in 0..colors.len() {
+            if a == b {
+                continue;
+            }
+            let color_a = ArtColor::from_rgb(colors[a].r, colors[a].g, colors[a].b).unwrap_or_default();
+            let color_b = ArtColor::from_rgb(colors[b].r, colors[b].g, colors[b].b).unwrap_or_default();
+            let closeness =
+                f64::max(0.0, 1.0 - distance(&color_a, &color_b) / COLOR_CLOSENESS_DISTANCE) as f32;
+
+            let border_total_min = usize::min(border_totals[a], border_totals[b]);
+            let neighbourhood = if border_total_min == 0 {
+                0.0
+            } else {
+                f32::min(1.0, border_counts[a][b] as f32 / border_total_min as f32)
+            };
+
+            conflicts[a][b] = closeness + NEIGHBOUR_CONFLICT_WEIGHT * neighbourhood;
+        }
+    }
+    conflicts
+}
+
+/// Jaccard similarity of two symbol ink masks in [0, 1]
+fn symbol_similarity(mask_a: &[bool], mask_b: &[bool]) -> f32 {
+    let mut intersection = 0;
+    let mut union = 0;
+    for (&a, &b) in mask_a.iter().zip(mask_b.iter()) {
+        if a && b {
+            intersection += 1;
+        }
+        if a || b {
+            union += 1;
+        }
+    }
+    if union == 0 {
+        1.0
+    } else {
+        intersection as f32 / union as f32
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbol_from_rows(rows: &[&str]) -> Bitmap {
+        let data = rows
+            .iter()
+            .flat_map(|row| row.chars())
+            .map(|c| {
+                if c == '#' {
+                    PixelRGBA::black()
+                } else {
+                    PixelRGBA::white()
+                }
+            })
+            .collect();
+        Bitmap::new_from_buffer(rows[0].len() as u32, rows.len() as u32, data)
+    }
+
+    #[test]
+    fn neighbouring_colors_get_dissimilar_symbols() {
+        let symbols = vec![
+            symbol_from_rows(&["##..", "##..", "....", "...."]),
+            symbol_from_rows(&["###.", "##..", "....", "...."]),
+            symbol_from_rows(&["....", "....", "..##", "..##"]),
+        ];
+        let color_a = PixelRGBA::new(200, 10, 10, 255);
+        let color_b = PixelRGBA::new(205, 12, 10, 255);
+        let image = Bitmap::new_from_buffer(2, 1, vec![color_a, color_b]);
+
+        let assignment = assign_symbols(
+            &image,
+            "test",
+            &[color_a, color_b],
+            &symbols,
+            PixelRGBA::white(),
+            &[],
+        )
+        .unwrap();
+
+        // The first two symbols are nearly identical so one of the colors must get the third
+        assert!(assignment.contains(&2));
+        assert_ne!(assignment[0], assignment[1]);
+    }
+
+    #[test]
+    fn pinned_symbols_are_kept() {
+        let symbols = vec![
+            symbol_from_rows(&["#.", ".."]),
+            symbol_from_rows(&[".#", ".."]),
+            symbol_from_rows(&["..", "#."]),
+        ];
+        let color_a = PixelRGBA::new(0, 0, 0, 255);
+        let color_b = PixelRGBA::new(255, 255, 255, 255);
+        let image = Bitmap::new_from_buffer(2, 1, vec![color_a, color_b]);
+
+        let pin = |color_index: usize, symbol_index: usize, thread: &str| PinnedSymbol {
+            color_index,
+            symbol_index,
+            thread: thread.to_owned(),
+        };
+        let assign = |pinned_symbols: &[PinnedSymbol]| {
+            assign_symbols(
+                &image,
+                "test",
+                &[color_a, color_b],
+                &symbols,
+                PixelRGBA::white(),
+                pinned_symbols,
+            )
+        };
+
+        let assignment = assign(&[pin(1, 0, "310")]).unwrap();
+        assert_eq!(assignment[1], 0);
+        assert_ne!(assignment[0], 0);
+
+        assert!(matches!(
+            assign(&[pin(0, 3, "310")]),
+            Err(ConversionError::InvalidPinnedSymbol { .. })
+        ));
+        assert!(matches!(
+            assign(&[pin(0, 1, "310"), pin(1, 1, "B5200")]),
+            Err(ConversionError::InvalidPinnedSymbol { .. })
+        ));
+        assert!(matches!(
+            assign_symbols(
+                &image,
+                "test",
+                &[color_a, color_b],
+                &symbols[..1],
+                PixelRGBA::white(),
+                &[]
+            ),
+            Err(ConversionError::NotEnoughSymbols { .. })
+        ));
+    }
+}
@@ -0,0 +1,508 @@
+use std::collections::HashMap;
+
+use crate::core::serde_derive::{Deserialize, Serialize};
+use crate::image::{Bitmap, PixelRGBA};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Constants
+
+/// Key of cells without a color
+const BLANK_KEY: &str = "blank";
+
+const PDF_POINTS_PER_MILLIMETER: f32 = 72.0 / 25.4;
+const PDF_FONT_SIZE: f32 = 9.0;
+const PDF_LINE_HEIGHT: f32 = 11.0;
+/// Courier glyphs are 0.6 of the font size wide
+const PDF_GLYPH_WIDTH: f32 = 0.6 * PDF_FONT_SIZE;
+/// We only print on A4 paper if no paper size is set
+const PDF_PAPER_SIZE_DEFAULT_MM: (f32, f32) = (210.0, 297.0);
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Instructions setup
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InstructionOrder {
+    /// Row by row from the bottom, i.e. for graphgans and tapestry crochet
+    #[default]
+    Rows,
+    /// Diagonal by diagonal from the bottom left corner for corner to corner (C2C) crochet
+    CornerToCorner,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InstructionFormat {
+    Text,
+    Markdown,
+    Pdf,
+}
+
+impl InstructionFormat {
+    pub fn file_extension(self) -> &'static str {
+        match self {
+            InstructionFormat::Text => "txt",
+            InstructionFormat::Markdown => "md",
+            InstructionFormat::Pdf => "pdf",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            InstructionFormat::Text => "text/plain; charset=utf-8",
+            InstructionFormat::Markdown => "text/markdown; charset=utf-8",
+            InstructionFormat::Pdf => "application/pdf",
+        }
+    }
+}
+
+/// Determines the written color change instructions of a pattern
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InstructionsSetup {
+    /// Instruction files to write next to the charts. No instructions are written by default.
+    pub formats: Vec<InstructionFormat>,
+    pub order: InstructionOrder,
+    /// Works every other row in the opposite direction, like when turning the work after each
+    /// row. Otherwise every row is worked in the direction of the first row.
+    pub alternate_direction: bool,
+}
+
+impl Default for InstructionsSetup {
+    fn default() -> InstructionsSetup {
+        InstructionsSetup {
+            formats: Vec::new(),
+            order: InstructionOrder::Rows,
+            alternate_direction: true,
+        }
+    }
+}
+
+impl InstructionsSetup {
+    pub const FORMAT_NAMES: [&'static str; 3] = ["text", "markdown", "pdf"];
+    pub const ORDER_NAMES: [&'static str; 2] = ["rows", "c2c"];
+
+    pub fn format_from_name(name: &str) -> Result<InstructionFormat, String> {
+        match name {
+            "text" => Ok(InstructionFormat::Text),
+            "markdown" => Ok(InstructionFormat::Markdown),
+            "pdf" => Ok(InstructionFormat::Pdf),
+            _ => Err(format!(
+                "Unknown instruction format '{}' - valid formats are: {}",
+                name,
+                InstructionsSetup::FORMAT_NAMES.join(", ")
+            )),
+        }
+    }
+
+    pub fn order_from_name(name: &str) -> Result<InstructionOrder, String> {
+        match name {
+            "rows" => Ok(InstructionOrder::Rows),
+            "c2c" => Ok(InstructionOrder::CornerToCorner),
+            _ => Err(format!(
+                "Unknown instruction order '{}' - valid orders are: {}",
+                name,
+                InstructionsSetup::ORDER_NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Instructions
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkDirection {
+    RightToLeft,
+    LeftToRight,
+    /// From the bottom edge to the left or top edge of the image
+    Upwards,
+    /// From the left or top edge to the bottom edge of the image
+    Downwards,
+}
+
+impl WorkDirection {
+    fn description(self) -> &'static str {
+        match self {
+            WorkDirection::RightToLeft => "from right",
+            WorkDirection::LeftToRight => "from left",
+            WorkDirection::Upwards => "upwards",
+            WorkDirection::Downwards => "downwards",
+        }
+    }
+}
+
+/// A color of the instructions, i.e. `A` for `DMC 310`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorKey {
+    pub key: String,
+    pub label: String,
+    pub stitch_count: usize,
+}
+
+/// A row or diagonal as runs of `(stitch count, color key)` in the order they are worked
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstructionRow {
+    /// Starts at 1
+    pub number: usize,
+    pub direction: WorkDirection,
+    pub runs: Vec<(usize, String)>,
+}
+
+impl InstructionRow {
+    /// Returns i.e. `Row 12 (from right): 4 A, 7 B, 2 A`
+    pub fn to_text(&self) -> String {
+        format!(
+            "Row {} ({}): {}",
+            self.number,
+            self.direction.description(),
+            self.runs_text()
+        )
+    }
+
+    fn runs_text(&self) -> String {
+        let runs: Vec<String> = self
+            .runs
+            .iter()
+            .map(|(count, key)| format!("{} {}", count, key))
+            .collect();
+        runs.join(", ")
+    }
+}
+
+/// Written color change instructions of a whole image
+pub struct Instructions {
+    pub title: String,
+    pub order: InstructionOrder,
+    pub keys: Vec<ColorKey>,
+    pub rows: Vec<InstructionRow>,
+}
+
+/// Returns the letters that identify the color with the given index: `A` to `Z`, then `AA`, `AB`,
+/// and so on
+pub fn letter_key(index: usize) -> String {
+    let mut result = Vec::new();
+    let mut remaining = index + 1;
+    while remaining > 0 {
+        remaining -= 1;
+        result.push(b'A' + (remaining % 26) as u8);
+        remaining /= 26;
+    }
+    result.reverse();
+    String::from_utf8(result).unwrap()
+}
+
+impl Instructions {
+    /// Collects the instructions of the given image. Colors are listed in the given order, which
+    /// should match the legend, and get a letter key each. Transparent cells are called `blank`.
+    pub fn new(
+        title: &str,
+        image: &Bitmap,
+        colors: &[(PixelRGBA, String, usize)],
+        setup: &InstructionsSetup,
+    ) -> Instructions {
+        let mut keys = Vec::new();
+        let mut key_of_color = HashMap::new();
+        for (color, label, stitch_count) in colors.iter().filter(|(color, _, _)| color.a != 0) {
+            let key = letter_key(keys.len());
+            key_of_color.insert(*color, key.clone());
+            keys.push(ColorKey {
+                key,
+                label: label.clone(),
+                stitch_count: *stitch_count,
+            });
+        }
+        let key_at = |x: i32, y: i32| {
+            let color = image.get(x, y);
+            if color.a == 0 {
+                BLANK_KEY.to_owned()
+            } else {
+                key_of_color[&color].clone()
+            }
+        };
+
+        // NOTE: The first row is at the bottom of the image and worked from right to left, like
+        //       the right side rows of our knitting charts
+        let mut rows = Vec::new();
+        match setup.order {
+            InstructionOrder::Rows => {
+                for number in 1..=image.height as usize {
+                    let y = image.height - number as i32;
+                    let direction = if setup.alternate_direction && number % 2 == 0 {
+                        WorkDirection::LeftToRight
+                    } else {
+                        WorkDirection::RightToLeft
+                    };
+                    let cells: Vec<String> = match direction {
+                        WorkDirection::LeftToRight => (0..image.width).map(|x| key_at(x, y)).collect(),
+                        _ => (0..image.width).rev().map(|x| key_at(x, y)).collect(),
+                    };
+                    rows.push(InstructionRow {
+                        number,
+                        direction,
+                        runs: run_length_encoded(&cells),
+                    });
+                }
+            }
+            InstructionOrder::CornerToCorner => {
+                // NOTE: Diagonal `d` contains all cells that are `d` steps away from the bottom
+                //       left corner. Going upwards along a diagonal means going up and left.
+                let diagonal_count = (image.width + image.height - 1) as usize;
+                for number in 1..=diagonal_count {
+                    let diagonal = number as i32 - 1;
+                    let direction = if setup.alternate_direction && number % 2 == 0 {
+                        WorkDirection::Downwards
+                    } else {
+                        WorkDirection::Upwards
+                    };
+                    let height_min = i32::max(0, diagonal - image.width + 1);
+                    let height_max = i32::min(diagonal, image.height - 1);
+                    let cell_at_height = |height: i32| {
+                        key_at(diagonal - height, image.height - 1 - height)
+                    };
+                    let cells: Vec<String> = match direction {
+                        WorkDirection::Downwards => {
+                            (height_min..=height_max).rev().map(cell_at_height).collect()
+                        }
+                        _ => (height_min..=height_max).map(cell_at_height).collect(),
+                    };
+                    rows.push(InstructionRow {
+                        number,
+                        direction,
+                        runs: run_length_encoded(&cells),
+                    });
+                }
+            }
+        }
+
+        Instructions {
+            title: title.to_owned(),
+            order: setup.order,
+            keys,
+            rows,
+        }
+    }
+
+    fn order_description(&self) -> &'static str {
+        match self.order {
+            InstructionOrder::Rows => "Rows are counted from the bottom of the chart.",
+            InstructionOrder::CornerToCorner => {
+                "Corner to corner: rows are the diagonals counted from the bottom left corner."
+            }
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut result = format!("{}\n\n{}\n\nColors:\n", self.title, self.order_description());
+        for key in &self.keys {
+            result += &format!("  {:<4} {} ({} stitches)\n", key.key, key.label, key.stitch_count);
+        }
+        result += "\n";
+        for row in &self.rows {
+            result += &row.to_text();
+            result += "\n";
+        }
+        result
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut result = format!(
+            "# {}\n\n{}\n\n## Colors\n\n| Key | Color | Stitches |\n|---|---|---|\n",
+            self.title,
+            self.order_description()
+        );
+        for key in &self.keys {
+            // NOTE: Palette names may contain pipes which would end the table cell
+            result += &format!(
+                "| {} | {} | {} |\n",
+                key.key,
+                key.label.replace('|', "\\|"),
+                key.stitch_count
+            );
+        }
+        result += "\n## Rows\n\n";
+        for row in &self.rows {
+            result += &format!(
+                "- **Row {}** ({}): {}\n",
+                row.number,
+                row.direction.description(),
+                row.runs_text()
+            );
+        }
+        result
+    }
+
+    /// Creates a PDF document of the plain text instructions on paper of the given size. Without
+    /// a paper size we use A4.
+    pub fn to_pdf(&self, paper_size_mm: Option<(f32, f32)>, margin_mm: f32) -> Vec<u8> {
+        let (paper_width_mm, paper_height_mm) = paper_size_mm.unwrap_or(PDF_PAPER_SIZE_DEFAULT_MM);
+        let page_width = paper_width_mm * PDF_POINTS_PER_MILLIMETER;
+        let page_height = paper_height_mm * PDF_POINTS_PER_MILLIMETER;
+        let margin = margin_mm * PDF_POINTS_PER_MILLIMETER;
+
+        let line_charcount_max = usize::max(20, ((page_width - 2.0 * margin) / PDF_GLYPH_WIDTH) as usize);
+        let wrap_options = textwrap::Options::new(line_charcount_max).subsequent_indent("    ");
+        let lines: Vec<String> = self
+            .to_text()
+            .lines()
+            .flat_map(|line| {
+                if line.is_empty() {
+                    vec![String::new()]
+                } else {
+                    textwrap::wrap(line, &wrap_options)
+                        .into_iter()
+                        .map(|wrapped| wrapped.into_owned())
+                        .collect()
+                }
+            })
+            .collect();
+
+        let lines_per_page = usize::max(1, ((page_height - 2.0 * margin) / PDF_LINE_HEIGHT) as usize);
+        let pages: Vec<&[String]> = lines.chunks(lines_per_page).collect();
+        create_text_pdf(&pages, page_width, page_height, margin)
+    }
+}
+
+/// Merges neighbouring equal keys into `(count, key)` runs
+fn run_length_encoded(cells: &[String]) -> Vec<(usize, String)> {
+    let mut result: Vec<(usize, String)> = Vec::new();
+    for cell in cells {
+        match result.last_mut() {
+            Some((count, key)) if key == cell => *count += 1,
+            _ => result.push((1, cell.clone())),
+        }
+    }
+    result
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// PDF
+
+/// Writes a minimal PDF with one page per given chunk of lines, set in the standard Courier font
+/// which every PDF reader has built in.
+///
+/// NOTE: The standard fonts only cover latin characters, so we replace everything that is not
+///       printable ASCII
+fn create_text_pdf(pages: &[&[String]], page_width: f32, page_height: f32, margin: f32) -> Vec<u8> {
+    // Object 1 is the catalog, 2 the page tree and 3 the font. Every page has a page and a
+    // content object after that.
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    let page_ids: Vec<String> = (0..pages.len())
+        .map(|page_index| format!("{} 0 R", 4 + 2 * page_index))
+        .collect();
+    objects.push(
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids.join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+    );
+    objects.push(b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier >>".to_vec());
+
+    for (page_index, lines) in pages.iter().enumerate() {
+        let mut content = format!(
+            "BT\n/F1 {} Tf\n{} TL\n{:.2} {:.2} Td\n",
+            PDF_FONT_SIZE,
+            PDF_LINE_HEIGHT,
+            margin,
+            page_height - margin - PDF_FONT_SIZE
+        );
+        for line in lines.iter() {
+            let escaped: String = line
+                .chars()
+                .map(|character| if (' '..='~').contains(&character) { character } else { '?' })
+                .flat_map(|character| match character {
+                    '(' | ')' | '\\' => vec!['\\', character],
+                    _ => vec![character],
+                })
+                .collect();
+            content += &format!("({}) Tj T*\n", escaped);
+        }
+        content += "ET\n";
+
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                page_width,
+                page_height,
+                5 + 2 * page_index
+            )
+            .into_bytes(),
+        );
+        let mut content_object = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        content_object.extend_from_slice(content.as_bytes());
+        content_object.extend_from_slice(b"endstream");
+        objects.push(content_object);
+    }
+
+    let mut result = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (object_index, object) in objects.iter().enumerate() {
+        offsets.push(result.len());
+        result.extend_from_slice(format!("{} 0 obj\n", object_index + 1).as_bytes());
+        result.extend_from_slice(object);
+        result.extend_from_slice(b"\nendobj\n");
+    }
+    let xref_offset = result.len();
+    result.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        result.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    result.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref_offset
+        )
+        .as_bytes(),
+    );
+    result
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_rows_and_diagonals_as_color_runs() {
+        let red = PixelRGBA::new(255, 0, 0, 255);
+        let blue = PixelRGBA::new(0, 0, 255, 255);
+        let mut image = Bitmap::new_filled(4, 2, red);
+        image.set(0, 1, blue);
+        image.set(3, 0, PixelRGBA::transparent());
+        let colors = vec![(red, "DMC 321".to_owned(), 6), (blue, "DMC 820".to_owned(), 1)];
+
+        let rows = Instructions::new("test", &image, &colors, &InstructionsSetup::default()).rows;
+        assert_eq!(rows[0].to_text(), "Row 1 (from right): 3 A, 1 B");
+        assert_eq!(rows[1].to_text(), "Row 2 (from left): 3 A, 1 blank");
+
+        let setup = InstructionsSetup {
+            order: InstructionOrder::CornerToCorner,
+            alternate_direction: false,
+            ..InstructionsSetup::default()
+        };
+        let diagonals = Instructions::new("test", &image, &colors, &setup).rows;
+        assert_eq!(diagonals.len(), 5);
+        assert_eq!(diagonals[0].to_text(), "Row 1 (upwards): 1 B");
+        assert_eq!(diagonals[1].to_text(), "Row 2 (upwards): 2 A");
+        assert_eq!(diagonals[4].to_text(), "Row 5 (upwards): 1 blank");
+
+        assert_eq!(letter_key(0), "A");
+        assert_eq!(letter_key(25), "Z");
+        assert_eq!(letter_key(26), "AA");
+    }
+
+    #[test]
+    fn escapes_pipes_in_markdown_color_table() {
+        let red = PixelRGBA::new(255, 0, 0, 255);
+        let image = Bitmap::new_filled(2, 1, red);
+        let colors = vec![(red, "Red | Rot".to_owned(), 2)];
+
+        let markdown =
+            Instructions::new("test", &image, &colors, &InstructionsSetup::default()).to_markdown();
+        assert!(markdown.contains("| A | Red \\| Rot | 2 |\n"));
+    }
+}